@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+CREATE TABLE "refresh_tokens"
+(
+    "id"          UUID      NOT NULL PRIMARY KEY,
+    "user_id"     UUID      NOT NULL,
+    "family_id"   UUID      NOT NULL,
+    "token_hash"  VARCHAR   NOT NULL UNIQUE,
+    "expires_at"  TIMESTAMP NOT NULL,
+    "consumed_at" TIMESTAMP,
+    "revoked_at"  TIMESTAMP,
+    "created_at"  TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "refresh_tokens_family_id_idx" ON "refresh_tokens" ("family_id");
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+CREATE TABLE "revoked_tokens"
+(
+    "jti"        UUID      NOT NULL PRIMARY KEY,
+    "user_id"    UUID      NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    "revoked_at" TIMESTAMP NOT NULL
+);
+
+CREATE TABLE "user_token_revocations"
+(
+    "user_id"        UUID      NOT NULL PRIMARY KEY,
+    "revoked_before" TIMESTAMP NOT NULL
+);
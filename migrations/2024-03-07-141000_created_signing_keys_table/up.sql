@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE "signing_keys"
+(
+    "kid"          VARCHAR   NOT NULL PRIMARY KEY,
+    "algorithm"    VARCHAR   NOT NULL,
+    "private_key"  TEXT      NOT NULL,
+    "created_at"   TIMESTAMP NOT NULL,
+    "activated_at" TIMESTAMP,
+    "retired_at"   TIMESTAMP
+);
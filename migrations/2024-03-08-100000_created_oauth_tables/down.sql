@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS "authorization_codes";
+DROP TABLE IF EXISTS "oauth_clients";
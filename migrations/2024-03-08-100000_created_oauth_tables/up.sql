@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+CREATE TABLE "oauth_clients"
+(
+    "id"            UUID      NOT NULL PRIMARY KEY,
+    "name"          VARCHAR   NOT NULL,
+    "redirect_uris" TEXT[]    NOT NULL,
+    "created_at"    TIMESTAMP NOT NULL,
+    "updated_at"    TIMESTAMP
+);
+
+CREATE TABLE "authorization_codes"
+(
+    "id"                    UUID      NOT NULL PRIMARY KEY,
+    "code_hash"             VARCHAR   NOT NULL UNIQUE,
+    "client_id"             UUID      NOT NULL,
+    "user_id"               UUID      NOT NULL,
+    "redirect_uri"          VARCHAR   NOT NULL,
+    "code_challenge"        VARCHAR   NOT NULL,
+    "code_challenge_method" VARCHAR   NOT NULL,
+    "expires_at"            TIMESTAMP NOT NULL,
+    "consumed_at"           TIMESTAMP,
+    "created_at"            TIMESTAMP NOT NULL,
+    FOREIGN KEY ("client_id") REFERENCES "oauth_clients" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
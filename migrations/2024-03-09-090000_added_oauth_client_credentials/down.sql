@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE "oauth_clients" DROP COLUMN "scopes";
+ALTER TABLE "oauth_clients" DROP COLUMN "client_secret";
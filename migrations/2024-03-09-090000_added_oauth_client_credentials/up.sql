@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE "oauth_clients"
+    ADD COLUMN "client_secret" VARCHAR;
+ALTER TABLE "oauth_clients"
+    ADD COLUMN "scopes" TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE "authorization_codes" DROP COLUMN "nonce";
+ALTER TABLE "authorization_codes" DROP COLUMN "scope";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE "authorization_codes"
+    ADD COLUMN "scope" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "authorization_codes"
+    ADD COLUMN "nonce" VARCHAR;
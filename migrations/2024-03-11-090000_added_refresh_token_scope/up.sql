@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+ALTER TABLE "refresh_tokens"
+    ADD COLUMN "scope" VARCHAR;
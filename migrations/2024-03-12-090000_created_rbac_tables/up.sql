@@ -0,0 +1,62 @@
+-- Your SQL goes here
+
+CREATE TABLE "roles"
+(
+    "id"          UUID      NOT NULL PRIMARY KEY,
+    "name"        VARCHAR   NOT NULL UNIQUE,
+    "description" VARCHAR,
+    "created_at"  TIMESTAMP NOT NULL,
+    "updated_at"  TIMESTAMP
+);
+
+CREATE TABLE "permissions"
+(
+    "id"          UUID      NOT NULL PRIMARY KEY,
+    "name"        VARCHAR   NOT NULL UNIQUE,
+    "description" VARCHAR,
+    "created_at"  TIMESTAMP NOT NULL,
+    "updated_at"  TIMESTAMP
+);
+
+CREATE TABLE "role_permissions"
+(
+    "role_id"       UUID NOT NULL,
+    "permission_id" UUID NOT NULL,
+    PRIMARY KEY ("role_id", "permission_id"),
+    FOREIGN KEY ("role_id") REFERENCES "roles" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("permission_id") REFERENCES "permissions" ("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "user_roles"
+(
+    "user_id" UUID NOT NULL,
+    "role_id" UUID NOT NULL,
+    PRIMARY KEY ("user_id", "role_id"),
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE,
+    FOREIGN KEY ("role_id") REFERENCES "roles" ("id") ON DELETE CASCADE
+);
+
+-- Permissions checked by the service itself
+INSERT INTO "permissions" ("id", "name", "description", "created_at")
+VALUES (gen_random_uuid(), 'roles:manage', 'Manage roles, permissions and their assignments', NOW()),
+       (gen_random_uuid(), 'keys:manage', 'Manage token signing keys', NOW()),
+       (gen_random_uuid(), 'oauth_clients:manage', 'Manage OAuth clients', NOW()),
+       (gen_random_uuid(), 'sessions:revoke', 'Revoke the sessions of any user', NOW()),
+       (gen_random_uuid(), 'users:write', 'Grants the users:write scope', NOW()),
+       (gen_random_uuid(), 'schools:write', 'Grants the schools:write scope', NOW());
+
+INSERT INTO "roles" ("id", "name", "description", "created_at")
+VALUES (gen_random_uuid(), 'admin', 'Administrators', NOW()),
+       (gen_random_uuid(), 'user', 'Regular users', NOW());
+
+INSERT INTO "role_permissions" ("role_id", "permission_id")
+SELECT "roles"."id", "permissions"."id"
+FROM "roles",
+     "permissions"
+WHERE "roles"."name" = 'admin';
+
+INSERT INTO "user_roles" ("user_id", "role_id")
+SELECT "users"."id", "roles"."id"
+FROM "users",
+     "roles"
+WHERE "roles"."name" = CASE WHEN "users"."is_admin" THEN 'admin' ELSE 'user' END;
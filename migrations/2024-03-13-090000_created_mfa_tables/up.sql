@@ -0,0 +1,36 @@
+-- Your SQL goes here
+
+CREATE TABLE "totp_factors"
+(
+    "user_id"        UUID      NOT NULL PRIMARY KEY,
+    "secret"         VARCHAR   NOT NULL,
+    "confirmed_at"   TIMESTAMP,
+    "last_used_step" BIGINT,
+    "created_at"     TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "recovery_codes"
+(
+    "id"         UUID      NOT NULL PRIMARY KEY,
+    "user_id"    UUID      NOT NULL,
+    "code_hash"  VARCHAR   NOT NULL,
+    "used_at"    TIMESTAMP,
+    "created_at" TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "mfa_challenges"
+(
+    "id"          UUID      NOT NULL PRIMARY KEY,
+    "token_hash"  VARCHAR   NOT NULL UNIQUE,
+    "user_id"     UUID      NOT NULL,
+    "attempts"    INTEGER   NOT NULL DEFAULT 0,
+    "expires_at"  TIMESTAMP NOT NULL,
+    "consumed_at" TIMESTAMP,
+    "created_at"  TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+
+ALTER TABLE "roles"
+    ADD COLUMN "mfa_required" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,27 @@
+-- Your SQL goes here
+
+CREATE TABLE "webauthn_credentials"
+(
+    "id"            UUID      NOT NULL PRIMARY KEY,
+    "user_id"       UUID      NOT NULL,
+    "credential_id" VARCHAR   NOT NULL UNIQUE,
+    "public_key"    VARCHAR   NOT NULL,
+    "sign_count"    BIGINT    NOT NULL DEFAULT 0,
+    "transports"    VARCHAR,
+    "name"          VARCHAR,
+    "created_at"    TIMESTAMP NOT NULL,
+    "last_used_at"  TIMESTAMP,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+
+CREATE TABLE "webauthn_challenges"
+(
+    "id"             UUID      NOT NULL PRIMARY KEY,
+    "challenge_hash" VARCHAR   NOT NULL UNIQUE,
+    "user_id"        UUID,
+    "ceremony"       VARCHAR   NOT NULL,
+    "expires_at"     TIMESTAMP NOT NULL,
+    "consumed_at"    TIMESTAMP,
+    "created_at"     TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
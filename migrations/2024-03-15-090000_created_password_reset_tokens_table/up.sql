@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+CREATE TABLE "password_reset_tokens"
+(
+    "id"         UUID      NOT NULL PRIMARY KEY,
+    "token_hash" VARCHAR   NOT NULL UNIQUE,
+    "user_id"    UUID      NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    "used_at"    TIMESTAMP,
+    "created_at" TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+CREATE TABLE "mail_outbox"
+(
+    "id"              UUID      NOT NULL PRIMARY KEY,
+    "recipient"       VARCHAR   NOT NULL,
+    "subject"         VARCHAR   NOT NULL,
+    "text_body"       TEXT      NOT NULL,
+    "html_body"       TEXT,
+    "attempts"        INTEGER   NOT NULL DEFAULT 0,
+    "last_error"      VARCHAR,
+    "next_attempt_at" TIMESTAMP NOT NULL,
+    "sent_at"         TIMESTAMP,
+    "failed_at"       TIMESTAMP,
+    "created_at"      TIMESTAMP NOT NULL
+);
+
+CREATE INDEX "mail_outbox_pending_idx" ON "mail_outbox" ("next_attempt_at")
+    WHERE "sent_at" IS NULL AND "failed_at" IS NULL;
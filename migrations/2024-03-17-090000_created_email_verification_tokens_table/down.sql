@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS "email_verification_tokens";
+
+ALTER TABLE "users"
+    DROP COLUMN "email_verified_at";
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+ALTER TABLE "users"
+    ADD COLUMN "email_verified_at" TIMESTAMP;
+
+-- Accounts created before verification existed keep working
+UPDATE "users"
+SET "email_verified_at" = "created_at";
+
+CREATE TABLE "email_verification_tokens"
+(
+    "id"         UUID      NOT NULL PRIMARY KEY,
+    "token_hash" VARCHAR   NOT NULL UNIQUE,
+    "user_id"    UUID      NOT NULL,
+    "email"      VARCHAR   NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    "used_at"    TIMESTAMP,
+    "created_at" TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
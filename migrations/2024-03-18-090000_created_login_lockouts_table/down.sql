@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM "permissions"
+WHERE "name" = 'lockouts:manage';
+
+DROP TABLE IF EXISTS "login_lockouts";
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+CREATE TABLE "login_lockouts"
+(
+    "id"              UUID      NOT NULL PRIMARY KEY,
+    "scope"           VARCHAR   NOT NULL,
+    "key"             VARCHAR   NOT NULL,
+    "failures"        INTEGER   NOT NULL DEFAULT 0,
+    "last_failure_at" TIMESTAMP NOT NULL,
+    "locked_until"    TIMESTAMP,
+    "created_at"      TIMESTAMP NOT NULL,
+    UNIQUE ("scope", "key")
+);
+
+INSERT INTO "permissions" ("id", "name", "description", "created_at")
+VALUES (gen_random_uuid(), 'lockouts:manage', 'Unlock accounts and addresses locked after failed logins', NOW());
+
+INSERT INTO "role_permissions" ("role_id", "permission_id")
+SELECT "roles"."id", "permissions"."id"
+FROM "roles",
+     "permissions"
+WHERE "roles"."name" = 'admin'
+  AND "permissions"."name" = 'lockouts:manage';
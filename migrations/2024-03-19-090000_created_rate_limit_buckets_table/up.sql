@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+CREATE TABLE "rate_limit_buckets"
+(
+    "key"        VARCHAR          NOT NULL PRIMARY KEY,
+    "tokens"     DOUBLE PRECISION NOT NULL,
+    "allowed"    BOOLEAN          NOT NULL,
+    "updated_at" TIMESTAMP        NOT NULL
+);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS "password_history";
+
+ALTER TABLE "users"
+    DROP COLUMN "password_changed_at";
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+-- The age of existing passwords is counted from this migration
+ALTER TABLE "users"
+    ADD COLUMN "password_changed_at" TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC');
+
+CREATE TABLE "password_history"
+(
+    "id"            UUID      NOT NULL PRIMARY KEY,
+    "user_id"       UUID      NOT NULL,
+    "password_hash" VARCHAR   NOT NULL,
+    "created_at"    TIMESTAMP NOT NULL,
+    FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+
+CREATE INDEX "password_history_user_id_created_at_idx" ON "password_history" ("user_id", "created_at");
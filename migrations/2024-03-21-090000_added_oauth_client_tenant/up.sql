@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+ALTER TABLE "oauth_clients"
+    ADD COLUMN "tenant_id" UUID REFERENCES "schools" ("id") ON DELETE SET NULL;
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+INSERT INTO "permissions" ("id", "name", "description", "created_at")
+VALUES (gen_random_uuid(), 'users:write', 'Grants the users:write scope', NOW());
+
+INSERT INTO "role_permissions" ("role_id", "permission_id")
+SELECT "roles"."id", "permissions"."id"
+FROM "roles",
+     "permissions"
+WHERE "roles"."name" = 'admin'
+  AND "permissions"."name" = 'users:write';
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- Every user is entitled to the users:write scope, the ownership policy limits them to their account
+DELETE
+FROM "permissions"
+WHERE "name" = 'users:write';
@@ -26,6 +26,378 @@ pub struct AuthConfig {
     pub token_expire_minutes: i64,
     pub issuer: String,
     pub audience: String,
+    #[serde(default = "default_refresh_token_expire_days")]
+    pub refresh_token_expire_days: i64,
+    #[serde(default = "default_revocation_cache_seconds")]
+    pub revocation_cache_seconds: u64,
+    #[serde(default = "default_signing_algorithm")]
+    pub signing_algorithm: String,
+    pub signing_key_path: Option<String>,
+    pub signing_key_id: Option<String>,
+    #[serde(default = "default_key_refresh_seconds")]
+    pub key_refresh_seconds: u64,
+    #[serde(default = "default_leeway_seconds")]
+    pub leeway_seconds: u64,
+    #[serde(default = "default_authorization_code_expire_seconds")]
+    pub authorization_code_expire_seconds: i64,
+    #[serde(default = "default_mfa_challenge_expire_seconds")]
+    pub mfa_challenge_expire_seconds: i64,
+    #[serde(default = "default_totp_issuer")]
+    pub totp_issuer: String,
+    #[serde(default = "default_webauthn_rp_id")]
+    pub webauthn_rp_id: String,
+    #[serde(default = "default_webauthn_rp_name")]
+    pub webauthn_rp_name: String,
+    #[serde(default = "default_webauthn_origin")]
+    pub webauthn_origin: String,
+    #[serde(default = "default_webauthn_challenge_expire_seconds")]
+    pub webauthn_challenge_expire_seconds: i64,
+    #[serde(default = "default_password_reset_expire_minutes")]
+    pub password_reset_expire_minutes: i64,
+    #[serde(default = "default_password_reset_url")]
+    pub password_reset_url: String,
+    #[serde(default)]
+    pub email_verification_required: bool,
+    #[serde(default = "default_email_verification_expire_hours")]
+    pub email_verification_expire_hours: i64,
+    #[serde(default = "default_email_verification_url")]
+    pub email_verification_url: String,
+    #[serde(default = "default_lockout_account_threshold")]
+    pub lockout_account_threshold: i32,
+    #[serde(default = "default_lockout_ip_threshold")]
+    pub lockout_ip_threshold: i32,
+    #[serde(default = "default_lockout_window_seconds")]
+    pub lockout_window_seconds: i64,
+    #[serde(default = "default_lockout_backoff_seconds")]
+    pub lockout_backoff_seconds: i64,
+    #[serde(default = "default_lockout_seconds")]
+    pub lockout_seconds: i64,
+    #[serde(default = "default_lockout_max_seconds")]
+    pub lockout_max_seconds: i64,
+    #[serde(default = "default_password_hash_memory_kib")]
+    pub password_hash_memory_kib: u32,
+    #[serde(default = "default_password_hash_time_cost")]
+    pub password_hash_time_cost: u32,
+    #[serde(default = "default_password_hash_parallelism")]
+    pub password_hash_parallelism: u32,
+    /// Number of last passwords, the current one included, that can not be reused
+    #[serde(default = "default_password_history_size")]
+    pub password_history_size: i64,
+    /// Days after which a password must be changed, never when zero
+    #[serde(default)]
+    pub password_max_age_days: i64,
+}
+
+/// Settings without a default, such as the secret key, are left empty
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            secret_key: String::new(),
+            token_expire_minutes: 0,
+            issuer: String::new(),
+            audience: String::new(),
+            refresh_token_expire_days: default_refresh_token_expire_days(),
+            revocation_cache_seconds: default_revocation_cache_seconds(),
+            signing_algorithm: default_signing_algorithm(),
+            signing_key_path: None,
+            signing_key_id: None,
+            key_refresh_seconds: default_key_refresh_seconds(),
+            leeway_seconds: default_leeway_seconds(),
+            authorization_code_expire_seconds: default_authorization_code_expire_seconds(),
+            mfa_challenge_expire_seconds: default_mfa_challenge_expire_seconds(),
+            totp_issuer: default_totp_issuer(),
+            webauthn_rp_id: default_webauthn_rp_id(),
+            webauthn_rp_name: default_webauthn_rp_name(),
+            webauthn_origin: default_webauthn_origin(),
+            webauthn_challenge_expire_seconds: default_webauthn_challenge_expire_seconds(),
+            password_reset_expire_minutes: default_password_reset_expire_minutes(),
+            password_reset_url: default_password_reset_url(),
+            email_verification_required: false,
+            email_verification_expire_hours: default_email_verification_expire_hours(),
+            email_verification_url: default_email_verification_url(),
+            lockout_account_threshold: default_lockout_account_threshold(),
+            lockout_ip_threshold: default_lockout_ip_threshold(),
+            lockout_window_seconds: default_lockout_window_seconds(),
+            lockout_backoff_seconds: default_lockout_backoff_seconds(),
+            lockout_seconds: default_lockout_seconds(),
+            lockout_max_seconds: default_lockout_max_seconds(),
+            password_hash_memory_kib: default_password_hash_memory_kib(),
+            password_hash_time_cost: default_password_hash_time_cost(),
+            password_hash_parallelism: default_password_hash_parallelism(),
+            password_history_size: default_password_history_size(),
+            password_max_age_days: 0,
+        }
+    }
+}
+
+impl AuthConfig {
+    /**
+     * Lists the accepted audiences. `AUDIENCE` may hold several comma separated values
+     *
+     * @return Vec<String>
+     */
+    pub fn audiences(&self) -> Vec<String> {
+        self.audience
+            .split(',')
+            .map(|audience| audience.trim().to_string())
+            .filter(|audience| !audience.is_empty())
+            .collect()
+    }
+}
+
+fn default_refresh_token_expire_days() -> i64 {
+    30
+}
+
+fn default_revocation_cache_seconds() -> u64 {
+    30
+}
+
+fn default_signing_algorithm() -> String {
+    "HS256".to_string()
+}
+
+fn default_key_refresh_seconds() -> u64 {
+    60
+}
+
+fn default_leeway_seconds() -> u64 {
+    60
+}
+
+fn default_authorization_code_expire_seconds() -> i64 {
+    60
+}
+
+fn default_mfa_challenge_expire_seconds() -> i64 {
+    300
+}
+
+fn default_totp_issuer() -> String {
+    "crud".to_string()
+}
+
+fn default_webauthn_rp_id() -> String {
+    "localhost".to_string()
+}
+
+fn default_webauthn_rp_name() -> String {
+    "crud".to_string()
+}
+
+fn default_webauthn_origin() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_webauthn_challenge_expire_seconds() -> i64 {
+    300
+}
+
+fn default_password_reset_expire_minutes() -> i64 {
+    30
+}
+
+fn default_password_reset_url() -> String {
+    "http://localhost:8080/reset-password".to_string()
+}
+
+fn default_email_verification_expire_hours() -> i64 {
+    24
+}
+
+fn default_email_verification_url() -> String {
+    "http://localhost:8080/verify-email".to_string()
+}
+
+fn default_lockout_account_threshold() -> i32 {
+    5
+}
+
+fn default_lockout_ip_threshold() -> i32 {
+    50
+}
+
+fn default_lockout_window_seconds() -> i64 {
+    900
+}
+
+fn default_lockout_backoff_seconds() -> i64 {
+    1
+}
+
+fn default_lockout_seconds() -> i64 {
+    900
+}
+
+fn default_lockout_max_seconds() -> i64 {
+    86400
+}
+
+fn default_password_hash_memory_kib() -> u32 {
+    19456
+}
+
+fn default_password_hash_time_cost() -> u32 {
+    2
+}
+
+fn default_password_hash_parallelism() -> u32 {
+    1
+}
+
+fn default_password_history_size() -> i64 {
+    5
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MailConfig {
+    #[serde(default = "default_mail_backend")]
+    pub mail_backend: String,
+    #[serde(default = "default_mail_from")]
+    pub mail_from: String,
+    #[serde(default = "default_mail_file_dir")]
+    pub mail_file_dir: String,
+    #[serde(default = "default_smtp_host")]
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// Connects to the SMTP relay without STARTTLS, for a local relay only. Credentials are
+    /// refused in this mode
+    #[serde(default)]
+    pub smtp_plaintext: bool,
+    #[serde(default = "default_mail_max_attempts")]
+    pub mail_max_attempts: i32,
+    #[serde(default = "default_mail_outbox_poll_seconds")]
+    pub mail_outbox_poll_seconds: u64,
+    /// Days after which sent and failed emails are deleted from the outbox
+    #[serde(default = "default_mail_outbox_retention_days")]
+    pub mail_outbox_retention_days: i64,
+}
+
+fn default_mail_backend() -> String {
+    "console".to_string()
+}
+
+fn default_mail_from() -> String {
+    "no-reply@localhost".to_string()
+}
+
+fn default_mail_file_dir() -> String {
+    "mails".to_string()
+}
+
+fn default_smtp_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_mail_max_attempts() -> i32 {
+    5
+}
+
+fn default_mail_outbox_poll_seconds() -> u64 {
+    10
+}
+
+fn default_mail_outbox_retention_days() -> i64 {
+    7
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_rate_limit_enabled")]
+    pub rate_limit_enabled: bool,
+    #[serde(default = "default_rate_limit_backend")]
+    pub rate_limit_backend: String,
+    #[serde(default = "default_rate_limit_policies")]
+    pub rate_limit_policies: String,
+    #[serde(default = "default_rate_limit_purge_seconds")]
+    pub rate_limit_purge_seconds: u64,
+}
+
+fn default_rate_limit_enabled() -> bool {
+    true
+}
+
+fn default_rate_limit_backend() -> String {
+    "postgres".to_string()
+}
+
+fn default_rate_limit_policies() -> String {
+    [
+        "POST /auth/login=ip:10:10",
+        "POST /auth/password/*=ip:5:5",
+        "POST /auth/email/*=ip:5:5",
+        "POST /auth/mfa/*=ip:10:10",
+        "POST /auth/webauthn/*=ip:10:10",
+        "POST /oauth/*=ip:30:60",
+        "POST /users=ip:5:5",
+        "/*=user:120:600",
+    ]
+    .join(",")
+}
+
+fn default_rate_limit_purge_seconds() -> u64 {
+    3600
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasswordPolicyConfig {
+    #[serde(default = "default_password_min_length")]
+    pub password_min_length: usize,
+    #[serde(default = "default_password_max_length")]
+    pub password_max_length: usize,
+    #[serde(default)]
+    pub password_require_lowercase: bool,
+    #[serde(default)]
+    pub password_require_uppercase: bool,
+    #[serde(default)]
+    pub password_require_digit: bool,
+    #[serde(default)]
+    pub password_require_symbol: bool,
+    #[serde(default = "default_password_min_entropy_bits")]
+    pub password_min_entropy_bits: f64,
+    #[serde(default = "default_password_reject_email")]
+    pub password_reject_email: bool,
+    /// File with one breached password per line, checked besides the built-in list
+    pub password_breached_list: Option<String>,
+}
+
+impl Default for PasswordPolicyConfig {
+    fn default() -> Self {
+        Self {
+            password_min_length: default_password_min_length(),
+            password_max_length: default_password_max_length(),
+            password_require_lowercase: false,
+            password_require_uppercase: false,
+            password_require_digit: false,
+            password_require_symbol: false,
+            password_min_entropy_bits: default_password_min_entropy_bits(),
+            password_reject_email: default_password_reject_email(),
+            password_breached_list: None,
+        }
+    }
+}
+
+fn default_password_min_length() -> usize {
+    8
+}
+
+fn default_password_max_length() -> usize {
+    128
+}
+
+fn default_password_min_entropy_bits() -> f64 {
+    30.0
+}
+
+fn default_password_reject_email() -> bool {
+    true
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,6 +406,9 @@ pub struct ApplicationConfig {
     pub logger: LogConfig,
     pub database: DatabaseConfig,
     pub auth: AuthConfig,
+    pub mail: MailConfig,
+    pub rate_limit: RateLimitConfig,
+    pub password_policy: PasswordPolicyConfig,
 }
 
 impl ApplicationConfig {
@@ -42,11 +417,17 @@ impl ApplicationConfig {
         let log_config = envy::from_env::<LogConfig>().unwrap();
         let database_config = envy::from_env::<DatabaseConfig>().unwrap();
         let auth_config = envy::from_env::<AuthConfig>().unwrap();
+        let mail_config = envy::from_env::<MailConfig>().unwrap();
+        let rate_limit_config = envy::from_env::<RateLimitConfig>().unwrap();
+        let password_policy_config = envy::from_env::<PasswordPolicyConfig>().unwrap();
         Self {
             server: server_config,
             logger: log_config,
             database: database_config,
             auth: auth_config,
+            mail: mail_config,
+            rate_limit: rate_limit_config,
+            password_policy: password_policy_config,
         }
     }
 }
@@ -0,0 +1,187 @@
+/**
+ * Minimal CBOR (RFC 8949) decoder, covering what WebAuthn attestation objects and COSE keys use:
+ * integers, byte and text strings, arrays, maps and simple values. Indefinite lengths, tags and
+ * floats are rejected.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CborValue {
+    Integer(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    Map(Vec<(CborValue, CborValue)>),
+    Bool(bool),
+    Null,
+}
+
+impl CborValue {
+    /**
+     * Decodes the first value of the input
+     *
+     * @param input: &[u8]
+     * @return Result<(CborValue, usize), String> the value and the number of bytes it used
+     */
+    pub fn decode(input: &[u8]) -> Result<(CborValue, usize), String> {
+        let mut offset = 0;
+        let value = Self::decode_at(input, &mut offset, 0)?;
+        Ok((value, offset))
+    }
+
+    /**
+     * Gets an entry of a map by integer key, as used by COSE keys
+     *
+     * @param key: i128
+     * @return Option<&CborValue>
+     */
+    pub fn get_int(&self, key: i128) -> Option<&CborValue> {
+        self.get(&CborValue::Integer(key))
+    }
+
+    /**
+     * Gets an entry of a map by text key
+     *
+     * @param key: &str
+     * @return Option<&CborValue>
+     */
+    pub fn get_text(&self, key: &str) -> Option<&CborValue> {
+        self.get(&CborValue::Text(key.to_string()))
+    }
+
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            CborValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            CborValue::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            CborValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn get(&self, key: &CborValue) -> Option<&CborValue> {
+        match self {
+            CborValue::Map(entries) => entries
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn decode_at(input: &[u8], offset: &mut usize, depth: usize) -> Result<CborValue, String> {
+        if depth > 16 {
+            return Err("CBOR nesting too deep".to_string());
+        }
+        let initial = *input.get(*offset).ok_or("Unexpected end of CBOR input")?;
+        *offset += 1;
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+        if major == 7 {
+            return match info {
+                20 => Ok(CborValue::Bool(false)),
+                21 => Ok(CborValue::Bool(true)),
+                22 => Ok(CborValue::Null),
+                _ => Err(format!("Unsupported CBOR simple value {}", info)),
+            };
+        }
+        let argument = Self::argument(input, offset, info)?;
+        match major {
+            0 => Ok(CborValue::Integer(argument as i128)),
+            1 => Ok(CborValue::Integer(-1 - argument as i128)),
+            2 => Ok(CborValue::Bytes(
+                Self::take(input, offset, argument)?.to_vec(),
+            )),
+            3 => String::from_utf8(Self::take(input, offset, argument)?.to_vec())
+                .map(CborValue::Text)
+                .map_err(|_| "Invalid CBOR text string".to_string()),
+            4 => {
+                let mut items = Vec::new();
+                for _ in 0..argument {
+                    items.push(Self::decode_at(input, offset, depth + 1)?);
+                }
+                Ok(CborValue::Array(items))
+            }
+            5 => {
+                let mut entries = Vec::new();
+                for _ in 0..argument {
+                    let key = Self::decode_at(input, offset, depth + 1)?;
+                    let value = Self::decode_at(input, offset, depth + 1)?;
+                    entries.push((key, value));
+                }
+                Ok(CborValue::Map(entries))
+            }
+            _ => Err(format!("Unsupported CBOR major type {}", major)),
+        }
+    }
+
+    fn argument(input: &[u8], offset: &mut usize, info: u8) -> Result<u64, String> {
+        let length = match info {
+            0..=23 => return Ok(info as u64),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err("Unsupported CBOR length".to_string()),
+        };
+        Ok(Self::take(input, offset, length)?
+            .iter()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64))
+    }
+
+    fn take<'a>(input: &'a [u8], offset: &mut usize, length: u64) -> Result<&'a [u8], String> {
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| offset.checked_add(length))
+            .filter(|end| *end <= input.len())
+            .ok_or("Unexpected end of CBOR input")?;
+        let bytes = &input[*offset..end];
+        *offset = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decode() {
+        // {"fmt": "none", 1: -7, "a": [h'0102', true, null]} followed by a trailing byte
+        let input = [
+            0xa3, 0x63, b'f', b'm', b't', 0x64, b'n', b'o', b'n', b'e', 0x01, 0x26, 0x61, b'a',
+            0x83, 0x42, 0x01, 0x02, 0xf5, 0xf6, 0xff,
+        ];
+        let (value, used) = CborValue::decode(&input).unwrap();
+        assert_eq!(used, input.len() - 1);
+        assert_eq!(
+            value.get_text("fmt").and_then(|v| v.as_text()),
+            Some("none")
+        );
+        assert_eq!(value.get_int(1).and_then(|v| v.as_integer()), Some(-7));
+        assert_eq!(
+            value.get_text("a"),
+            Some(&CborValue::Array(vec![
+                CborValue::Bytes(vec![1, 2]),
+                CborValue::Bool(true),
+                CborValue::Null,
+            ]))
+        );
+
+        assert_eq!(
+            CborValue::decode(&[0x19, 0x01, 0x00]).unwrap(),
+            (CborValue::Integer(256), 3)
+        );
+        assert!(CborValue::decode(&[0x42, 0x01]).is_err());
+        assert!(CborValue::decode(&[0x9f]).is_err());
+    }
+}
@@ -7,14 +7,76 @@ pub enum Identifier {
     Email(String),
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum UserStatus {
     Inactive = 0,
     Active = 1,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum UserRole {
     User = 0,
     Admin = 1,
 }
+
+/// Tenant a caller acts within. Schools are the tenants
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tenant {
+    /// Administrators act across every school
+    All,
+    School(Uuid),
+    /// Callers who are not enrolled in a school yet
+    Unassigned,
+}
+
+impl Tenant {
+    /**
+     * Lists the schools visible to the tenant, or `None` when every school is
+     *
+     * @return Option<Vec<Uuid>>
+     */
+    pub fn school_ids(&self) -> Option<Vec<Uuid>> {
+        match self {
+            Tenant::All => None,
+            Tenant::School(id) => Some(vec![*id]),
+            Tenant::Unassigned => Some(Vec::new()),
+        }
+    }
+
+    /**
+     * Checks whether the school is visible to the tenant
+     *
+     * @param school_id: Uuid
+     * @return bool
+     */
+    pub fn contains(&self, school_id: Uuid) -> bool {
+        match self {
+            Tenant::All => true,
+            Tenant::School(id) => *id == school_id,
+            Tenant::Unassigned => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tenant() {
+        let school_id = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        assert!(Tenant::All.contains(other));
+        assert_eq!(Tenant::All.school_ids(), None);
+        assert!(Tenant::School(school_id).contains(school_id));
+        assert!(!Tenant::School(school_id).contains(other));
+        assert_eq!(
+            Tenant::School(school_id).school_ids(),
+            Some(vec![school_id])
+        );
+        assert!(!Tenant::Unassigned.contains(other));
+        assert_eq!(Tenant::Unassigned.school_ids(), Some(Vec::new()));
+    }
+}
@@ -1,31 +1,331 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum RepositoryError {
+use crate::schemas::error_schemas::ProblemDetails;
+use crate::services::password_policy_service::{PasswordViolation, PasswordViolationDetail};
+
+/// Media type of the error responses, defined in RFC 7807
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/**
+ * Errors of the API, rendered as RFC 7807 problem details. Each variant has a stable `code`
+ * that clients can rely on, unlike the human readable `detail`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// The request is malformed or breaks a rule
+    Validation(String),
+    /// The request is not authenticated, or its credentials are wrong
+    Unauthorized(String),
+    /// The principal is not allowed to do this
+    Forbidden(String),
     NotFound(String),
-    InternalServerError(String),
+    /// The request conflicts with an existing resource
     Conflict(String),
-    Unknown(String),
+    /// The request references a resource that does not exist
+    InvalidReference(String),
+    /// The password breaks the password policy
+    PasswordPolicy(Vec<PasswordViolation>),
+    TooManyRequests {
+        detail: String,
+        retry_after: u64,
+    },
+    /// Unexpected failure. Its message is logged but never returned to the client
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation_failed",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::InvalidReference(_) => "invalid_reference",
+            AppError::PasswordPolicy(_) => "password_policy_violation",
+            AppError::TooManyRequests { .. } => "too_many_requests",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /**
+     * Explanation returned to the client
+     *
+     * @return String
+     */
+    pub fn detail(&self) -> String {
+        match self {
+            AppError::Validation(detail)
+            | AppError::Unauthorized(detail)
+            | AppError::Forbidden(detail)
+            | AppError::NotFound(detail)
+            | AppError::Conflict(detail)
+            | AppError::InvalidReference(detail)
+            | AppError::TooManyRequests { detail, .. } => detail.clone(),
+            AppError::PasswordPolicy(_) => "Password does not meet the policy".to_string(),
+            AppError::Internal(_) => "An unexpected error occurred".to_string(),
+        }
+    }
+
+    /**
+     * Builds the problem details of the error
+     *
+     * @return ProblemDetails
+     */
+    pub fn problem(&self) -> ProblemDetails {
+        let status = self.status_code();
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string(),
+            status: status.as_u16(),
+            detail: self.detail(),
+            code: self.code().to_string(),
+            violations: match self {
+                AppError::PasswordPolicy(violations) => Some(
+                    violations
+                        .iter()
+                        .map(|violation| PasswordViolationDetail {
+                            message: violation.message(),
+                            violation: violation.clone(),
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            },
+        }
+    }
 }
 
-impl From<diesel::result::Error> for RepositoryError {
+/**
+ * Maps the database errors, including the ones the services raise themselves: missing rows are
+ * `404`, unique violations `409`, foreign key violations `422` and check violations `400`.
+ * Messages of constraints defined in the database are replaced, so the schema is not disclosed.
+ */
+impl From<diesel::result::Error> for AppError {
     fn from(e: diesel::result::Error) -> Self {
+        let message =
+            |info: &dyn DatabaseErrorInformation, fallback: &str| match info.constraint_name() {
+                Some(_) => fallback.to_string(),
+                None => info.message().to_string(),
+            };
         match e {
-            diesel::result::Error::NotFound => RepositoryError::NotFound(e.to_string()),
-            _ => RepositoryError::InternalServerError(e.to_string()),
+            diesel::result::Error::NotFound => AppError::NotFound("Not found".to_string()),
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                AppError::Conflict(message(info.as_ref(), "Resource already exists"))
+            }
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                AppError::InvalidReference(message(
+                    info.as_ref(),
+                    "Referenced resource does not exist",
+                ))
+            }
+            diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::CheckViolation | DatabaseErrorKind::NotNullViolation,
+                info,
+            ) => AppError::Validation(message(info.as_ref(), "Invalid value")),
+            e => AppError::Internal(e.to_string()),
         }
     }
 }
 
-impl std::fmt::Display for RepositoryError {
+impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            RepositoryError::NotFound(e) => write!(f, "Not found: {}", e),
-            RepositoryError::InternalServerError(e) => write!(f, "Internal server error: {}", e),
-            RepositoryError::Conflict(e) => write!(f, "Conflict: {}", e),
-            RepositoryError::Unknown(e) => write!(f, "Unknown error: {}", e),
+            AppError::Internal(e) => write!(f, "{}: {}", self.code(), e),
+            _ => write!(f, "{}: {}", self.code(), self.detail()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::InvalidReference(_) | AppError::PasswordPolicy(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let AppError::Internal(e) = self {
+            log::error!("Internal error: {}", e);
+        }
+        let mut response = HttpResponse::build(self.status_code());
+        response.insert_header((header::CONTENT_TYPE, PROBLEM_JSON));
+        if let AppError::TooManyRequests { retry_after, .. } = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+        }
+        response.body(serde_json::to_string(&self.problem()).unwrap_or_default())
+    }
+}
+
+/**
+ * Errors of the OAuth 2.0 endpoints, as defined in RFC 6749 section 5.2
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum OAuthError {
+    InvalidRequest(String),
+    InvalidClient(String),
+    InvalidGrant(String),
+    InvalidScope(String),
+    UnauthorizedClient(String),
+    AccessDenied(String),
+    UnsupportedResponseType(String),
+    UnsupportedGrantType(String),
+    ServerError(String),
+}
+
+impl OAuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest(_) => "invalid_request",
+            OAuthError::InvalidClient(_) => "invalid_client",
+            OAuthError::InvalidGrant(_) => "invalid_grant",
+            OAuthError::InvalidScope(_) => "invalid_scope",
+            OAuthError::UnauthorizedClient(_) => "unauthorized_client",
+            OAuthError::AccessDenied(_) => "access_denied",
+            OAuthError::UnsupportedResponseType(_) => "unsupported_response_type",
+            OAuthError::UnsupportedGrantType(_) => "unsupported_grant_type",
+            OAuthError::ServerError(_) => "server_error",
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            OAuthError::InvalidRequest(e)
+            | OAuthError::InvalidClient(e)
+            | OAuthError::InvalidGrant(e)
+            | OAuthError::InvalidScope(e)
+            | OAuthError::UnauthorizedClient(e)
+            | OAuthError::AccessDenied(e)
+            | OAuthError::UnsupportedResponseType(e)
+            | OAuthError::UnsupportedGrantType(e)
+            | OAuthError::ServerError(e) => e,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for OAuthError {
+    fn from(e: diesel::result::Error) -> Self {
+        OAuthError::ServerError(e.to_string())
+    }
+}
+
+/**
+ * Maps the errors of the shared services: rejected credentials or tokens are `invalid_grant`,
+ * and unexpected failures `server_error`
+ */
+impl From<AppError> for OAuthError {
+    fn from(e: AppError) -> Self {
+        match e {
+            AppError::Unauthorized(detail) => OAuthError::InvalidGrant(detail),
+            AppError::Forbidden(detail) => OAuthError::AccessDenied(detail),
+            AppError::Validation(detail) => OAuthError::InvalidRequest(detail),
+            e => OAuthError::ServerError(e.to_string()),
         }
     }
 }
 
-impl std::error::Error for RepositoryError {}
+impl std::fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.description())
+    }
+}
+
+impl std::error::Error for OAuthError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::body::MessageBody;
+
+    #[tokio::test]
+    async fn test_from_diesel() {
+        assert_eq!(
+            AppError::from(diesel::result::Error::NotFound).code(),
+            "not_found"
+        );
+        assert_eq!(
+            AppError::from(diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::UniqueViolation,
+                Box::new("Email already in use".to_string()),
+            )),
+            AppError::Conflict("Email already in use".to_string())
+        );
+        assert_eq!(
+            AppError::from(diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::ForeignKeyViolation,
+                Box::new("Unknown school".to_string()),
+            )),
+            AppError::InvalidReference("Unknown school".to_string())
+        );
+        assert_eq!(
+            AppError::from(diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::CheckViolation,
+                Box::new("Wrong credentials".to_string()),
+            )),
+            AppError::Validation("Wrong credentials".to_string())
+        );
+        assert_eq!(
+            AppError::from(diesel::result::Error::RollbackTransaction).code(),
+            "internal_error"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let response = AppError::NotFound("User not found".to_string()).error_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_JSON
+        );
+        let body = response.into_body().try_into_bytes().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "User not found",
+                "code": "not_found",
+            })
+        );
+
+        let response = AppError::Internal("connection refused".to_string()).error_response();
+        let body = response.into_body().try_into_bytes().unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("connection refused"));
+
+        let response = AppError::TooManyRequests {
+            detail: "Too many requests".to_string(),
+            retry_after: 30,
+        }
+        .error_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "30");
+    }
+
+    #[tokio::test]
+    async fn test_password_policy_problem() {
+        let problem = AppError::PasswordPolicy(vec![PasswordViolation::Breached]).problem();
+        assert_eq!(problem.status, 422);
+        assert_eq!(problem.code, "password_policy_violation");
+        assert_eq!(problem.violations.unwrap().len(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod cbor;
 pub mod enums;
 pub mod exceptions;
 pub mod logger;
@@ -17,6 +17,26 @@ pub async fn get_connection(
         .unwrap()
 }
 
+/**
+ * Checks whether a path matches a pattern. A pattern ending with a `*` segment matches every
+ * path below it.
+ *
+ * @param path: &str
+ * @param pattern: &str
+ * @return bool
+ */
+pub fn matches_path(path: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => {
+            path == prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        }
+        None => path == pattern,
+    }
+}
+
 // type of variable
 pub fn type_of<T>(_: T) -> &'static str {
     std::any::type_name::<T>()
@@ -1 +1,2 @@
 pub mod repository_interface;
+pub mod tenant_repository_interface;
@@ -0,0 +1,37 @@
+use std::fmt::Debug;
+
+use diesel_async::AsyncPgConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::helper::enums::{Identifier, Tenant};
+use crate::helper::exceptions::AppError;
+
+/**
+ * Repository of records owned by a school. Every read and write is limited to the records of
+ * the given tenant; records of other tenants behave as if they did not exist.
+ */
+pub trait ITenantRepository<'a, T, U, R>
+where
+    T: Debug + Serialize + Deserialize<'a>,
+    U: Debug + Serialize + Deserialize<'a>,
+    R: Debug + Serialize + Deserialize<'a>,
+{
+    type Model;
+    async fn create(conn: &mut AsyncPgConnection, tenant: &Tenant, data: T) -> Result<R, AppError>;
+    async fn get(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+    ) -> Result<Option<R>, AppError>;
+    async fn update(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+        new_data: U,
+    ) -> Result<R, AppError>;
+    async fn delete(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+    ) -> Result<usize, AppError>;
+}
@@ -1,21 +1,43 @@
 use actix_web::{App, HttpResponse, HttpServer, middleware, web};
-use utoipa;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use configs::common::ApplicationConfig;
 use databases::async_postgres::AsyncPostgresPool;
+use helper::exceptions::AppError;
 use helper::logger::initialize_logger;
+use middlewares::auth_middleware::AuthMiddleware;
+use middlewares::rate_limit_middleware::RateLimitMiddleware;
+use middlewares::timer_middleware::TimerMiddleware;
 use routes::auth_routes::AuthRoutes;
 use routes::class_routes::ClassRoutes;
+use routes::email_routes::EmailRoutes;
 use routes::password_routes::PasswordRoutes;
 use routes::schedule_routes::ScheduleRoutes;
 use routes::school_routes::SchoolRoutes;
 use routes::student_routes::StudentRoutes;
 use routes::user_routes::UserRoutes;
+use routes::key_routes::KeyRoutes;
+use routes::lockout_routes::LockoutRoutes;
+use routes::mfa_routes::MfaRoutes;
+use routes::oauth_client_routes::OAuthClientRoutes;
+use routes::oauth_routes::OAuthRoutes;
+use routes::oidc_routes::OidcRoutes;
+use routes::permission_routes::PermissionRoutes;
+use routes::role_routes::RoleRoutes;
+use routes::webauthn_routes::WebAuthnRoutes;
+use services::key_service::KeyService;
+use services::mailer_service::MailerService;
+use services::outbox_service::OutboxService;
+use services::password_policy_service::PasswordPolicyService;
+use services::password_service::PasswordService;
+use services::rate_limit_service::RateLimitService;
+use services::revocation_service::RevocationCache;
 use schemas::user_schemas::{UserCreate, UserResponse, UserUpdate};
 
 use crate::routes::health_routes::health;
+use crate::routes::jwks_routes::jwks;
+use crate::routes::oidc_routes::openid_configuration;
 use crate::routes::user_routes::create_user;
 
 mod configs;
@@ -42,6 +64,40 @@ async fn main() -> std::io::Result<()> {
         .expect("Failed to initialize logger");
 
     log::info!("Logger initialized");
+    PasswordService::configure(&configs.auth).expect("Failed to configure password hashing");
+    PasswordPolicyService::configure(&configs.password_policy)
+        .expect("Failed to configure password policy");
+    let configured_key =
+        KeyService::configured_key(&configs.auth).expect("Failed to load signing key");
+    let key_ring = web::Data::new(
+        KeyService::initialize(&mut pool.pool.get().await.unwrap(), configured_key)
+            .await
+            .expect("Failed to load signing keys"),
+    );
+    actix_web::rt::spawn(KeyService::refresh_periodically(
+        pool.pool.clone(),
+        key_ring.clone(),
+        std::time::Duration::from_secs(configs.auth.key_refresh_seconds),
+    ));
+    let revocation_cache = web::Data::new(RevocationCache::new(std::time::Duration::from_secs(
+        configs.auth.revocation_cache_seconds,
+    )));
+    let mailer = MailerService::configured(&configs.mail).expect("Failed to configure mailer");
+    actix_web::rt::spawn(OutboxService::deliver_periodically(
+        pool.pool.clone(),
+        mailer,
+        configs.mail.clone(),
+    ));
+    let rate_limiter = std::sync::Arc::new(
+        RateLimitService::configured(&configs.rate_limit, pool.pool.clone())
+            .expect("Failed to configure rate limits"),
+    );
+    if configs.rate_limit.rate_limit_backend == "postgres" {
+        actix_web::rt::spawn(RateLimitService::purge_periodically(
+            pool.pool.clone(),
+            std::time::Duration::from_secs(configs.rate_limit.rate_limit_purge_seconds),
+        ));
+    }
     log::info!(
         "Starting server at http://{}:{} ...",
         &configs.server.app_host,
@@ -49,23 +105,75 @@ async fn main() -> std::io::Result<()> {
     );
     #[derive(OpenApi)]
     #[openapi(
-    paths(routes::health_routes::health, routes::user_routes::create_user),
+    paths(
+    routes::health_routes::health,
+    routes::jwks_routes::jwks,
+    routes::oidc_routes::openid_configuration,
+    routes::user_routes::create_user
+    ),
     components(schemas(UserCreate, UserResponse, UserUpdate))
     )]
     struct ApiDoc;
     HttpServer::new(move || {
         App::new()
+            .wrap(RateLimitMiddleware::by_principal(rate_limiter.clone()))
+            .wrap(AuthMiddleware::new(&[
+                "/health",
+                "/docs/*",
+                "/api-docs/*",
+                "/.well-known/*",
+                "/auth/login",
+                "/auth/refresh",
+                "/auth/email/*",
+                "/auth/mfa/*",
+                "/auth/password/*",
+                "/auth/webauthn/*",
+                "/oauth/*",
+                "/users",
+            ]))
+            .wrap(RateLimitMiddleware::by_ip(rate_limiter.clone()))
             .wrap(middleware::Logger::default())
             .wrap(middleware::Logger::new("%a %{User-Agent}i"))
             .wrap(middleware::Compress::default())
-
+            .wrap(TimerMiddleware)
             .app_data(state.clone())
             .app_data(web::Data::new(pool.pool.clone()))
+            .app_data(revocation_cache.clone())
+            .app_data(key_ring.clone())
+            .app_data(web::JsonConfig::default().error_handler(|err, _| {
+                AppError::Validation(err.to_string()).into()
+            }))
+            .app_data(web::PathConfig::default().error_handler(|err, _| {
+                AppError::NotFound(err.to_string()).into()
+            }))
+            .app_data(web::QueryConfig::default().error_handler(|err, _| {
+                AppError::Validation(err.to_string()).into()
+            }))
             .service(
                 SwaggerUi::new("/docs/{_:.*}").url("/api-docs/openapi.json", ApiDoc::openapi()),
             )
             .service(health)
+            .service(jwks)
+            .service(openid_configuration)
             .route("/auth/login", web::post().to(AuthRoutes::login))
+            .route("/auth/refresh", web::post().to(AuthRoutes::refresh))
+            .route("/auth/logout", web::post().to(AuthRoutes::logout))
+            .route("/auth/email/verify", web::post().to(EmailRoutes::verify))
+            .route("/auth/email/resend", web::post().to(EmailRoutes::resend))
+            .route("/auth/password/forgot", web::post().to(PasswordRoutes::forgot))
+            .route("/auth/password/reset", web::post().to(PasswordRoutes::reset))
+            .route("/auth/mfa/verify", web::post().to(MfaRoutes::verify))
+            .route("/auth/mfa/enroll", web::post().to(MfaRoutes::enroll_with_challenge))
+            .route("/auth/mfa/webauthn/options", web::post().to(WebAuthnRoutes::mfa_options))
+            .route("/auth/mfa/webauthn/verify", web::post().to(WebAuthnRoutes::verify_mfa))
+            .route("/auth/webauthn/login/options", web::post().to(WebAuthnRoutes::login_options))
+            .route("/auth/webauthn/login", web::post().to(WebAuthnRoutes::login))
+            .route("/oauth/authorize", web::post().to(OAuthRoutes::authorize))
+            .route("/oauth/token", web::post().to(OAuthRoutes::token))
+            .route("/oauth/introspect", web::post().to(OAuthRoutes::introspect))
+            .route("/oauth/revoke", web::post().to(OAuthRoutes::revoke))
+            .route("/userinfo", web::get().to(OidcRoutes::userinfo))
+            .route("/userinfo", web::post().to(OidcRoutes::userinfo))
             .service(create_user)
             .service(
                 web::scope("/users")
@@ -73,7 +181,65 @@ async fn main() -> std::io::Result<()> {
                     .route("/{id}", web::get().to(UserRoutes::get))
                     .route("/{id}", web::put().to(UserRoutes::update))
                     .route("/{id}", web::delete().to(UserRoutes::delete))
-                    .route("/{id}/password", web::put().to(PasswordRoutes::update)),
+                    .route("/{id}/password", web::put().to(PasswordRoutes::update))
+                    .route("/{id}/email", web::put().to(EmailRoutes::change))
+                    .route("/{id}/sessions", web::delete().to(AuthRoutes::revoke_sessions))
+                    .route("/{id}/roles", web::get().to(RoleRoutes::user_roles))
+                    .route("/{id}/roles/{role_id}", web::post().to(RoleRoutes::assign))
+                    .route("/{id}/roles/{role_id}", web::delete().to(RoleRoutes::unassign))
+                    .route("/{id}/mfa/totp", web::post().to(MfaRoutes::enroll))
+                    .route("/{id}/mfa/totp", web::delete().to(MfaRoutes::disable))
+                    .route("/{id}/mfa/totp/confirm", web::post().to(MfaRoutes::confirm))
+                    .route("/{id}/mfa/recovery-codes", web::post().to(MfaRoutes::recovery_codes))
+                    .route("/{id}/webauthn/register/options", web::post().to(WebAuthnRoutes::registration_options))
+                    .route("/{id}/webauthn/register", web::post().to(WebAuthnRoutes::register))
+                    .route("/{id}/webauthn/credentials", web::get().to(WebAuthnRoutes::list))
+                    .route("/{id}/webauthn/credentials/{credential_id}", web::delete().to(WebAuthnRoutes::delete)),
+            )
+            .service(
+                web::scope("/admin/keys")
+                    .route("", web::get().to(KeyRoutes::list))
+                    .route("", web::post().to(KeyRoutes::create))
+                    .route("/{kid}/promote", web::post().to(KeyRoutes::promote))
+                    .route("/{kid}", web::delete().to(KeyRoutes::retire)),
+            )
+            .service(
+                web::scope("/admin/lockouts")
+                    .route("", web::get().to(LockoutRoutes::list))
+                    .route("/users/{id}", web::delete().to(LockoutRoutes::unlock_user))
+                    .route("/ips/{ip}", web::delete().to(LockoutRoutes::unlock_ip)),
+            )
+            .service(
+                web::scope("/admin/oauth/clients")
+                    .route("", web::post().to(OAuthClientRoutes::create))
+                    .route("/{id}", web::get().to(OAuthClientRoutes::get))
+                    .route("/{id}", web::put().to(OAuthClientRoutes::update))
+                    .route("/{id}", web::delete().to(OAuthClientRoutes::delete)),
+            )
+            .service(
+                web::scope("/admin/roles")
+                    .route("", web::get().to(RoleRoutes::list))
+                    .route("", web::post().to(RoleRoutes::create))
+                    .route("/{id}", web::get().to(RoleRoutes::get))
+                    .route("/{id}", web::put().to(RoleRoutes::update))
+                    .route("/{id}", web::delete().to(RoleRoutes::delete))
+                    .route("/{id}/permissions", web::get().to(RoleRoutes::permissions))
+                    .route(
+                        "/{id}/permissions/{permission_id}",
+                        web::post().to(RoleRoutes::grant_permission),
+                    )
+                    .route(
+                        "/{id}/permissions/{permission_id}",
+                        web::delete().to(RoleRoutes::revoke_permission),
+                    ),
+            )
+            .service(
+                web::scope("/admin/permissions")
+                    .route("", web::get().to(PermissionRoutes::list))
+                    .route("", web::post().to(PermissionRoutes::create))
+                    .route("/{id}", web::get().to(PermissionRoutes::get))
+                    .route("/{id}", web::put().to(PermissionRoutes::update))
+                    .route("/{id}", web::delete().to(PermissionRoutes::delete)),
             )
             .service(
                 web::scope("/schools")
@@ -103,6 +269,9 @@ async fn main() -> std::io::Result<()> {
                     .route("/{id}", web::patch().to(ScheduleRoutes::update))
                     .route("/{id}", web::delete().to(ScheduleRoutes::delete)),
             )
+            .default_service(web::to(|| async {
+                Err::<HttpResponse, _>(AppError::NotFound("Not Found".to_string()))
+            }))
     })
         .bind(format!(
             "{}:{}",
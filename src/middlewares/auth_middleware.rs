@@ -1 +1,112 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
 
+use actix_web::dev::Payload;
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, FromRequest, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::helper::utils::matches_path;
+use crate::services::auth_extractor::AuthExtractorService;
+
+/**
+ * Authenticates every request of the wrapped `App` or `web::scope` and stores the principal in
+ * the request extensions, where `AuthExtractorService` picks it up. Paths of the allowlist are
+ * let through unauthenticated; an entry ending with a `*` segment allows every path below it.
+ */
+pub struct AuthMiddleware {
+    public_paths: Rc<Vec<String>>,
+}
+
+impl AuthMiddleware {
+    pub fn new(public_paths: &[&str]) -> Self {
+        Self {
+            public_paths: Rc::new(public_paths.iter().map(|path| path.to_string()).collect()),
+        }
+    }
+
+    /**
+     * Checks whether a path is on the allowlist
+     *
+     * @param path: &str
+     * @param public_paths: &[String]
+     * @return bool
+     */
+    pub fn is_public(path: &str, public_paths: &[String]) -> bool {
+        public_paths.iter().any(|public| matches_path(path, public))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AuthMiddlewareTransform<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthMiddlewareTransform {
+            service: Rc::new(service),
+            public_paths: self.public_paths.clone(),
+        }))
+    }
+}
+
+pub struct AuthMiddlewareTransform<S> {
+    service: Rc<S>,
+    public_paths: Rc<Vec<String>>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthMiddlewareTransform<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if AuthMiddleware::is_public(req.path(), &self.public_paths) {
+            return Box::pin(self.service.call(req));
+        }
+
+        let service = self.service.clone();
+        let auth = AuthExtractorService::from_request(req.request(), &mut Payload::None);
+
+        Box::pin(async move {
+            let auth = auth.await?;
+            req.extensions_mut().insert(auth);
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_is_public() {
+        let public_paths = vec!["/auth/login".to_string(), "/oauth/*".to_string()];
+        assert!(AuthMiddleware::is_public("/auth/login", &public_paths));
+        assert!(!AuthMiddleware::is_public("/auth/logout", &public_paths));
+        assert!(!AuthMiddleware::is_public(
+            "/auth/login/other",
+            &public_paths
+        ));
+        assert!(AuthMiddleware::is_public("/oauth", &public_paths));
+        assert!(AuthMiddleware::is_public("/oauth/token", &public_paths));
+        assert!(!AuthMiddleware::is_public("/oauthx/token", &public_paths));
+    }
+}
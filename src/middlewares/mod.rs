@@ -1,2 +1,3 @@
 pub mod auth_middleware;
+pub mod rate_limit_middleware;
 pub mod timer_middleware;
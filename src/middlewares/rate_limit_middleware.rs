@@ -0,0 +1,265 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::helper::exceptions::AppError;
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::lockout_service::LockoutService;
+use crate::services::rate_limit_service::{RateLimitKey, RateLimitPolicy, RateLimiter};
+
+/**
+ * Limits the requests of the wrapped `App` or `web::scope` with the token buckets of the first
+ * matching policy. Policies keyed by client address are enforced by a middleware wrapped outside
+ * `AuthMiddleware`, so that the requests it rejects are counted too. Policies keyed by user or
+ * client are enforced by a second one wrapped inside, where the principal is known. Successful
+ * responses carry the `RateLimit-*` headers, and rejected requests get `429 Too Many Requests`
+ * with a `Retry-After` header. When the store is unavailable, requests are let through rather
+ * than taking the API down with it.
+ */
+pub struct RateLimitMiddleware {
+    limiter: Arc<RateLimiter>,
+    by_principal: bool,
+}
+
+impl RateLimitMiddleware {
+    /// Enforces the policies keyed by client address, wrapped outside `AuthMiddleware`
+    pub fn by_ip(limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            limiter,
+            by_principal: false,
+        }
+    }
+
+    /// Enforces the policies keyed by user or client, wrapped inside `AuthMiddleware`
+    pub fn by_principal(limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            limiter,
+            by_principal: true,
+        }
+    }
+
+    /**
+     * Whether a policy is enforced by this middleware
+     *
+     * @param policy: &RateLimitPolicy
+     * @return bool
+     */
+    pub fn enforces(&self, policy: &RateLimitPolicy) -> bool {
+        (policy.key != RateLimitKey::Ip) == self.by_principal
+    }
+
+    /**
+     * Key of the bucket a request is counted against. Anonymous requests fall back to the
+     * client address.
+     *
+     * @param policy: &RateLimitPolicy
+     * @param client_ip: Option<&str>
+     * @param auth: Option<&AuthExtractorService>
+     * @return String
+     */
+    pub fn bucket_key(
+        policy: &RateLimitPolicy,
+        client_ip: Option<&str>,
+        auth: Option<&AuthExtractorService>,
+    ) -> String {
+        let ip = || format!("ip:{}", client_ip.unwrap_or("unknown"));
+        let subject = match (policy.key, auth) {
+            (RateLimitKey::Client, Some(auth)) => match auth.client_id {
+                Some(client_id) => format!("client:{}", client_id),
+                None => format!("user:{}", auth.id),
+            },
+            (RateLimitKey::User, Some(auth)) => format!("user:{}", auth.id),
+            _ => ip(),
+        };
+        format!(
+            "{}{}|{}",
+            policy
+                .method
+                .as_deref()
+                .map(|method| format!("{} ", method))
+                .unwrap_or_default(),
+            policy.pattern,
+            subject
+        )
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimitMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitMiddlewareTransform<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddlewareTransform {
+            service: Rc::new(service),
+            middleware: Rc::new(Self {
+                limiter: self.limiter.clone(),
+                by_principal: self.by_principal,
+            }),
+        }))
+    }
+}
+
+pub struct RateLimitMiddlewareTransform<S> {
+    service: Rc<S>,
+    middleware: Rc<RateLimitMiddleware>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddlewareTransform<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(policy) = self
+            .middleware
+            .limiter
+            .policy_for(req.method().as_str(), req.path())
+            .filter(|policy| self.middleware.enforces(policy))
+            .cloned()
+        else {
+            return Box::pin(self.service.call(req));
+        };
+
+        let service = self.service.clone();
+        let limiter = self.middleware.limiter.clone();
+        let key = RateLimitMiddleware::bucket_key(
+            &policy,
+            LockoutService::client_ip(req.request()).as_deref(),
+            req.extensions().get::<AuthExtractorService>(),
+        );
+
+        Box::pin(async move {
+            let taken = limiter
+                .store
+                .take(&key, policy.capacity as f64, policy.refill_per_second())
+                .await;
+            let bucket = match taken {
+                Ok(bucket) => bucket,
+                Err(e) => {
+                    log::error!("Failed to check rate limit of {}: {}", key, e);
+                    return service.call(req).await;
+                }
+            };
+            if !bucket.allowed {
+                let retry_after = policy.seconds_until(&bucket, 1.0).max(1);
+                log::warn!("Rate limited {} for {}s", key, retry_after);
+                return Err(AppError::TooManyRequests {
+                    detail: "Too many requests".to_string(),
+                    retry_after,
+                }
+                .into());
+            }
+
+            let mut res = service.call(req).await?;
+            let headers = res.headers_mut();
+            for (name, value) in [
+                ("ratelimit-limit", policy.capacity as u64),
+                ("ratelimit-remaining", bucket.tokens.floor() as u64),
+                (
+                    "ratelimit-reset",
+                    policy.seconds_until(&bucket, policy.capacity as f64),
+                ),
+            ] {
+                headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    use crate::services::rate_limit_service::MemoryRateLimitStore;
+
+    #[tokio::test]
+    async fn test_bucket_key() {
+        let auth = AuthExtractorService {
+            jti: Uuid::new_v4(),
+            exp: 0,
+            id: Uuid::new_v4(),
+            tenant_id: None,
+            email: "user@example.com".to_string(),
+            admin: false,
+            active: true,
+            client_id: None,
+            scopes: Vec::new(),
+            permissions: Vec::new(),
+        };
+        let policy = RateLimitPolicy::parse("/*=user:10:10").unwrap();
+        assert_eq!(
+            RateLimitMiddleware::bucket_key(&policy, Some("10.0.0.1"), Some(&auth)),
+            format!("/*|user:{}", auth.id)
+        );
+        assert_eq!(
+            RateLimitMiddleware::bucket_key(&policy, Some("10.0.0.1"), None),
+            "/*|ip:10.0.0.1"
+        );
+
+        let policy = RateLimitPolicy::parse("POST /oauth/*=client:10:10").unwrap();
+        let client_id = Uuid::new_v4();
+        let client = AuthExtractorService {
+            client_id: Some(client_id),
+            ..auth.clone()
+        };
+        assert_eq!(
+            RateLimitMiddleware::bucket_key(&policy, None, Some(&client)),
+            format!("POST /oauth/*|client:{}", client_id)
+        );
+        assert_eq!(
+            RateLimitMiddleware::bucket_key(&policy, None, Some(&auth)),
+            format!("POST /oauth/*|user:{}", auth.id)
+        );
+
+        let policy = RateLimitPolicy::parse("/auth/login=ip:10:10").unwrap();
+        assert_eq!(
+            RateLimitMiddleware::bucket_key(&policy, None, Some(&auth)),
+            "/auth/login|ip:unknown"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enforces() {
+        let limiter = Arc::new(RateLimiter {
+            policies: Vec::new(),
+            store: Arc::new(MemoryRateLimitStore::default()),
+        });
+        let by_ip = RateLimitMiddleware::by_ip(limiter.clone());
+        let by_principal = RateLimitMiddleware::by_principal(limiter);
+
+        let policy = RateLimitPolicy::parse("/auth/login=ip:10:10").unwrap();
+        assert!(by_ip.enforces(&policy));
+        assert!(!by_principal.enforces(&policy));
+
+        for policy in ["/*=user:10:10", "/oauth/*=client:10:10"] {
+            let policy = RateLimitPolicy::parse(policy).unwrap();
+            assert!(!by_ip.enforces(&policy));
+            assert!(by_principal.enforces(&policy));
+        }
+    }
+}
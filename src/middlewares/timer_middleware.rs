@@ -61,7 +61,7 @@ where
             );
 
             res.headers_mut().insert(
-                HeaderName::from_static("X-Response-Time"),
+                HeaderName::from_static("x-response-time"),
                 HeaderValue::from_str(&format!("{}ms", elapsed.as_millis())).unwrap(),
             );
             Ok(res)
@@ -0,0 +1,64 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::authorization_codes;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = authorization_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuthorizationCodeModel {
+    pub id: Uuid,
+    pub code_hash: String,
+    pub client_id: Uuid,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+    pub expires_at: NaiveDateTime,
+    pub consumed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub scope: String,
+    pub nonce: Option<String>,
+}
+
+impl AuthorizationCodeModel {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        code_hash: String,
+        client_id: Uuid,
+        user_id: Uuid,
+        redirect_uri: String,
+        code_challenge: String,
+        code_challenge_method: String,
+        expires_at: NaiveDateTime,
+        scope: String,
+        nonce: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            code_hash,
+            client_id,
+            user_id,
+            redirect_uri,
+            code_challenge,
+            code_challenge_method,
+            expires_at,
+            consumed_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+            scope,
+            nonce,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::email_verification_tokens;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = email_verification_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EmailVerificationTokenModel {
+    pub id: Uuid,
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub email: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl EmailVerificationTokenModel {
+    pub fn new(
+        token_hash: String,
+        user_id: Uuid,
+        email: String,
+        expires_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            token_hash,
+            user_id,
+            email,
+            expires_at,
+            used_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::login_lockouts;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = login_lockouts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoginLockoutModel {
+    pub id: Uuid,
+    pub scope: String,
+    pub key: String,
+    pub failures: i32,
+    pub last_failure_at: NaiveDateTime,
+    pub locked_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl LoginLockoutModel {
+    pub fn new(
+        scope: String,
+        key: String,
+        failures: i32,
+        locked_until: Option<NaiveDateTime>,
+    ) -> Self {
+        let now = chrono::Utc::now().naive_utc();
+        Self {
+            id: Uuid::new_v4(),
+            scope,
+            key,
+            failures,
+            last_failure_at: now,
+            locked_until,
+            created_at: now,
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::mail_outbox;
+use crate::services::mailer_service::Email;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = mail_outbox)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MailOutboxModel {
+    pub id: Uuid,
+    pub recipient: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+    pub failed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl MailOutboxModel {
+    pub fn new(email: Email) -> Self {
+        let now = chrono::Utc::now().naive_utc();
+        Self {
+            id: Uuid::new_v4(),
+            recipient: email.to,
+            subject: email.subject,
+            text_body: email.text_body,
+            html_body: email.html_body,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            sent_at: None,
+            failed_at: None,
+            created_at: now,
+        }
+    }
+
+    pub fn email(&self) -> Email {
+        Email {
+            to: self.recipient.clone(),
+            subject: self.subject.clone(),
+            text_body: self.text_body.clone(),
+            html_body: self.html_body.clone(),
+        }
+    }
+}
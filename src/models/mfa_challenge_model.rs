@@ -0,0 +1,43 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::mfa_challenges;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = mfa_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MfaChallengeModel {
+    pub id: Uuid,
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub attempts: i32,
+    pub expires_at: NaiveDateTime,
+    pub consumed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl MfaChallengeModel {
+    pub fn new(token_hash: String, user_id: Uuid, expires_at: NaiveDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            token_hash,
+            user_id,
+            attempts: 0,
+            expires_at,
+            consumed_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
@@ -1,5 +1,25 @@
+pub mod authorization_code_model;
 pub mod class_model;
+pub mod email_verification_token_model;
+pub mod login_lockout_model;
+pub mod mail_outbox_model;
+pub mod mfa_challenge_model;
+pub mod oauth_client_model;
+pub mod password_history_model;
+pub mod password_reset_token_model;
+pub mod permission_model;
+pub mod recovery_code_model;
+pub mod refresh_token_model;
+pub mod revoked_token_model;
+pub mod role_model;
+pub mod role_permission_model;
 pub mod schedule_model;
 pub mod school_model;
+pub mod signing_key_model;
 pub mod student_model;
+pub mod totp_factor_model;
 pub mod user_model;
+pub mod user_role_model;
+pub mod user_token_revocation_model;
+pub mod webauthn_challenge_model;
+pub mod webauthn_credential_model;
@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::oauth_clients;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = oauth_clients)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthClientModel {
+    pub id: Uuid,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+    /// School the tokens of the client act within
+    pub tenant_id: Option<Uuid>,
+}
+
+impl OAuthClientModel {
+    pub fn new(
+        name: String,
+        redirect_uris: Vec<String>,
+        client_secret: Option<String>,
+        scopes: Vec<String>,
+        tenant_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            redirect_uris,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+            client_secret,
+            scopes,
+            tenant_id,
+        }
+    }
+}
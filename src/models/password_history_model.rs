@@ -0,0 +1,37 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::password_history;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = password_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PasswordHistoryModel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub password_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl PasswordHistoryModel {
+    pub fn new(user_id: Uuid, password_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            password_hash,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::password_reset_tokens;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = password_reset_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PasswordResetTokenModel {
+    pub id: Uuid,
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl PasswordResetTokenModel {
+    pub fn new(token_hash: String, user_id: Uuid, expires_at: NaiveDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            token_hash,
+            user_id,
+            expires_at,
+            used_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
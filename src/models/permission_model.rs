@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::permissions;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = permissions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PermissionModel {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl PermissionModel {
+    pub fn new(name: String, description: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::recovery_codes;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = recovery_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RecoveryCodeModel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl RecoveryCodeModel {
+    pub fn new(user_id: Uuid, code_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            code_hash,
+            used_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::revoked_tokens;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = revoked_tokens)]
+#[diesel(primary_key(jti))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RevokedTokenModel {
+    pub jti: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: NaiveDateTime,
+}
+
+impl RevokedTokenModel {
+    pub fn new(jti: Uuid, user_id: Uuid, expires_at: NaiveDateTime) -> Self {
+        Self {
+            jti,
+            user_id,
+            expires_at,
+            revoked_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::roles;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = roles)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RoleModel {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub mfa_required: bool,
+}
+
+impl RoleModel {
+    pub fn new(name: String, description: Option<String>, mfa_required: bool) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            description,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None,
+            mfa_required,
+        }
+    }
+}
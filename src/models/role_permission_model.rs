@@ -0,0 +1,25 @@
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::role_permissions;
+
+#[derive(
+    Insertable, Queryable, Identifiable, Selectable, Deserialize, Serialize, Debug, PartialEq,
+)]
+#[diesel(table_name = role_permissions)]
+#[diesel(primary_key(role_id, permission_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RolePermissionModel {
+    pub role_id: Uuid,
+    pub permission_id: Uuid,
+}
+
+impl RolePermissionModel {
+    pub fn new(role_id: Uuid, permission_id: Uuid) -> Self {
+        Self {
+            role_id,
+            permission_id,
+        }
+    }
+}
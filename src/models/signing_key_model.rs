@@ -0,0 +1,47 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::signing_keys;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = signing_keys)]
+#[diesel(primary_key(kid))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SigningKeyModel {
+    pub kid: String,
+    pub algorithm: String,
+    #[serde(skip_serializing)]
+    pub private_key: String,
+    pub created_at: NaiveDateTime,
+    pub activated_at: Option<NaiveDateTime>,
+    pub retired_at: Option<NaiveDateTime>,
+}
+
+impl SigningKeyModel {
+    pub fn new(
+        kid: String,
+        algorithm: String,
+        private_key: String,
+        activated_at: Option<NaiveDateTime>,
+    ) -> Self {
+        Self {
+            kid,
+            algorithm,
+            private_key,
+            created_at: chrono::Utc::now().naive_utc(),
+            activated_at,
+            retired_at: None,
+        }
+    }
+}
@@ -25,6 +25,8 @@ pub struct UserModel {
     pub is_admin: bool,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: Option<chrono::NaiveDateTime>,
+    pub email_verified_at: Option<chrono::NaiveDateTime>,
+    pub password_changed_at: chrono::NaiveDateTime,
 }
 
 impl UserModel {
@@ -37,6 +39,8 @@ impl UserModel {
             is_admin,
             created_at: chrono::Utc::now().naive_utc(),
             updated_at: None,
+            email_verified_at: None,
+            password_changed_at: chrono::Utc::now().naive_utc(),
         }
     }
 }
@@ -0,0 +1,22 @@
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::user_roles;
+
+#[derive(
+    Insertable, Queryable, Identifiable, Selectable, Deserialize, Serialize, Debug, PartialEq,
+)]
+#[diesel(table_name = user_roles)]
+#[diesel(primary_key(user_id, role_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserRoleModel {
+    pub user_id: Uuid,
+    pub role_id: Uuid,
+}
+
+impl UserRoleModel {
+    pub fn new(user_id: Uuid, role_id: Uuid) -> Self {
+        Self { user_id, role_id }
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{NaiveDateTime, SubsecRound};
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::user_token_revocations;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = user_token_revocations)]
+#[diesel(primary_key(user_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserTokenRevocationModel {
+    pub user_id: Uuid,
+    pub revoked_before: NaiveDateTime,
+}
+
+impl UserTokenRevocationModel {
+    /// Truncated to whole seconds, like the `iat` claim it is compared with
+    pub fn new(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            revoked_before: chrono::Utc::now().naive_utc().trunc_subsecs(0),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::webauthn_challenges;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = webauthn_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebAuthnChallengeModel {
+    pub id: Uuid,
+    pub challenge_hash: String,
+    pub user_id: Option<Uuid>,
+    pub ceremony: String,
+    pub expires_at: NaiveDateTime,
+    pub consumed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl WebAuthnChallengeModel {
+    pub fn new(
+        challenge_hash: String,
+        user_id: Option<Uuid>,
+        ceremony: String,
+        expires_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            challenge_hash,
+            user_id,
+            ceremony,
+            expires_at,
+            consumed_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::schema::webauthn_credentials;
+
+#[derive(
+    Insertable,
+    Queryable,
+    Identifiable,
+    Selectable,
+    Deserialize,
+    Serialize,
+    AsChangeset,
+    Debug,
+    PartialEq,
+)]
+#[diesel(table_name = webauthn_credentials)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebAuthnCredentialModel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub credential_id: String,
+    pub public_key: String,
+    pub sign_count: i64,
+    pub transports: Option<String>,
+    pub name: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl WebAuthnCredentialModel {
+    pub fn new(
+        user_id: Uuid,
+        credential_id: String,
+        public_key: String,
+        sign_count: i64,
+        transports: Option<String>,
+        name: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            credential_id,
+            public_key,
+            sign_count,
+            transports,
+            name,
+            created_at: chrono::Utc::now().naive_utc(),
+            last_used_at: None,
+        }
+    }
+}
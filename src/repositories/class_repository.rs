@@ -1,24 +1,81 @@
+use diesel::dsl::exists;
+use diesel::pg::Pg;
 use diesel::result::Error;
-use diesel::ExpressionMethods;
-use diesel::QueryDsl;
+use diesel::sql_types::Bool;
+use diesel::{BoxableExpression, ExpressionMethods, QueryDsl};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
 
-use crate::helper::enums::Identifier;
+use crate::helper::enums::{Identifier, Tenant};
+use crate::helper::exceptions::AppError;
 use crate::helper::utils::type_of;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::models::class_model::ClassModel;
-use crate::schema::classes;
+use crate::repositories::student_repository::StudentRepository;
+use crate::schema::{classes, students};
 use crate::schemas::class_schema::{ClassCreate, ClassResponse, ClassUpdate};
 
 pub struct ClassRepository;
 
-impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepository {
+impl ClassRepository {
+    /**
+     * Builds the filter limiting classes to the students of the tenant
+     *
+     * @param tenant: &Tenant
+     * @return Box<dyn BoxableExpression<classes::table, Pg, SqlType = Bool>>
+     */
+    fn tenant_filter(
+        tenant: &Tenant,
+    ) -> Box<dyn BoxableExpression<classes::table, Pg, SqlType = Bool>> {
+        match tenant.school_ids() {
+            Some(school_ids) => Box::new(
+                classes::student_id.eq_any(
+                    students::table
+                        .filter(students::school_id.eq_any(school_ids))
+                        .select(students::id),
+                ),
+            ),
+            None => Box::new(classes::id.is_not_null()),
+        }
+    }
+
+    /**
+     * Rejects writes referencing a class outside of the tenant, as if it did not exist
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param tenant: &Tenant
+     * @param class_id: Uuid
+     * @return Result<(), AppError>
+     */
+    pub async fn check_in_tenant(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        class_id: Uuid,
+    ) -> Result<(), AppError> {
+        let in_tenant = diesel::select(exists(
+            classes::table
+                .find(class_id)
+                .filter(Self::tenant_filter(tenant)),
+        ))
+        .get_result::<bool>(conn)
+        .await?;
+        if in_tenant {
+            return Ok(());
+        }
+        log::error!("Class {} is outside of the tenant", class_id);
+        Err(AppError::NotFound("Class not found".to_string()))
+    }
+}
+
+impl ITenantRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepository {
     type Model = ClassModel;
 
     async fn create(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         data: ClassCreate,
-    ) -> Result<ClassResponse, Error> {
+    ) -> Result<ClassResponse, AppError> {
+        StudentRepository::check_in_tenant(conn, tenant, data.student_id).await?;
         let new_class = Self::Model::new(data.name, data.student_id);
         let created_class = diesel::insert_into(classes::table)
             .values(&new_class)
@@ -27,7 +84,7 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
         match created_class {
             Err(e) => {
                 log::error!("Failed to create class: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(created_class) => Ok(ClassResponse {
                 id: created_class.id,
@@ -41,11 +98,13 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
 
     async fn get(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
-    ) -> Result<Option<ClassResponse>, Error> {
+    ) -> Result<Option<ClassResponse>, AppError> {
         let class = match id {
             Identifier::Id(id) => classes::table
                 .find(id)
+                .filter(Self::tenant_filter(tenant))
                 .get_result::<Self::Model>(conn)
                 .await
                 .map(Some),
@@ -61,7 +120,7 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
         match class {
             Err(e) => {
                 log::error!("Failed to get class: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(None) => {
                 log::error!("Class not found");
@@ -79,13 +138,16 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
 
     async fn update(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
         new_data: ClassUpdate,
-    ) -> Result<ClassResponse, Error> {
+    ) -> Result<ClassResponse, AppError> {
+        StudentRepository::check_in_tenant(conn, tenant, new_data.student_id).await?;
         let old_data = match id {
             Identifier::Id(id) => {
                 classes::table
                     .find(id)
+                    .filter(Self::tenant_filter(tenant))
                     .get_result::<Self::Model>(conn)
                     .await?
             }
@@ -109,7 +171,7 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
         match updated_class {
             Err(e) => {
                 log::error!("Failed to update class: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(updated_class) => Ok(ClassResponse {
                 id: updated_class.id,
@@ -121,9 +183,17 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
         }
     }
 
-    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+    async fn delete(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+    ) -> Result<usize, AppError> {
         let number_deleted = match id {
-            Identifier::Id(id) => diesel::delete(classes::table.find(id)).execute(conn).await,
+            Identifier::Id(id) => {
+                diesel::delete(classes::table.find(id).filter(Self::tenant_filter(tenant)))
+                    .execute(conn)
+                    .await
+            }
             _ => {
                 log::error!(
                     "Wrong class identifier. Expecting uuid type. Got {:?}",
@@ -135,7 +205,7 @@ impl IRepository<'_, ClassCreate, ClassUpdate, ClassResponse> for ClassRepositor
         match number_deleted {
             Err(e) => {
                 log::error!("Failed to delete class: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(num) => Ok(num),
         }
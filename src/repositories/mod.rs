@@ -1,4 +1,7 @@
 pub mod class_repository;
+pub mod oauth_client_repository;
+pub mod permission_repository;
+pub mod role_repository;
 pub mod schedule_repository;
 pub mod school_repository;
 pub mod student_repository;
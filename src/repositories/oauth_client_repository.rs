@@ -0,0 +1,159 @@
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::helper::enums::Identifier;
+use crate::interfaces::repository_interface::IRepository;
+use crate::models::oauth_client_model::OAuthClientModel;
+use crate::schema::oauth_clients;
+use crate::schemas::oauth_schemas::{OAuthClientCreate, OAuthClientResponse, OAuthClientUpdate};
+use crate::services::password_service::PasswordService;
+use crate::services::refresh_token_service::RefreshTokenService;
+
+pub struct OAuthClientRepository;
+
+impl OAuthClientRepository {
+    fn to_response(client: OAuthClientModel, client_secret: Option<String>) -> OAuthClientResponse {
+        OAuthClientResponse {
+            id: client.id,
+            name: client.name,
+            redirect_uris: client.redirect_uris,
+            confidential: client.client_secret.is_some(),
+            scopes: client.scopes,
+            tenant_id: client.tenant_id,
+            client_secret,
+            created_at: client.created_at,
+            updated_at: client.updated_at,
+        }
+    }
+}
+
+impl IRepository<'_, OAuthClientCreate, OAuthClientUpdate, OAuthClientResponse>
+    for OAuthClientRepository
+{
+    type Model = OAuthClientModel;
+
+    async fn create(
+        conn: &mut AsyncPgConnection,
+        data: OAuthClientCreate,
+    ) -> Result<OAuthClientResponse, Error> {
+        // Like user passwords, only the hash of the secret is stored
+        let client_secret = data.confidential.then(RefreshTokenService::generate);
+        let new_client = Self::Model::new(
+            data.name,
+            data.redirect_uris,
+            client_secret.as_deref().map(PasswordService::hash),
+            data.scopes,
+            data.tenant_id,
+        );
+        let created_client = diesel::insert_into(oauth_clients::table)
+            .values(&new_client)
+            .get_result::<Self::Model>(conn)
+            .await;
+        match created_client {
+            Err(e) => {
+                log::error!("Failed to create oauth client: {}", e);
+                Err(e)
+            }
+            Ok(created_client) => Ok(Self::to_response(created_client, client_secret)),
+        }
+    }
+
+    async fn get(
+        conn: &mut AsyncPgConnection,
+        id: &Identifier,
+    ) -> Result<Option<OAuthClientResponse>, Error> {
+        let client = match id {
+            Identifier::Id(id) => oauth_clients::table
+                .find(id)
+                .get_result::<Self::Model>(conn)
+                .await
+                .map(Some),
+            _ => {
+                log::error!(
+                    "Wrong oauth client identifier. Expecting uuid type. Got {:?}",
+                    id
+                );
+                Err(Error::NotFound)
+            }
+        };
+
+        match client {
+            Err(e) => {
+                log::error!("Failed to get oauth client: {}", e);
+                Err(e)
+            }
+            Ok(None) => {
+                log::error!("OAuth client id {:?} not found", id);
+                Ok(None)
+            }
+            Ok(Some(client)) => Ok(Some(Self::to_response(client, None))),
+        }
+    }
+
+    async fn update(
+        conn: &mut AsyncPgConnection,
+        id: &Identifier,
+        new_data: OAuthClientUpdate,
+    ) -> Result<OAuthClientResponse, Error> {
+        let old_data = match id {
+            Identifier::Id(id) => {
+                oauth_clients::table
+                    .find(id)
+                    .get_result::<Self::Model>(conn)
+                    .await?
+            }
+            _ => {
+                log::error!(
+                    "Wrong oauth client identifier. Expecting uuid type. Got {:?}",
+                    id
+                );
+                Err(Error::NotFound)?
+            }
+        };
+
+        let updated_client = diesel::update(&old_data)
+            .set((
+                oauth_clients::name.eq(new_data.name),
+                oauth_clients::redirect_uris.eq(new_data.redirect_uris),
+                oauth_clients::scopes.eq(new_data.scopes),
+                oauth_clients::tenant_id.eq(new_data.tenant_id),
+                oauth_clients::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result::<Self::Model>(conn)
+            .await;
+
+        match updated_client {
+            Err(e) => {
+                log::error!("Failed to update oauth client: {}", e);
+                Err(e)
+            }
+            Ok(client) => Ok(Self::to_response(client, None)),
+        }
+    }
+
+    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+        let number_deleted = match id {
+            Identifier::Id(id) => {
+                diesel::delete(oauth_clients::table.find(id))
+                    .execute(conn)
+                    .await
+            }
+            _ => {
+                log::error!(
+                    "Wrong oauth client identifier. Expecting uuid type. Got {:?}",
+                    id
+                );
+                Err(Error::NotFound)?
+            }
+        };
+        match number_deleted {
+            Ok(num) => Ok(num),
+            Err(e) => {
+                log::error!("Failed to delete oauth client: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::helper::enums::Identifier;
+use crate::interfaces::repository_interface::IRepository;
+use crate::models::permission_model::PermissionModel;
+use crate::schema::permissions;
+use crate::schemas::role_schemas::{PermissionCreate, PermissionResponse, PermissionUpdate};
+
+pub struct PermissionRepository;
+
+impl PermissionRepository {
+    pub fn to_response(permission: PermissionModel) -> PermissionResponse {
+        PermissionResponse {
+            id: permission.id,
+            name: permission.name,
+            description: permission.description,
+            created_at: permission.created_at,
+            updated_at: permission.updated_at,
+        }
+    }
+}
+
+impl IRepository<'_, PermissionCreate, PermissionUpdate, PermissionResponse>
+    for PermissionRepository
+{
+    type Model = PermissionModel;
+
+    async fn create(
+        conn: &mut AsyncPgConnection,
+        data: PermissionCreate,
+    ) -> Result<PermissionResponse, Error> {
+        let new_permission = Self::Model::new(data.name, data.description);
+        let created_permission = diesel::insert_into(permissions::table)
+            .values(&new_permission)
+            .get_result::<Self::Model>(conn)
+            .await;
+        match created_permission {
+            Err(e) => {
+                log::error!("Failed to create permission: {}", e);
+                Err(e)
+            }
+            Ok(created_permission) => Ok(Self::to_response(created_permission)),
+        }
+    }
+
+    async fn get(
+        conn: &mut AsyncPgConnection,
+        id: &Identifier,
+    ) -> Result<Option<PermissionResponse>, Error> {
+        let permission = match id {
+            Identifier::Id(id) => permissions::table
+                .find(id)
+                .get_result::<Self::Model>(conn)
+                .await
+                .map(Some),
+            _ => {
+                log::error!(
+                    "Wrong permission identifier. Expecting uuid type. Got {:?}",
+                    id
+                );
+                Err(Error::NotFound)
+            }
+        };
+
+        match permission {
+            Err(e) => {
+                log::error!("Failed to get permission: {}", e);
+                Err(e)
+            }
+            Ok(None) => {
+                log::error!("Permission id {:?} not found", id);
+                Ok(None)
+            }
+            Ok(Some(permission)) => Ok(Some(Self::to_response(permission))),
+        }
+    }
+
+    async fn update(
+        conn: &mut AsyncPgConnection,
+        id: &Identifier,
+        new_data: PermissionUpdate,
+    ) -> Result<PermissionResponse, Error> {
+        let old_data = match id {
+            Identifier::Id(id) => {
+                permissions::table
+                    .find(id)
+                    .get_result::<Self::Model>(conn)
+                    .await?
+            }
+            _ => {
+                log::error!(
+                    "Wrong permission identifier. Expecting uuid type. Got {:?}",
+                    id
+                );
+                Err(Error::NotFound)?
+            }
+        };
+
+        let updated_permission = diesel::update(&old_data)
+            .set((
+                permissions::description.eq(new_data.description),
+                permissions::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result::<Self::Model>(conn)
+            .await;
+
+        match updated_permission {
+            Err(e) => {
+                log::error!("Failed to update permission: {}", e);
+                Err(e)
+            }
+            Ok(permission) => Ok(Self::to_response(permission)),
+        }
+    }
+
+    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+        let number_deleted = match id {
+            Identifier::Id(id) => {
+                diesel::delete(permissions::table.find(id))
+                    .execute(conn)
+                    .await
+            }
+            _ => {
+                log::error!(
+                    "Wrong permission identifier. Expecting uuid type. Got {:?}",
+                    id
+                );
+                Err(Error::NotFound)?
+            }
+        };
+        match number_deleted {
+            Ok(num) => Ok(num),
+            Err(e) => {
+                log::error!("Failed to delete permission: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
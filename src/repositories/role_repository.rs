@@ -0,0 +1,127 @@
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::helper::enums::Identifier;
+use crate::interfaces::repository_interface::IRepository;
+use crate::models::role_model::RoleModel;
+use crate::schema::roles;
+use crate::schemas::role_schemas::{RoleCreate, RoleResponse, RoleUpdate};
+
+pub struct RoleRepository;
+
+impl RoleRepository {
+    pub fn to_response(role: RoleModel) -> RoleResponse {
+        RoleResponse {
+            id: role.id,
+            name: role.name,
+            description: role.description,
+            mfa_required: role.mfa_required,
+            created_at: role.created_at,
+            updated_at: role.updated_at,
+        }
+    }
+}
+
+impl IRepository<'_, RoleCreate, RoleUpdate, RoleResponse> for RoleRepository {
+    type Model = RoleModel;
+
+    async fn create(conn: &mut AsyncPgConnection, data: RoleCreate) -> Result<RoleResponse, Error> {
+        let new_role = Self::Model::new(data.name, data.description, data.mfa_required);
+        let created_role = diesel::insert_into(roles::table)
+            .values(&new_role)
+            .get_result::<Self::Model>(conn)
+            .await;
+        match created_role {
+            Err(e) => {
+                log::error!("Failed to create role: {}", e);
+                Err(e)
+            }
+            Ok(created_role) => Ok(Self::to_response(created_role)),
+        }
+    }
+
+    async fn get(
+        conn: &mut AsyncPgConnection,
+        id: &Identifier,
+    ) -> Result<Option<RoleResponse>, Error> {
+        let role = match id {
+            Identifier::Id(id) => roles::table
+                .find(id)
+                .get_result::<Self::Model>(conn)
+                .await
+                .map(Some),
+            _ => {
+                log::error!("Wrong role identifier. Expecting uuid type. Got {:?}", id);
+                Err(Error::NotFound)
+            }
+        };
+
+        match role {
+            Err(e) => {
+                log::error!("Failed to get role: {}", e);
+                Err(e)
+            }
+            Ok(None) => {
+                log::error!("Role id {:?} not found", id);
+                Ok(None)
+            }
+            Ok(Some(role)) => Ok(Some(Self::to_response(role))),
+        }
+    }
+
+    async fn update(
+        conn: &mut AsyncPgConnection,
+        id: &Identifier,
+        new_data: RoleUpdate,
+    ) -> Result<RoleResponse, Error> {
+        let old_data = match id {
+            Identifier::Id(id) => {
+                roles::table
+                    .find(id)
+                    .get_result::<Self::Model>(conn)
+                    .await?
+            }
+            _ => {
+                log::error!("Wrong role identifier. Expecting uuid type. Got {:?}", id);
+                Err(Error::NotFound)?
+            }
+        };
+
+        let updated_role = diesel::update(&old_data)
+            .set((
+                roles::name.eq(new_data.name),
+                roles::description.eq(new_data.description),
+                roles::mfa_required.eq(new_data.mfa_required),
+                roles::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result::<Self::Model>(conn)
+            .await;
+
+        match updated_role {
+            Err(e) => {
+                log::error!("Failed to update role: {}", e);
+                Err(e)
+            }
+            Ok(role) => Ok(Self::to_response(role)),
+        }
+    }
+
+    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+        let number_deleted = match id {
+            Identifier::Id(id) => diesel::delete(roles::table.find(id)).execute(conn).await,
+            _ => {
+                log::error!("Wrong role identifier. Expecting uuid type. Got {:?}", id);
+                Err(Error::NotFound)?
+            }
+        };
+        match number_deleted {
+            Ok(num) => Ok(num),
+            Err(e) => {
+                log::error!("Failed to delete role: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
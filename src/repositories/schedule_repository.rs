@@ -1,24 +1,56 @@
+use diesel::pg::Pg;
 use diesel::result::Error;
-use diesel::ExpressionMethods;
-use diesel::QueryDsl;
+use diesel::sql_types::Bool;
+use diesel::{BoxableExpression, ExpressionMethods, QueryDsl};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 
-use crate::helper::enums::Identifier;
+use crate::helper::enums::{Identifier, Tenant};
+use crate::helper::exceptions::AppError;
 use crate::helper::utils::type_of;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::models::schedule_model::ScheduleModel;
-use crate::schema::schedules;
+use crate::repositories::class_repository::ClassRepository;
+use crate::repositories::student_repository::StudentRepository;
+use crate::schema::{schedules, students};
 use crate::schemas::schedule_schemas::{ScheduleCreate, ScheduleResponse, ScheduleUpdate};
 
 pub struct ScheduleRepository;
 
-impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for ScheduleRepository {
+impl ScheduleRepository {
+    /**
+     * Builds the filter limiting schedules to the students of the tenant
+     *
+     * @param tenant: &Tenant
+     * @return Box<dyn BoxableExpression<schedules::table, Pg, SqlType = Bool>>
+     */
+    fn tenant_filter(
+        tenant: &Tenant,
+    ) -> Box<dyn BoxableExpression<schedules::table, Pg, SqlType = Bool>> {
+        match tenant.school_ids() {
+            Some(school_ids) => Box::new(
+                schedules::student_id.eq_any(
+                    students::table
+                        .filter(students::school_id.eq_any(school_ids))
+                        .select(students::id),
+                ),
+            ),
+            None => Box::new(schedules::id.is_not_null()),
+        }
+    }
+}
+
+impl ITenantRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse>
+    for ScheduleRepository
+{
     type Model = ScheduleModel;
 
     async fn create(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         data: ScheduleCreate,
-    ) -> Result<ScheduleResponse, Error> {
+    ) -> Result<ScheduleResponse, AppError> {
+        StudentRepository::check_in_tenant(conn, tenant, data.student_id).await?;
+        ClassRepository::check_in_tenant(conn, tenant, data.class_id).await?;
         let new_schedule = Self::Model::new(
             data.student_id,
             data.class_id,
@@ -33,7 +65,7 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
         match created_schedule {
             Err(e) => {
                 log::error!("Failed to create schedule: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(created_schedule) => Ok(ScheduleResponse {
                 id: created_schedule.id,
@@ -50,11 +82,13 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
 
     async fn get(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
-    ) -> Result<Option<ScheduleResponse>, Error> {
+    ) -> Result<Option<ScheduleResponse>, AppError> {
         let schedule = match id {
             Identifier::Id(id) => schedules::table
                 .find(id)
+                .filter(Self::tenant_filter(tenant))
                 .get_result::<Self::Model>(conn)
                 .await
                 .map(Some),
@@ -70,7 +104,7 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
         match schedule {
             Err(e) => {
                 log::error!("Failed to get schedule: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(None) => {
                 log::error!("Schedule id {:?} not found", id);
@@ -91,13 +125,17 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
 
     async fn update(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
         new_data: ScheduleUpdate,
-    ) -> Result<ScheduleResponse, Error> {
+    ) -> Result<ScheduleResponse, AppError> {
+        StudentRepository::check_in_tenant(conn, tenant, new_data.student_id).await?;
+        ClassRepository::check_in_tenant(conn, tenant, new_data.class_id).await?;
         let old_data = match id {
             Identifier::Id(id) => {
                 schedules::table
                     .find(id)
+                    .filter(Self::tenant_filter(tenant))
                     .get_result::<Self::Model>(conn)
                     .await?
             }
@@ -124,7 +162,7 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
         match updated_schedule {
             Err(e) => {
                 log::error!("Failed to update schedule: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(updated_schedule) => Ok(ScheduleResponse {
                 id: updated_schedule.id,
@@ -139,12 +177,20 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
         }
     }
 
-    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+    async fn delete(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+    ) -> Result<usize, AppError> {
         let deleted_schedule = match id {
             Identifier::Id(id) => {
-                diesel::delete(schedules::table.find(id))
-                    .execute(conn)
-                    .await
+                diesel::delete(
+                    schedules::table
+                        .find(id)
+                        .filter(Self::tenant_filter(tenant)),
+                )
+                .execute(conn)
+                .await
             }
             _ => {
                 log::error!(
@@ -157,7 +203,7 @@ impl IRepository<'_, ScheduleCreate, ScheduleUpdate, ScheduleResponse> for Sched
         match deleted_schedule {
             Err(e) => {
                 log::error!("Failed to delete schedule: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(deleted_schedule) => Ok(deleted_schedule),
         }
@@ -1,24 +1,50 @@
+use diesel::pg::Pg;
 use diesel::result::Error;
-use diesel::ExpressionMethods;
-use diesel::QueryDsl;
+use diesel::sql_types::Bool;
+use diesel::{BoxableExpression, ExpressionMethods, QueryDsl};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 
-use crate::helper::enums::Identifier;
+use crate::helper::enums::{Identifier, Tenant};
+use crate::helper::exceptions::AppError;
 use crate::helper::utils::type_of;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::models::school_model::SchoolModel;
 use crate::schema::schools;
 use crate::schemas::school_schemas::{SchoolCreate, SchoolResponse, SchoolUpdate};
 
 pub struct SchoolRepository;
 
-impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepository {
+impl SchoolRepository {
+    /**
+     * Builds the filter limiting schools to the tenant
+     *
+     * @param tenant: &Tenant
+     * @return Box<dyn BoxableExpression<schools::table, Pg, SqlType = Bool>>
+     */
+    fn tenant_filter(
+        tenant: &Tenant,
+    ) -> Box<dyn BoxableExpression<schools::table, Pg, SqlType = Bool>> {
+        match tenant.school_ids() {
+            Some(school_ids) => Box::new(schools::id.eq_any(school_ids)),
+            None => Box::new(schools::id.is_not_null()),
+        }
+    }
+}
+
+impl ITenantRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepository {
     type Model = SchoolModel;
 
     async fn create(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         data: SchoolCreate,
-    ) -> Result<SchoolResponse, Error> {
+    ) -> Result<SchoolResponse, AppError> {
+        if *tenant != Tenant::All {
+            log::error!("Schools can only be created outside of a tenant");
+            return Err(AppError::Forbidden(
+                "Schools can only be created outside of a tenant".to_string(),
+            ));
+        }
         let new_school = Self::Model::new(data.name, data.website);
         let created_school = diesel::insert_into(schools::table)
             .values(&new_school)
@@ -27,7 +53,7 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
         match created_school {
             Err(e) => {
                 log::error!("Failed to create school: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(created_school) => Ok(SchoolResponse {
                 id: created_school.id,
@@ -41,11 +67,13 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
 
     async fn get(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
-    ) -> Result<Option<SchoolResponse>, Error> {
+    ) -> Result<Option<SchoolResponse>, AppError> {
         let school = match id {
             Identifier::Id(id) => schools::table
                 .find(id)
+                .filter(Self::tenant_filter(tenant))
                 .get_result::<Self::Model>(conn)
                 .await
                 .map(Some),
@@ -61,7 +89,7 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
         match school {
             Err(e) => {
                 log::error!("Failed to get school: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(None) => {
                 log::error!("School id {:?} not found", id);
@@ -79,13 +107,15 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
 
     async fn update(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
         new_data: SchoolUpdate,
-    ) -> Result<SchoolResponse, Error> {
+    ) -> Result<SchoolResponse, AppError> {
         let old_data = match id {
             Identifier::Id(id) => {
                 schools::table
                     .find(id)
+                    .filter(Self::tenant_filter(tenant))
                     .get_result::<Self::Model>(conn)
                     .await?
             }
@@ -107,7 +137,7 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
         match updated_school {
             Err(e) => {
                 log::error!("Failed to update school: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(sch) => Ok(SchoolResponse {
                 id: sch.id,
@@ -119,9 +149,17 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
         }
     }
 
-    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+    async fn delete(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+    ) -> Result<usize, AppError> {
         let number_deleted = match id {
-            Identifier::Id(id) => diesel::delete(schools::table.find(id)).execute(conn).await,
+            Identifier::Id(id) => {
+                diesel::delete(schools::table.find(id).filter(Self::tenant_filter(tenant)))
+                    .execute(conn)
+                    .await
+            }
             _ => {
                 log::error!("Wrong school identifier. Expecting uuid type. Got {:?}", id);
                 Err(Error::NotFound)?
@@ -131,7 +169,7 @@ impl IRepository<'_, SchoolCreate, SchoolUpdate, SchoolResponse> for SchoolRepos
             Ok(num) => Ok(num),
             Err(e) => {
                 log::error!("Failed to delete school: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
         }
     }
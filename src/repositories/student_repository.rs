@@ -1,24 +1,94 @@
+use diesel::dsl::exists;
+use diesel::pg::Pg;
 use diesel::result::Error;
-use diesel::ExpressionMethods;
-use diesel::QueryDsl;
+use diesel::sql_types::Bool;
+use diesel::{BoxableExpression, ExpressionMethods, QueryDsl};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
 
-use crate::helper::enums::Identifier;
+use crate::helper::enums::{Identifier, Tenant};
+use crate::helper::exceptions::AppError;
 use crate::helper::utils::type_of;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::models::student_model::StudentModel;
 use crate::schema::students;
 use crate::schemas::student_schemas::{StudentCreate, StudentResponse, StudentUpdate};
 
 pub struct StudentRepository;
 
-impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentRepository {
+impl StudentRepository {
+    /**
+     * Builds the filter limiting students to the schools of the tenant
+     *
+     * @param tenant: &Tenant
+     * @return Box<dyn BoxableExpression<students::table, Pg, SqlType = Bool>>
+     */
+    fn tenant_filter(
+        tenant: &Tenant,
+    ) -> Box<dyn BoxableExpression<students::table, Pg, SqlType = Bool>> {
+        match tenant.school_ids() {
+            Some(school_ids) => Box::new(students::school_id.eq_any(school_ids)),
+            None => Box::new(students::id.is_not_null()),
+        }
+    }
+
+    /**
+     * Rejects writes referencing a student outside of the tenant, as if it did not exist
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param tenant: &Tenant
+     * @param student_id: Uuid
+     * @return Result<(), AppError>
+     */
+    pub async fn check_in_tenant(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        student_id: Uuid,
+    ) -> Result<(), AppError> {
+        let in_tenant = diesel::select(exists(
+            students::table
+                .find(student_id)
+                .filter(Self::tenant_filter(tenant)),
+        ))
+        .get_result::<bool>(conn)
+        .await?;
+        if in_tenant {
+            return Ok(());
+        }
+        log::error!("Student {} is outside of the tenant", student_id);
+        Err(AppError::NotFound("Student not found".to_string()))
+    }
+
+    /**
+     * Rejects writes moving a student to a school outside of the tenant
+     *
+     * @param tenant: &Tenant
+     * @param school_id: Uuid
+     * @return Result<(), AppError>
+     */
+    fn check_school(tenant: &Tenant, school_id: Uuid) -> Result<(), AppError> {
+        if tenant.contains(school_id) {
+            return Ok(());
+        }
+        log::error!("School {} is outside of the tenant", school_id);
+        Err(AppError::Forbidden(
+            "School is outside of the tenant".to_string(),
+        ))
+    }
+}
+
+impl ITenantRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentRepository {
     type Model = StudentModel;
 
     async fn create(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         data: StudentCreate,
-    ) -> Result<StudentResponse, Error> {
+    ) -> Result<StudentResponse, AppError> {
+        // Users who are not enrolled yet may enroll in any school
+        if *tenant != Tenant::Unassigned {
+            Self::check_school(tenant, data.school_id)?;
+        }
         let new_student = Self::Model::new(
             data.first_name,
             data.last_name,
@@ -34,7 +104,7 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
         match created_student {
             Err(e) => {
                 log::error!("Failed to create student: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(created_student) => Ok(StudentResponse {
                 id: created_student.id,
@@ -52,11 +122,13 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
 
     async fn get(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
-    ) -> Result<Option<StudentResponse>, Error> {
+    ) -> Result<Option<StudentResponse>, AppError> {
         let student = match id {
             Identifier::Id(id) => students::table
                 .find(id)
+                .filter(Self::tenant_filter(tenant))
                 .get_result::<Self::Model>(conn)
                 .await
                 .map(Some),
@@ -72,7 +144,7 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
         match student {
             Err(e) => {
                 log::error!("Failed to get student: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(None) => {
                 log::error!("Student not found");
@@ -94,13 +166,16 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
 
     async fn update(
         conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
         id: &Identifier,
         new_data: StudentUpdate,
-    ) -> Result<StudentResponse, Error> {
+    ) -> Result<StudentResponse, AppError> {
+        Self::check_school(tenant, new_data.school_id)?;
         let old_data = match id {
             Identifier::Id(id) => {
                 students::table
                     .find(id)
+                    .filter(Self::tenant_filter(tenant))
                     .get_result::<Self::Model>(conn)
                     .await?
             }
@@ -127,7 +202,7 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
         match updated_student {
             Err(e) => {
                 log::error!("Failed to update student: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
             Ok(updated_student) => Ok(StudentResponse {
                 id: updated_student.id,
@@ -143,9 +218,17 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
         }
     }
 
-    async fn delete(conn: &mut AsyncPgConnection, id: &Identifier) -> Result<usize, Error> {
+    async fn delete(
+        conn: &mut AsyncPgConnection,
+        tenant: &Tenant,
+        id: &Identifier,
+    ) -> Result<usize, AppError> {
         let number_deleted = match id {
-            Identifier::Id(id) => diesel::delete(students::table.find(id)).execute(conn).await,
+            Identifier::Id(id) => {
+                diesel::delete(students::table.find(id).filter(Self::tenant_filter(tenant)))
+                    .execute(conn)
+                    .await
+            }
             _ => {
                 log::error!(
                     "Wrong student identifier. Expecting uuid type. Got {:?}",
@@ -158,7 +241,7 @@ impl IRepository<'_, StudentCreate, StudentUpdate, StudentResponse> for StudentR
             Ok(number_deleted) => Ok(number_deleted),
             Err(e) => {
                 log::error!("Failed to delete student: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
         }
     }
@@ -20,14 +20,6 @@ impl IRepository<'_, UserCreate, UserUpdate, UserResponse> for UserRepository {
     type Model = UserModel;
 
     async fn create(conn: &mut AsyncPgConnection, data: UserCreate) -> Result<UserResponse, Error> {
-        if !PasswordService::validate(&data.password) {
-            log::error!("Password length must be at least 8 characters");
-            // @TODO: Replace with custom error
-            return Err(Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::CheckViolation,
-                Box::new("Password length must be at least 8 characters".to_string()),
-            ));
-        }
         let hashed_password = PasswordService::hash(&data.password);
 
         let new_user = Self::Model::new(data.email, hashed_password, data.is_active, data.is_admin);
@@ -48,6 +40,7 @@ impl IRepository<'_, UserCreate, UserUpdate, UserResponse> for UserRepository {
                 is_admin: created_user.is_admin,
                 created_at: created_user.created_at,
                 updated_at: created_user.updated_at,
+                email_verified_at: created_user.email_verified_at,
             }),
         }
     }
@@ -81,6 +74,7 @@ impl IRepository<'_, UserCreate, UserUpdate, UserResponse> for UserRepository {
                 is_admin: user.is_admin,
                 created_at: user.created_at,
                 updated_at: user.updated_at,
+                email_verified_at: user.email_verified_at,
             })),
             Err(e) => {
                 log::error!("Failed to get user: {}", e);
@@ -128,6 +122,7 @@ impl IRepository<'_, UserCreate, UserUpdate, UserResponse> for UserRepository {
                     is_admin: user.is_admin,
                     created_at: user.created_at,
                     updated_at: user.updated_at,
+                    email_verified_at: user.email_verified_at,
                 })
             }
             Err(e) => {
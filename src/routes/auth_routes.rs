@@ -1,28 +1,111 @@
-use actix_web::{web, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
 
 use crate::configs::common::ApplicationConfig;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
-use crate::schemas::auth_schemas::LoginRequest;
+use crate::schemas::auth_schemas::{LoginRequest, LogoutRequest, RefreshRequest};
+use crate::services::auth_extractor::AuthExtractorService;
 use crate::services::auth_service::AuthService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::permission_extractor::{RequirePermission, SessionsRevoke};
+use crate::services::revocation_service::{RevocationCache, RevocationService};
 
 pub struct AuthRoutes;
 
 impl AuthRoutes {
     pub async fn login(
+        req: HttpRequest,
         pool: web::Data<DbPool>,
         auth: web::Json<LoginRequest>,
         app_config: web::Data<ApplicationConfig>,
-    ) -> actix_web::Result<impl Responder> {
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
         log::info!("Logging in: {:?}", auth.email);
         let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+        LockoutService::require_unlocked(&mut conn, &auth.email, client_ip.as_deref()).await?;
 
-        let token = AuthService::login(&mut conn, auth.into_inner(), &app_config.auth).await;
+        let token = AuthService::login(
+            &mut conn,
+            auth.into_inner(),
+            client_ip.as_deref(),
+            &app_config.auth,
+            &key_ring,
+        )
+        .await;
         match token {
             Ok(token) => Ok(actix_web::HttpResponse::Ok().json(token)),
             Err(e) => {
                 log::error!("Failed to login: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn refresh(
+        pool: web::Data<DbPool>,
+        refresh: web::Json<RefreshRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let token =
+            AuthService::refresh(&mut conn, refresh.into_inner(), &app_config.auth, &key_ring)
+                .await;
+        match token {
+            Ok(token) => Ok(actix_web::HttpResponse::Ok().json(token)),
+            Err(e) => {
+                log::error!("Failed to refresh token: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn logout(
+        pool: web::Data<DbPool>,
+        logout: Option<web::Json<LogoutRequest>>,
+        revocation_cache: web::Data<RevocationCache>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        log::info!("Logging out: {:?}", auth.id);
+        let mut conn = get_connection(&pool).await;
+
+        let logged_out = AuthService::logout(
+            &mut conn,
+            &revocation_cache,
+            &auth,
+            logout.map(|logout| logout.into_inner()),
+        )
+        .await;
+        match logged_out {
+            Ok(_) => Ok(HttpResponse::Ok().finish()),
+            Err(e) => {
+                log::error!("Failed to logout: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn revoke_sessions(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        revocation_cache: web::Data<RevocationCache>,
+        _: RequirePermission<SessionsRevoke>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = id.into_inner();
+        log::info!("Revoking sessions of user: {:?}", &_id);
+
+        let revoked = RevocationService::revoke_user(&mut conn, &revocation_cache, _id).await;
+        match revoked {
+            Ok(_) => Ok(HttpResponse::Ok().finish()),
+            Err(e) => {
+                log::error!("Failed to revoke sessions: {}", e);
+                Err(AppError::from(e))
             }
         }
     }
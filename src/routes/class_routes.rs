@@ -2,11 +2,14 @@ use actix_web::{web, Responder};
 use uuid::Uuid;
 
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::repositories::class_repository::ClassRepository;
 use crate::schemas::class_schema::{ClassCreate, ClassUpdate};
+use crate::services::ownership_service::OwnershipService;
+use crate::services::scope_extractor::{ClassesRead, ClassesWrite, RequireScope};
 
 pub struct ClassRoutes;
 
@@ -14,15 +17,17 @@ impl ClassRoutes {
     pub async fn create(
         pool: web::Data<DbPool>,
         class: web::Json<ClassCreate>,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<ClassesWrite>,
+    ) -> Result<impl Responder, AppError> {
         log::info!("Creating class: {:?}", class.name);
         let mut conn = get_connection(&pool).await;
-        let _class = ClassRepository::create(&mut conn, class.into_inner()).await;
+        OwnershipService::require_student(&mut conn, &auth, class.student_id).await?;
+        let _class = ClassRepository::create(&mut conn, &auth.tenant(), class.into_inner()).await;
         match _class {
             Ok(_class) => Ok(actix_web::HttpResponse::Ok().json(_class)),
             Err(e) => {
                 log::error!("Failed to create class: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -30,15 +35,18 @@ impl ClassRoutes {
     pub async fn get(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<ClassesRead>,
+    ) -> Result<impl Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let _id = Identifier::Id(id.into_inner());
-        let class = ClassRepository::get(&mut conn, &_id).await;
+        let id = id.into_inner();
+        OwnershipService::require_class(&mut conn, &auth, id).await?;
+        let _id = Identifier::Id(id);
+        let class = ClassRepository::get(&mut conn, &auth.tenant(), &_id).await;
         match class {
             Ok(class) => Ok(actix_web::HttpResponse::Ok().json(class)),
             Err(e) => {
                 log::error!("Failed to get class: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -47,18 +55,23 @@ impl ClassRoutes {
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
         class: web::Json<ClassUpdate>,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<ClassesWrite>,
+    ) -> Result<impl Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let _id = Identifier::Id(id.into_inner());
+        let id = id.into_inner();
+        OwnershipService::require_class(&mut conn, &auth, id).await?;
+        // The class must stay with a student of the caller
+        OwnershipService::require_student(&mut conn, &auth, class.student_id).await?;
+        let _id = Identifier::Id(id);
         log::info!("Updating class: {:?}", &_id);
 
         let class = class.into_inner();
-        let updated_class = ClassRepository::update(&mut conn, &_id, class).await;
+        let updated_class = ClassRepository::update(&mut conn, &auth.tenant(), &_id, class).await;
         match updated_class {
             Ok(updated_class) => Ok(actix_web::HttpResponse::Ok().json(updated_class)),
             Err(e) => {
                 log::error!("Failed to update class: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -66,17 +79,20 @@ impl ClassRoutes {
     pub async fn delete(
         pool: web::Data<DbPool>,
         id: web::Path<Uuid>,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<ClassesWrite>,
+    ) -> Result<impl Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let _id = Identifier::Id(id.into_inner());
+        let id = id.into_inner();
+        OwnershipService::require_class(&mut conn, &auth, id).await?;
+        let _id = Identifier::Id(id);
         log::info!("Deleting class: {:?}", &_id);
 
-        let deleted_class = ClassRepository::delete(&mut conn, &_id).await;
+        let deleted_class = ClassRepository::delete(&mut conn, &auth.tenant(), &_id).await;
         match deleted_class {
             Ok(deleted_class) => Ok(actix_web::HttpResponse::Ok().json(deleted_class)),
             Err(e) => {
                 log::error!("Failed to delete class: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
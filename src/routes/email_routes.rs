@@ -0,0 +1,86 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::configs::common::ApplicationConfig;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::schemas::user_schemas::{EmailChangeRequest, EmailResendRequest, EmailVerifyRequest};
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::email_verification_service::EmailVerificationService;
+use crate::services::lockout_service::LockoutService;
+use crate::services::ownership_service::OwnershipService;
+
+pub struct EmailRoutes;
+
+impl EmailRoutes {
+    pub async fn verify(
+        pool: web::Data<DbPool>,
+        verify: web::Json<EmailVerifyRequest>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let verified = EmailVerificationService::verify(&mut conn, verify.into_inner()).await;
+        match verified {
+            Ok(user) => {
+                log::info!("Email verified for user: {:?}", user.id);
+                Ok(HttpResponse::Ok().finish())
+            }
+            Err(e) => {
+                log::error!("Failed to verify email: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn resend(
+        pool: web::Data<DbPool>,
+        resend: web::Json<EmailResendRequest>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let resent =
+            EmailVerificationService::resend(&mut conn, resend.into_inner(), &app_config.auth)
+                .await;
+        match resent {
+            Ok(_) => Ok(HttpResponse::Accepted().finish()),
+            Err(e) => {
+                log::error!("Failed to resend verification email: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn change(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        change: web::Json<EmailChangeRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        log::info!("Changing email of user {}", user_id);
+        let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+        LockoutService::require_unlocked(&mut conn, &auth.email, client_ip.as_deref()).await?;
+
+        let requested = EmailVerificationService::request_change(
+            &mut conn,
+            user_id,
+            change.into_inner(),
+            client_ip.as_deref(),
+            &app_config.auth,
+        )
+        .await;
+        match requested {
+            Ok(_) => Ok(HttpResponse::Accepted().finish()),
+            Err(e) => {
+                log::error!("Failed to change email: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::services::key_service::KeyRing;
+
+#[utoipa::path(
+responses(
+(status = 200, description = "Public keys used to verify tokens")
+)
+)]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(key_ring: web::Data<KeyRing>) -> impl Responder {
+    HttpResponse::Ok().json(key_ring.jwks())
+}
@@ -0,0 +1,102 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::schemas::key_schemas::SigningKeyCreate;
+use crate::services::key_service::{KeyRing, KeyService};
+use crate::services::permission_extractor::{KeysManage, RequirePermission};
+
+pub struct KeyRoutes;
+
+impl KeyRoutes {
+    pub async fn list(
+        pool: web::Data<DbPool>,
+        _: RequirePermission<KeysManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let keys = KeyService::list(&mut conn).await;
+        match keys {
+            Ok(keys) => Ok(HttpResponse::Ok().json(keys)),
+            Err(e) => {
+                log::error!("Failed to list signing keys: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn create(
+        pool: web::Data<DbPool>,
+        key: web::Json<SigningKeyCreate>,
+        key_ring: web::Data<KeyRing>,
+        _: RequirePermission<KeysManage>,
+    ) -> Result<impl Responder, AppError> {
+        log::info!("Creating {} signing key", key.algorithm);
+        let mut conn = get_connection(&pool).await;
+
+        let created_key = KeyService::generate(&mut conn, key.into_inner()).await;
+        match created_key {
+            Ok(created_key) => {
+                Self::reload(&mut conn, &key_ring).await;
+                Ok(HttpResponse::Ok().json(created_key))
+            }
+            Err(e) => {
+                log::error!("Failed to create signing key: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn promote(
+        pool: web::Data<DbPool>,
+        kid: web::Path<String>,
+        key_ring: web::Data<KeyRing>,
+        _: RequirePermission<KeysManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let kid = kid.into_inner();
+        log::info!("Promoting signing key: {}", &kid);
+
+        let promoted_key = KeyService::promote(&mut conn, &kid).await;
+        match promoted_key {
+            Ok(promoted_key) => {
+                Self::reload(&mut conn, &key_ring).await;
+                Ok(HttpResponse::Ok().json(promoted_key))
+            }
+            Err(e) => {
+                log::error!("Failed to promote signing key: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn retire(
+        pool: web::Data<DbPool>,
+        kid: web::Path<String>,
+        key_ring: web::Data<KeyRing>,
+        _: RequirePermission<KeysManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let kid = kid.into_inner();
+        log::info!("Retiring signing key: {}", &kid);
+
+        let retired_key = KeyService::retire(&mut conn, &kid).await;
+        match retired_key {
+            Ok(retired_key) => {
+                Self::reload(&mut conn, &key_ring).await;
+                Ok(HttpResponse::Ok().json(retired_key))
+            }
+            Err(e) => {
+                log::error!("Failed to retire signing key: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn reload(conn: &mut diesel_async::AsyncPgConnection, key_ring: &KeyRing) {
+        if let Err(e) = KeyService::reload(conn, key_ring).await {
+            log::error!("Failed to reload signing keys: {}", e);
+        }
+    }
+}
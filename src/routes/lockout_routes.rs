@@ -0,0 +1,92 @@
+use actix_web::{web, HttpResponse, Responder};
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::schema::users;
+use crate::services::lockout_service::{LockoutService, SCOPE_ACCOUNT, SCOPE_IP};
+use crate::services::permission_extractor::{LockoutsManage, RequirePermission};
+
+pub struct LockoutRoutes;
+
+impl LockoutRoutes {
+    pub async fn list(
+        pool: web::Data<DbPool>,
+        _: RequirePermission<LockoutsManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let lockouts = LockoutService::list(&mut conn).await;
+        match lockouts {
+            Ok(lockouts) => Ok(HttpResponse::Ok().json(lockouts)),
+            Err(e) => {
+                log::error!("Failed to list lockouts: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn unlock_user(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        auth: RequirePermission<LockoutsManage>,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        let mut conn = get_connection(&pool).await;
+
+        let email = users::table
+            .find(user_id)
+            .select(users::email)
+            .get_result::<String>(&mut conn)
+            .await;
+        let unlocked = match email {
+            Ok(email) => {
+                LockoutService::unlock(
+                    &mut conn,
+                    SCOPE_ACCOUNT,
+                    &LockoutService::account_key(&email),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match unlocked {
+            Ok(_) => {
+                log::warn!("User {} unlocked the account of user {}", auth.id, user_id);
+                Ok(HttpResponse::NoContent().finish())
+            }
+            Err(diesel::result::Error::NotFound) => {
+                Err(AppError::NotFound("Not Found".to_string()))
+            }
+            Err(e) => {
+                log::error!("Failed to unlock user: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn unlock_ip(
+        pool: web::Data<DbPool>,
+        ip: web::Path<String>,
+        auth: RequirePermission<LockoutsManage>,
+    ) -> Result<impl Responder, AppError> {
+        let ip = ip.into_inner();
+        let mut conn = get_connection(&pool).await;
+
+        let unlocked = LockoutService::unlock(&mut conn, SCOPE_IP, &ip).await;
+        match unlocked {
+            Ok(0) => Err(AppError::NotFound("Not Found".to_string())),
+            Ok(_) => {
+                log::warn!("User {} unlocked the address {}", auth.id, ip);
+                Ok(HttpResponse::NoContent().finish())
+            }
+            Err(e) => {
+                log::error!("Failed to unlock address: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
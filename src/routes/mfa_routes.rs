@@ -0,0 +1,175 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::configs::common::ApplicationConfig;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::models::user_model::UserModel;
+use crate::schema::users;
+use crate::schemas::mfa_schemas::{
+    MfaCodeRequest, MfaEnrollRequest, MfaVerifyRequest, ReauthenticationRequest,
+    RecoveryCodesResponse,
+};
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::mfa_service::MfaService;
+use crate::services::ownership_service::OwnershipService;
+
+pub struct MfaRoutes;
+
+impl MfaRoutes {
+    pub async fn verify(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        verify: web::Json<MfaVerifyRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+
+        let token = MfaService::verify(
+            &mut conn,
+            verify.into_inner(),
+            client_ip.as_deref(),
+            &app_config.auth,
+            &key_ring,
+        )
+        .await?;
+        Ok(HttpResponse::Ok().json(token))
+    }
+
+    pub async fn enroll_with_challenge(
+        pool: web::Data<DbPool>,
+        enroll: web::Json<MfaEnrollRequest>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let enrollment =
+            MfaService::enroll_with_challenge(&mut conn, enroll.into_inner(), &app_config.auth)
+                .await?;
+        Ok(HttpResponse::Ok().json(enrollment))
+    }
+
+    pub async fn enroll(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        log::info!("Enrolling TOTP factor for user {}", user_id);
+        let mut conn = get_connection(&pool).await;
+
+        let user = users::table
+            .find(user_id)
+            .get_result::<UserModel>(&mut conn)
+            .await;
+        let enrollment = match user {
+            Ok(user) => MfaService::enroll(&mut conn, &user, &app_config.auth).await,
+            Err(e) => Err(AppError::from(e)),
+        };
+        match enrollment {
+            Ok(enrollment) => Ok(HttpResponse::Ok().json(enrollment)),
+            Err(e) => {
+                log::error!("Failed to enroll TOTP factor: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn confirm(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        confirm: web::Json<MfaCodeRequest>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        let mut conn = get_connection(&pool).await;
+
+        let recovery_codes = MfaService::confirm(&mut conn, user_id, &confirm.code).await;
+        match recovery_codes {
+            Ok(recovery_codes) => {
+                Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+            }
+            Err(e) => {
+                log::error!("Failed to confirm TOTP factor: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn disable(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        reauth: web::Json<ReauthenticationRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        log::info!("Disabling TOTP factor of user {}", user_id);
+        let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+        MfaService::reauthenticate(
+            &mut conn,
+            user_id,
+            &reauth,
+            client_ip.as_deref(),
+            &app_config.auth,
+        )
+        .await?;
+
+        let disabled = MfaService::disable(&mut conn, user_id).await;
+        match disabled {
+            Ok(0) => Err(AppError::NotFound("Not Found".to_string())),
+            Ok(_) => Ok(HttpResponse::NoContent().finish()),
+            Err(e) => {
+                log::error!("Failed to disable TOTP factor: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn recovery_codes(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        reauth: web::Json<ReauthenticationRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        log::info!("Regenerating recovery codes of user {}", user_id);
+        let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+        MfaService::reauthenticate(
+            &mut conn,
+            user_id,
+            &reauth,
+            client_ip.as_deref(),
+            &app_config.auth,
+        )
+        .await?;
+
+        let recovery_codes = MfaService::regenerate_recovery_codes(&mut conn, user_id).await;
+        match recovery_codes {
+            Ok(recovery_codes) => {
+                Ok(HttpResponse::Ok().json(RecoveryCodesResponse { recovery_codes }))
+            }
+            Err(e) => {
+                log::error!("Failed to regenerate recovery codes: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
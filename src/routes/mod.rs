@@ -1,8 +1,19 @@
 pub mod auth_routes;
 pub mod class_routes;
+pub mod email_routes;
 pub mod health_routes;
+pub mod jwks_routes;
+pub mod key_routes;
+pub mod lockout_routes;
+pub mod mfa_routes;
+pub mod oauth_client_routes;
+pub mod oauth_routes;
+pub mod oidc_routes;
 pub mod password_routes;
+pub mod permission_routes;
+pub mod role_routes;
 pub mod schedule_routes;
 pub mod school_routes;
 pub mod student_routes;
 pub mod user_routes;
+pub mod webauthn_routes;
@@ -0,0 +1,90 @@
+use actix_web::{web, Responder};
+
+use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::interfaces::repository_interface::IRepository;
+use crate::repositories::oauth_client_repository::OAuthClientRepository;
+use crate::schemas::oauth_schemas::{OAuthClientCreate, OAuthClientUpdate};
+use crate::services::permission_extractor::{OAuthClientsManage, RequirePermission};
+
+pub struct OAuthClientRoutes;
+
+impl OAuthClientRoutes {
+    pub async fn create(
+        pool: web::Data<DbPool>,
+        client: web::Json<OAuthClientCreate>,
+        _: RequirePermission<OAuthClientsManage>,
+    ) -> Result<impl Responder, AppError> {
+        log::info!("Creating oauth client: {:?}", client.name);
+        let mut conn = get_connection(&pool).await;
+
+        let _client = OAuthClientRepository::create(&mut conn, client.into_inner()).await;
+        match _client {
+            Ok(_client) => Ok(actix_web::HttpResponse::Ok().json(_client)),
+            Err(e) => {
+                log::error!("Failed to create oauth client: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn get(
+        pool: web::Data<DbPool>,
+        id: web::Path<uuid::Uuid>,
+        _: RequirePermission<OAuthClientsManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        let client = OAuthClientRepository::get(&mut conn, &_id).await;
+
+        match client {
+            Ok(client) => Ok(actix_web::HttpResponse::Ok().json(client)),
+            Err(e) => {
+                log::error!("Failed to get oauth client: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn update(
+        pool: web::Data<DbPool>,
+        id: web::Path<uuid::Uuid>,
+        client: web::Json<OAuthClientUpdate>,
+        _: RequirePermission<OAuthClientsManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        log::info!("Updating oauth client: {:?}", &_id);
+
+        let updated_client =
+            OAuthClientRepository::update(&mut conn, &_id, client.into_inner()).await;
+        match updated_client {
+            Ok(updated_client) => Ok(actix_web::HttpResponse::Ok().json(updated_client)),
+            Err(e) => {
+                log::error!("Failed to update oauth client: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn delete(
+        pool: web::Data<DbPool>,
+        id: web::Path<uuid::Uuid>,
+        _: RequirePermission<OAuthClientsManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        log::info!("Deleting oauth client: {:?}", &_id);
+
+        let deleted_client = OAuthClientRepository::delete(&mut conn, &_id).await;
+        match deleted_client {
+            Ok(deleted_client) => Ok(actix_web::HttpResponse::Ok().json(deleted_client)),
+            Err(e) => {
+                log::error!("Failed to delete oauth client: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
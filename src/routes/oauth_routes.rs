@@ -0,0 +1,233 @@
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::configs::common::ApplicationConfig;
+use crate::helper::exceptions::{AppError, OAuthError};
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::schemas::auth_schemas::LoginRequest;
+use crate::schemas::oauth_schemas::{
+    AuthorizeRequest, OAuthErrorResponse, TokenActionRequest, TokenRequest,
+};
+use crate::services::auth_service::AuthService;
+use crate::services::email_verification_service::EmailVerificationService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::mfa_service::MfaService;
+use crate::services::oauth_service::OAuthService;
+use crate::services::revocation_service::RevocationCache;
+
+pub struct OAuthRoutes;
+
+impl OAuthRoutes {
+    pub async fn authorize(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        authorize: web::Form<AuthorizeRequest>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let authorize = authorize.into_inner();
+        log::info!("Authorizing client: {:?}", authorize.client_id);
+        let mut conn = get_connection(&pool).await;
+
+        // Unknown clients and redirect URIs are never redirected to
+        let client =
+            OAuthService::find_client(&mut conn, &authorize.client_id, &authorize.redirect_uri)
+                .await;
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                log::error!("Failed to authorize client: {}", e);
+                return Ok(Self::error_response(e));
+            }
+        };
+
+        let login_request = LoginRequest {
+            email: authorize.email.clone(),
+            password: authorize.password.clone(),
+        };
+        let client_ip = LockoutService::client_ip(&req);
+        LockoutService::require_unlocked(&mut conn, &login_request.email, client_ip.as_deref())
+            .await?;
+        let user = match AuthService::verify_credentials(
+            &mut conn,
+            &login_request,
+            client_ip.as_deref(),
+            &app_config.auth,
+        )
+        .await
+        {
+            Ok(user) => user,
+            Err(e) => {
+                log::error!("Failed to authorize client: {}", e);
+                return Err(e);
+            }
+        };
+        EmailVerificationService::check_login(&user, &app_config.auth)?;
+        match MfaService::check_login(&mut conn, &user, authorize.mfa_code.as_deref()).await {
+            Ok(true) => {
+                LockoutService::record_success(&mut conn, &user.email).await?;
+            }
+            Ok(false) => {
+                log::error!("Failed to authorize client: invalid MFA code");
+                LockoutService::record_failure(
+                    &mut conn,
+                    &user.email,
+                    client_ip.as_deref(),
+                    &app_config.auth,
+                )
+                .await?;
+                return Err(AppError::Unauthorized("Unauthorized".to_string()));
+            }
+            Err(e) => {
+                log::error!("Failed to authorize client: {}", e);
+                return Err(AppError::from(e));
+            }
+        }
+
+        let code =
+            OAuthService::authorize(&mut conn, &client, &user, &authorize, &app_config.auth).await;
+        let mut params = match &code {
+            Ok(code) => vec![("code", code.as_str())],
+            Err(e) => {
+                log::error!("Failed to authorize client: {}", e);
+                vec![("error", e.code()), ("error_description", e.description())]
+            }
+        };
+        if let Some(state) = &authorize.state {
+            params.push(("state", state));
+        }
+        Ok(HttpResponse::Found()
+            .insert_header((
+                header::LOCATION,
+                OAuthService::redirect_location(&authorize.redirect_uri, &params),
+            ))
+            .finish())
+    }
+
+    pub async fn token(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        token: web::Form<TokenRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut token = token.into_inner();
+        if let Some((client_id, client_secret)) = Self::basic_credentials(&req) {
+            token.client_id = Some(client_id);
+            token.client_secret = Some(client_secret);
+        }
+        let mut conn = get_connection(&pool).await;
+
+        let tokens = OAuthService::token(&mut conn, token, &app_config.auth, &key_ring).await;
+        match tokens {
+            Ok(tokens) => Ok(HttpResponse::Ok()
+                .insert_header((header::CACHE_CONTROL, "no-store"))
+                .json(tokens)),
+            Err(e) => {
+                log::error!("Failed to issue token: {}", e);
+                Ok(Self::error_response(e))
+            }
+        }
+    }
+
+    pub async fn introspect(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        introspection: web::Form<TokenActionRequest>,
+        revocation_cache: web::Data<RevocationCache>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut introspection = introspection.into_inner();
+        if let Some((client_id, client_secret)) = Self::basic_credentials(&req) {
+            introspection.client_id = Some(client_id);
+            introspection.client_secret = Some(client_secret);
+        }
+        let mut conn = get_connection(&pool).await;
+
+        let introspected = OAuthService::introspect(
+            &mut conn,
+            &revocation_cache,
+            introspection,
+            &app_config.auth,
+            &key_ring,
+        )
+        .await;
+        match introspected {
+            Ok(introspected) => Ok(HttpResponse::Ok()
+                .insert_header((header::CACHE_CONTROL, "no-store"))
+                .json(introspected)),
+            Err(e) => {
+                log::error!("Failed to introspect token: {}", e);
+                Ok(Self::error_response(e))
+            }
+        }
+    }
+
+    pub async fn revoke(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        revocation: web::Form<TokenActionRequest>,
+        revocation_cache: web::Data<RevocationCache>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut revocation = revocation.into_inner();
+        if let Some((client_id, client_secret)) = Self::basic_credentials(&req) {
+            revocation.client_id = Some(client_id);
+            revocation.client_secret = Some(client_secret);
+        }
+        let mut conn = get_connection(&pool).await;
+
+        let revoked = OAuthService::revoke(
+            &mut conn,
+            &revocation_cache,
+            revocation,
+            &app_config.auth,
+            &key_ring,
+        )
+        .await;
+        match revoked {
+            Ok(_) => Ok(HttpResponse::Ok().finish()),
+            Err(e) => {
+                log::error!("Failed to revoke token: {}", e);
+                Ok(Self::error_response(e))
+            }
+        }
+    }
+
+    /**
+     * Extracts client credentials sent with HTTP Basic authentication
+     *
+     * @param req: &HttpRequest
+     * @return Option<(String, String)>
+     */
+    fn basic_credentials(req: &HttpRequest) -> Option<(String, String)> {
+        let auth_header = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+        let encoded = auth_header.strip_prefix("Basic ")?;
+        let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+        let (client_id, client_secret) = decoded.split_once(':')?;
+        Some((client_id.to_string(), client_secret.to_string()))
+    }
+
+    fn error_response(error: OAuthError) -> HttpResponse {
+        let mut response = match error {
+            OAuthError::InvalidClient(_) => HttpResponse::Unauthorized(),
+            OAuthError::ServerError(_) => HttpResponse::InternalServerError(),
+            _ => HttpResponse::BadRequest(),
+        };
+        let description = match error {
+            OAuthError::ServerError(_) => None,
+            _ => Some(error.description().to_string()),
+        };
+        response
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .json(OAuthErrorResponse {
+                error: error.code().to_string(),
+                error_description: description,
+            })
+    }
+}
@@ -0,0 +1,51 @@
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::configs::common::ApplicationConfig;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::key_service::KeyRing;
+use crate::services::oidc_service::OidcService;
+
+#[utoipa::path(
+responses(
+(status = 200, description = "OpenID Connect discovery document")
+)
+)]
+#[get("/.well-known/openid-configuration")]
+pub async fn openid_configuration(
+    app_config: web::Data<ApplicationConfig>,
+    key_ring: web::Data<KeyRing>,
+) -> impl Responder {
+    HttpResponse::Ok().json(OidcService::configuration(&app_config.auth, &key_ring))
+}
+
+pub struct OidcRoutes;
+
+impl OidcRoutes {
+    pub async fn userinfo(
+        pool: web::Data<DbPool>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        if auth.client_id.is_some() {
+            log::error!("Client {} has no user info", auth.id);
+            return Err(AppError::Forbidden("Forbidden".to_string()));
+        }
+        let scope = auth.scopes.join(" ");
+        if !OidcService::has_scope(&scope, "openid") {
+            log::error!("Token of {} is missing the openid scope", auth.id);
+            return Err(AppError::Forbidden("insufficient_scope".to_string()));
+        }
+        let mut conn = get_connection(&pool).await;
+
+        let user_info = OidcService::user_info(&mut conn, auth.id, &scope).await;
+        match user_info {
+            Ok(user_info) => Ok(HttpResponse::Ok().json(user_info)),
+            Err(e) => {
+                log::error!("Failed to get user info: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
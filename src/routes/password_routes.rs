@@ -1,40 +1,117 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
 
+use crate::configs::common::ApplicationConfig;
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
-use crate::schemas::user_schemas::PasswordUpdate;
+use crate::schema::users;
+use crate::schemas::user_schemas::{PasswordForgotRequest, PasswordResetRequest, PasswordUpdate};
 use crate::services::auth_extractor::AuthExtractorService;
-use crate::services::password_service::PasswordService;
+use crate::services::lockout_service::LockoutService;
+use crate::services::ownership_service::OwnershipService;
+use crate::services::password_policy_service::PasswordPolicyService;
+use crate::services::password_service::{PasswordChange, PasswordService};
+use crate::services::revocation_service::RevocationCache;
 
 pub struct PasswordRoutes;
 
 impl PasswordRoutes {
     pub async fn update(
+        req: HttpRequest,
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
         password: web::Json<PasswordUpdate>,
-        _: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
-        let mut conn = get_connection(&pool).await;
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
         let _id = id.into_inner();
+        OwnershipService::require_user(&auth, _id)?;
+        let mut conn = get_connection(&pool).await;
         log::info!("Updating password for user: {:?}", &_id);
+        let email = users::table
+            .find(_id)
+            .select(users::email)
+            .get_result::<String>(&mut conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to get user: {}", e);
+                AppError::NotFound("Not Found".to_string())
+            })?;
+        let client_ip = LockoutService::client_ip(&req);
+        LockoutService::require_unlocked(&mut conn, &email, client_ip.as_deref()).await?;
+        PasswordPolicyService::require_valid(&password.new_password, Some(&email))?;
 
         let updated_password = PasswordService::update_password(
             &mut conn,
             &Identifier::Id(_id),
             password.into_inner(),
+            client_ip.as_deref(),
+            &app_config.auth,
         )
         .await;
 
         match updated_password {
-            Ok(_) => {
+            Ok(PasswordChange::Changed) => {
                 log::info!("Password updated for user: {:?}", &_id);
                 Ok(HttpResponse::Ok().finish())
             }
+            Ok(PasswordChange::Rejected(violations)) => {
+                Err(PasswordPolicyService::reject(violations))
+            }
             Err(e) => {
                 log::error!("Failed to update password: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn forgot(
+        pool: web::Data<DbPool>,
+        forgot: web::Json<PasswordForgotRequest>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let requested =
+            PasswordService::forgot_password(&mut conn, forgot.into_inner(), &app_config.auth)
+                .await;
+        match requested {
+            Ok(_) => Ok(HttpResponse::Accepted().finish()),
+            Err(e) => {
+                log::error!("Failed to start password reset: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn reset(
+        pool: web::Data<DbPool>,
+        reset: web::Json<PasswordResetRequest>,
+        revocation_cache: web::Data<RevocationCache>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let user = PasswordService::reset_token_user(&mut conn, &reset.token).await?;
+        PasswordPolicyService::require_valid(&reset.new_password, Some(&user.email))?;
+
+        let reset_password = PasswordService::reset_password(
+            &mut conn,
+            &revocation_cache,
+            reset.into_inner(),
+            &app_config.auth,
+        )
+        .await;
+        match reset_password {
+            Ok(PasswordChange::Changed) => Ok(HttpResponse::Ok().finish()),
+            Ok(PasswordChange::Rejected(violations)) => {
+                Err(PasswordPolicyService::reject(violations))
+            }
+            Err(e) => {
+                log::error!("Failed to reset password: {}", e);
+                Err(e)
             }
         }
     }
@@ -0,0 +1,108 @@
+use actix_web::{web, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::interfaces::repository_interface::IRepository;
+use crate::repositories::permission_repository::PermissionRepository;
+use crate::schemas::role_schemas::{PermissionCreate, PermissionUpdate};
+use crate::services::permission_extractor::{RequirePermission, RolesManage};
+use crate::services::role_service::RoleService;
+
+pub struct PermissionRoutes;
+
+impl PermissionRoutes {
+    pub async fn list(
+        pool: web::Data<DbPool>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let permissions = RoleService::list_permissions(&mut conn).await;
+        match permissions {
+            Ok(permissions) => Ok(HttpResponse::Ok().json(permissions)),
+            Err(e) => {
+                log::error!("Failed to list permissions: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn create(
+        pool: web::Data<DbPool>,
+        permission: web::Json<PermissionCreate>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        log::info!("Creating permission: {:?}", permission.name);
+        let mut conn = get_connection(&pool).await;
+
+        let _permission = PermissionRepository::create(&mut conn, permission.into_inner()).await;
+        match _permission {
+            Ok(_permission) => Ok(HttpResponse::Ok().json(_permission)),
+            Err(e) => {
+                log::error!("Failed to create permission: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn get(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        let permission = PermissionRepository::get(&mut conn, &_id).await;
+
+        match permission {
+            Ok(permission) => Ok(HttpResponse::Ok().json(permission)),
+            Err(e) => {
+                log::error!("Failed to get permission: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn update(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        permission: web::Json<PermissionUpdate>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        log::info!("Updating permission: {:?}", &_id);
+
+        let updated_permission =
+            PermissionRepository::update(&mut conn, &_id, permission.into_inner()).await;
+        match updated_permission {
+            Ok(updated_permission) => Ok(HttpResponse::Ok().json(updated_permission)),
+            Err(e) => {
+                log::error!("Failed to update permission: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn delete(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        log::info!("Deleting permission: {:?}", &_id);
+
+        let deleted_permission = PermissionRepository::delete(&mut conn, &_id).await;
+        match deleted_permission {
+            Ok(deleted_permission) => Ok(HttpResponse::Ok().json(deleted_permission)),
+            Err(e) => {
+                log::error!("Failed to delete permission: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
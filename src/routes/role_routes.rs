@@ -0,0 +1,223 @@
+use actix_web::{web, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::interfaces::repository_interface::IRepository;
+use crate::repositories::role_repository::RoleRepository;
+use crate::schemas::role_schemas::{RoleCreate, RoleUpdate};
+use crate::services::permission_extractor::{RequirePermission, RolesManage};
+use crate::services::role_service::RoleService;
+
+pub struct RoleRoutes;
+
+impl RoleRoutes {
+    pub async fn list(
+        pool: web::Data<DbPool>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let roles = RoleService::list_roles(&mut conn).await;
+        match roles {
+            Ok(roles) => Ok(HttpResponse::Ok().json(roles)),
+            Err(e) => {
+                log::error!("Failed to list roles: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn create(
+        pool: web::Data<DbPool>,
+        role: web::Json<RoleCreate>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        log::info!("Creating role: {:?}", role.name);
+        let mut conn = get_connection(&pool).await;
+
+        let _role = RoleRepository::create(&mut conn, role.into_inner()).await;
+        match _role {
+            Ok(_role) => Ok(HttpResponse::Ok().json(_role)),
+            Err(e) => {
+                log::error!("Failed to create role: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn get(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        let role = RoleRepository::get(&mut conn, &_id).await;
+
+        match role {
+            Ok(role) => Ok(HttpResponse::Ok().json(role)),
+            Err(e) => {
+                log::error!("Failed to get role: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn update(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        role: web::Json<RoleUpdate>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        log::info!("Updating role: {:?}", &_id);
+
+        let updated_role = RoleRepository::update(&mut conn, &_id, role.into_inner()).await;
+        match updated_role {
+            Ok(updated_role) => Ok(HttpResponse::Ok().json(updated_role)),
+            Err(e) => {
+                log::error!("Failed to update role: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn delete(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let _id = Identifier::Id(id.into_inner());
+        log::info!("Deleting role: {:?}", &_id);
+
+        let deleted_role = RoleRepository::delete(&mut conn, &_id).await;
+        match deleted_role {
+            Ok(deleted_role) => Ok(HttpResponse::Ok().json(deleted_role)),
+            Err(e) => {
+                log::error!("Failed to delete role: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn permissions(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let permissions = RoleService::role_permissions(&mut conn, id.into_inner()).await;
+        match permissions {
+            Ok(permissions) => Ok(HttpResponse::Ok().json(permissions)),
+            Err(e) => {
+                log::error!("Failed to list role permissions: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn grant_permission(
+        pool: web::Data<DbPool>,
+        path: web::Path<(Uuid, Uuid)>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let (role_id, permission_id) = path.into_inner();
+        log::info!("Granting permission {} to role {}", permission_id, role_id);
+
+        let granted = RoleService::grant_permission(&mut conn, role_id, permission_id).await;
+        match granted {
+            Ok(_) => Ok(HttpResponse::NoContent().finish()),
+            Err(e) => {
+                log::error!("Failed to grant permission: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn revoke_permission(
+        pool: web::Data<DbPool>,
+        path: web::Path<(Uuid, Uuid)>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let (role_id, permission_id) = path.into_inner();
+        log::info!(
+            "Revoking permission {} from role {}",
+            permission_id,
+            role_id
+        );
+
+        let revoked = RoleService::revoke_permission(&mut conn, role_id, permission_id).await;
+        match revoked {
+            Ok(0) => Err(AppError::NotFound("Not Found".to_string())),
+            Ok(_) => Ok(HttpResponse::NoContent().finish()),
+            Err(e) => {
+                log::error!("Failed to revoke permission: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn user_roles(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let roles = RoleService::user_roles(&mut conn, id.into_inner()).await;
+        match roles {
+            Ok(roles) => Ok(HttpResponse::Ok().json(roles)),
+            Err(e) => {
+                log::error!("Failed to list user roles: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn assign(
+        pool: web::Data<DbPool>,
+        path: web::Path<(Uuid, Uuid)>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let (user_id, role_id) = path.into_inner();
+        log::info!("Assigning role {} to user {}", role_id, user_id);
+
+        let assigned = RoleService::assign_role(&mut conn, user_id, role_id).await;
+        match assigned {
+            Ok(_) => Ok(HttpResponse::NoContent().finish()),
+            Err(e) => {
+                log::error!("Failed to assign role: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn unassign(
+        pool: web::Data<DbPool>,
+        path: web::Path<(Uuid, Uuid)>,
+        _: RequirePermission<RolesManage>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let (user_id, role_id) = path.into_inner();
+        log::info!("Removing role {} from user {}", role_id, user_id);
+
+        let removed = RoleService::unassign_role(&mut conn, user_id, role_id).await;
+        match removed {
+            Ok(0) => Err(AppError::NotFound("Not Found".to_string())),
+            Ok(_) => Ok(HttpResponse::NoContent().finish()),
+            Err(e) => {
+                log::error!("Failed to remove role: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use actix_web::web;
 
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::repositories::schedule_repository::ScheduleRepository;
+use crate::services::ownership_service::OwnershipService;
+use crate::services::scope_extractor::{RequireScope, SchedulesRead, SchedulesWrite};
 
 pub struct ScheduleRoutes;
 
@@ -12,18 +15,22 @@ impl ScheduleRoutes {
     pub async fn create(
         pool: web::Data<DbPool>,
         schedule: web::Json<crate::schemas::schedule_schemas::ScheduleCreate>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<SchedulesWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         log::info!(
             "Creating new schedule for student: {:?}",
             schedule.student_id
         );
         let mut conn = get_connection(&pool).await;
-        let schedule = ScheduleRepository::create(&mut conn, schedule.into_inner()).await;
+        OwnershipService::require_student(&mut conn, &auth, schedule.student_id).await?;
+        OwnershipService::require_class(&mut conn, &auth, schedule.class_id).await?;
+        let schedule =
+            ScheduleRepository::create(&mut conn, &auth.tenant(), schedule.into_inner()).await;
         match schedule {
             Ok(schedule) => Ok(actix_web::HttpResponse::Ok().json(schedule)),
             Err(e) => {
                 log::error!("Failed to create schedule: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -31,15 +38,18 @@ impl ScheduleRoutes {
     pub async fn get(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<SchedulesRead>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let id = Identifier::Id(id.into_inner());
-        let schedule = ScheduleRepository::get(&mut conn, &id).await;
+        let id = id.into_inner();
+        OwnershipService::require_schedule(&mut conn, &auth, id).await?;
+        let id = Identifier::Id(id);
+        let schedule = ScheduleRepository::get(&mut conn, &auth.tenant(), &id).await;
         match schedule {
             Ok(schedule) => Ok(actix_web::HttpResponse::Ok().json(schedule)),
             Err(e) => {
                 log::error!("Failed to get schedule: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -48,17 +58,23 @@ impl ScheduleRoutes {
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
         schedule: web::Json<crate::schemas::schedule_schemas::ScheduleUpdate>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<SchedulesWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let id = Identifier::Id(id.into_inner());
+        let id = id.into_inner();
+        OwnershipService::require_schedule(&mut conn, &auth, id).await?;
+        // The schedule must stay with a student and class of the caller
+        OwnershipService::require_student(&mut conn, &auth, schedule.student_id).await?;
+        OwnershipService::require_class(&mut conn, &auth, schedule.class_id).await?;
+        let id = Identifier::Id(id);
         log::info!("Updating schedule: {:?}", &id);
         let schedule = schedule.into_inner();
-        let schedule = ScheduleRepository::update(&mut conn, &id, schedule).await;
+        let schedule = ScheduleRepository::update(&mut conn, &auth.tenant(), &id, schedule).await;
         match schedule {
             Ok(schedule) => Ok(actix_web::HttpResponse::Ok().json(schedule)),
             Err(e) => {
                 log::error!("Failed to update schedule: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -66,16 +82,19 @@ impl ScheduleRoutes {
     pub async fn delete(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<SchedulesWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let id = Identifier::Id(id.into_inner());
+        let id = id.into_inner();
+        OwnershipService::require_schedule(&mut conn, &auth, id).await?;
+        let id = Identifier::Id(id);
         log::info!("Deleting schedule: {:?}", &id);
-        let deleted_schedule = ScheduleRepository::delete(&mut conn, &id).await;
+        let deleted_schedule = ScheduleRepository::delete(&mut conn, &auth.tenant(), &id).await;
         match deleted_schedule {
             Ok(schedule) => Ok(actix_web::HttpResponse::Ok().json(schedule)),
             Err(e) => {
                 log::error!("Failed to delete schedule: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -1,12 +1,13 @@
 use actix_web::{web, Responder};
 
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::repositories::school_repository::SchoolRepository;
 use crate::schemas::school_schemas::{SchoolCreate, SchoolUpdate};
-use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::scope_extractor::{RequireScope, SchoolsRead, SchoolsWrite};
 
 pub struct SchoolRoutes;
 
@@ -14,17 +15,18 @@ impl SchoolRoutes {
     pub async fn create(
         pool: web::Data<DbPool>,
         school: web::Json<SchoolCreate>,
-        _: AuthExtractorService,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<SchoolsWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         log::info!("Creating school: {:?}", school.name);
         let mut conn = get_connection(&pool).await;
 
-        let _school = SchoolRepository::create(&mut conn, school.into_inner()).await;
+        let _school =
+            SchoolRepository::create(&mut conn, &auth.tenant(), school.into_inner()).await;
         match _school {
             Ok(_school) => Ok(actix_web::HttpResponse::Ok().json(_school)),
             Err(e) => {
                 log::error!("Failed to create school: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -32,17 +34,17 @@ impl SchoolRoutes {
     pub async fn get(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-        _: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<SchoolsRead>,
+    ) -> Result<impl Responder, AppError> {
         let mut conn = get_connection(&pool).await;
         let _id = Identifier::Id(id.into_inner());
-        let school = SchoolRepository::get(&mut conn, &_id).await;
+        let school = SchoolRepository::get(&mut conn, &auth.tenant(), &_id).await;
 
         match school {
             Ok(school) => Ok(actix_web::HttpResponse::Ok().json(school)),
             Err(e) => {
                 log::error!("Failed to get school: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -51,20 +53,21 @@ impl SchoolRoutes {
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
         school: web::Json<SchoolUpdate>,
-        _: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<SchoolsWrite>,
+    ) -> Result<impl Responder, AppError> {
         let mut conn = get_connection(&pool).await;
         let _id = Identifier::Id(id.into_inner());
         log::info!("Updating school: {:?}", &_id);
 
         let school = school.into_inner();
-        let updated_school = SchoolRepository::update(&mut conn, &_id, school).await;
+        let updated_school =
+            SchoolRepository::update(&mut conn, &auth.tenant(), &_id, school).await;
 
         match updated_school {
             Ok(updated_school) => Ok(actix_web::HttpResponse::Ok().json(updated_school)),
             Err(e) => {
                 log::error!("Failed to update school: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -72,19 +75,19 @@ impl SchoolRoutes {
     pub async fn delete(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-        _: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
+        auth: RequireScope<SchoolsWrite>,
+    ) -> Result<impl Responder, AppError> {
         let mut conn = get_connection(&pool).await;
         let _id = Identifier::Id(id.into_inner());
         log::info!("Deleting school: {:?}", &_id);
 
-        let deleted_school = SchoolRepository::delete(&mut conn, &_id).await;
+        let deleted_school = SchoolRepository::delete(&mut conn, &auth.tenant(), &_id).await;
 
         match deleted_school {
             Ok(deleted_school) => Ok(actix_web::HttpResponse::Ok().json(deleted_school)),
             Err(e) => {
                 log::error!("Failed to delete school: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
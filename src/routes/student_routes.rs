@@ -2,11 +2,14 @@ use actix_web::web;
 use uuid::Uuid;
 
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
-use crate::interfaces::repository_interface::IRepository;
+use crate::interfaces::tenant_repository_interface::ITenantRepository;
 use crate::repositories::student_repository::StudentRepository;
 use crate::schemas::student_schemas::{StudentCreate, StudentUpdate};
+use crate::services::ownership_service::OwnershipService;
+use crate::services::scope_extractor::{RequireScope, StudentsRead, StudentsWrite};
 
 pub struct StudentRoutes;
 
@@ -14,31 +17,37 @@ impl StudentRoutes {
     pub async fn create(
         pool: web::Data<DbPool>,
         student: web::Json<StudentCreate>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<StudentsWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
+        OwnershipService::require_user(&auth, student.user_id)?;
         log::info!("Creating student account for user: {:?}", student.user_id);
         let mut conn = get_connection(&pool).await;
 
-        let student = StudentRepository::create(&mut conn, student.into_inner()).await;
+        let student =
+            StudentRepository::create(&mut conn, &auth.tenant(), student.into_inner()).await;
         match student {
             Ok(student) => Ok(actix_web::HttpResponse::Ok().json(student)),
             Err(e) => {
                 log::error!("Failed to create student: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
     pub async fn get(
         pool: web::Data<DbPool>,
         id: web::Path<Uuid>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<StudentsRead>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let _id = Identifier::Id(id.into_inner());
-        let student = StudentRepository::get(&mut conn, &_id).await;
+        let id = id.into_inner();
+        OwnershipService::require_student(&mut conn, &auth, id).await?;
+        let _id = Identifier::Id(id);
+        let student = StudentRepository::get(&mut conn, &auth.tenant(), &_id).await;
         match student {
             Ok(student) => Ok(actix_web::HttpResponse::Ok().json(student)),
             Err(e) => {
                 log::error!("Failed to get student: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -47,17 +56,20 @@ impl StudentRoutes {
         pool: web::Data<DbPool>,
         id: web::Path<Uuid>,
         student: web::Json<StudentUpdate>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<StudentsWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let _id = Identifier::Id(id.into_inner());
+        let id = id.into_inner();
+        OwnershipService::require_student(&mut conn, &auth, id).await?;
+        let _id = Identifier::Id(id);
         log::info!("Updating student: {:?}", &_id);
         let student = student.into_inner();
-        let student = StudentRepository::update(&mut conn, &_id, student).await;
+        let student = StudentRepository::update(&mut conn, &auth.tenant(), &_id, student).await;
         match student {
             Ok(student) => Ok(actix_web::HttpResponse::Ok().json(student)),
             Err(e) => {
                 log::error!("Failed to update student: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
@@ -65,16 +77,19 @@ impl StudentRoutes {
     pub async fn delete(
         pool: web::Data<DbPool>,
         id: web::Path<Uuid>,
-    ) -> actix_web::Result<impl actix_web::Responder> {
+        auth: RequireScope<StudentsWrite>,
+    ) -> Result<impl actix_web::Responder, AppError> {
         let mut conn = get_connection(&pool).await;
-        let _id = Identifier::Id(id.into_inner());
+        let id = id.into_inner();
+        OwnershipService::require_student(&mut conn, &auth, id).await?;
+        let _id = Identifier::Id(id);
         log::info!("Deleting student: {:?}", &_id);
-        let deleted_student = StudentRepository::delete(&mut conn, &_id).await;
+        let deleted_student = StudentRepository::delete(&mut conn, &auth.tenant(), &_id).await;
         match deleted_student {
             Ok(num) => Ok(actix_web::HttpResponse::Ok().json(num)),
             Err(e) => {
                 log::error!("Failed to delete student: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(e)
             }
         }
     }
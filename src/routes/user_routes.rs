@@ -1,13 +1,19 @@
 use actix_web::{HttpResponse, post, Responder, web};
-use utoipa;
 
+use crate::configs::common::ApplicationConfig;
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
 use crate::helper::type_alias::DbPool;
 use crate::helper::utils::get_connection;
 use crate::interfaces::repository_interface::IRepository;
 use crate::repositories::user_repository::UserRepository;
-use crate::schemas::user_schemas::{UserCreate, UserResponse, UserUpdate};
+use crate::schemas::user_schemas::{UserCreate, UserUpdate};
 use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::email_verification_service::EmailVerificationService;
+use crate::services::ownership_service::OwnershipService;
+use crate::services::password_policy_service::PasswordPolicyService;
+use crate::services::revocation_service::{RevocationCache, RevocationService};
+use crate::services::scope_extractor::{RequireScope, UsersRead, UsersWrite};
 
 pub struct UserRoutes;
 
@@ -16,6 +22,8 @@ post,
 request_body(content = UserCreate, description = "User to create", content_type = "application/json"),
 responses(
 (status = 200, description = "User created", body = UserResponse),
+(status = 403, description = "Only administrators can create administrators"),
+(status = 422, description = "Password does not meet the policy"),
 (status = 500, description = "Internal server error")
 )
 )]
@@ -23,15 +31,28 @@ responses(
 pub async fn create_user(
     user: web::Json<UserCreate>,
     pool: web::Data<DbPool>,
-) -> actix_web::Result<impl Responder> {
+    app_config: web::Data<ApplicationConfig>,
+    auth: Option<AuthExtractorService>,
+) -> Result<impl Responder, AppError> {
     log::info!("Creating user: {:?}", user.email);
+    OwnershipService::require_admin_grant(auth.as_ref(), user.is_admin)?;
+    PasswordPolicyService::require_valid(&user.password, Some(&user.email))?;
     let mut conn = get_connection(&pool).await;
     let _user = UserRepository::create(&mut conn, user.into_inner()).await;
     match _user {
-        Ok(_user) => Ok(HttpResponse::Ok().json(_user)),
+        Ok(_user) => {
+            // The account exists either way, the email can be sent again with /auth/email/resend
+            if let Err(e) =
+                EmailVerificationService::send(&mut conn, _user.id, &_user.email, &app_config.auth)
+                    .await
+            {
+                log::error!("Failed to send verification email: {}", e);
+            }
+            Ok(HttpResponse::Ok().json(_user))
+        }
         Err(e) => {
             log::error!("Failed to create user: {}", e);
-            Err(actix_web::error::ErrorInternalServerError(e))
+            Err(AppError::from(e))
         }
     }
 }
@@ -40,9 +61,11 @@ impl UserRoutes {
     pub async fn get(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-        _auth: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
-        let id = Identifier::Id(id.into_inner());
+        auth: RequireScope<UsersRead>,
+    ) -> Result<impl Responder, AppError> {
+        let id = id.into_inner();
+        OwnershipService::require_user(&auth, id)?;
+        let id = Identifier::Id(id);
         log::info!("Getting user: {:?}", id);
 
         let mut conn = get_connection(&pool).await;
@@ -51,7 +74,7 @@ impl UserRoutes {
             Ok(_user) => Ok(HttpResponse::Ok().json(_user)),
             Err(e) => {
                 log::error!("Failed to get user: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(AppError::from(e))
             }
         }
     }
@@ -60,20 +83,30 @@ impl UserRoutes {
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
         user: web::Json<UserUpdate>,
-        _auth: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
-        let mut conn = get_connection(&pool).await;
+        revocation_cache: web::Data<RevocationCache>,
+        auth: RequireScope<UsersWrite>,
+    ) -> Result<impl Responder, AppError> {
         let _id = id.into_inner();
+        OwnershipService::require_user(&auth, _id)?;
+        let user = user.into_inner();
+        OwnershipService::require_admin_grant(Some(&*auth), user.is_admin)?;
+        let mut conn = get_connection(&pool).await;
         log::info!("Updating user: {:?}", &_id);
 
-        let user = user.into_inner();
         let updated_user = UserRepository::update(&mut conn, &Identifier::Id(_id), user).await;
 
         match updated_user {
-            Ok(_user) => Ok(HttpResponse::Ok().json(_user)),
+            Ok(_user) => {
+                if !_user.is_active {
+                    RevocationService::revoke_user(&mut conn, &revocation_cache, _user.id)
+                        .await
+                        .map_err(AppError::from)?;
+                }
+                Ok(HttpResponse::Ok().json(_user))
+            }
             Err(e) => {
                 log::error!("Failed to update user: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(AppError::from(e))
             }
         }
     }
@@ -81,12 +114,17 @@ impl UserRoutes {
     pub async fn delete(
         pool: web::Data<DbPool>,
         id: web::Path<uuid::Uuid>,
-        _auth: AuthExtractorService,
-    ) -> actix_web::Result<impl Responder> {
-        let mut conn = get_connection(&pool).await;
+        revocation_cache: web::Data<RevocationCache>,
+        auth: RequireScope<UsersWrite>,
+    ) -> Result<impl Responder, AppError> {
         let _id = id.into_inner();
+        OwnershipService::require_user(&auth, _id)?;
+        let mut conn = get_connection(&pool).await;
         log::info!("Deleting user: {:?}", &_id);
 
+        RevocationService::revoke_user(&mut conn, &revocation_cache, _id)
+            .await
+            .map_err(AppError::from)?;
         let deletion_count = UserRepository::delete(&mut conn, &Identifier::Id(_id)).await;
         log::info!("deletion_count: {:?}", deletion_count);
         match deletion_count {
@@ -99,7 +137,7 @@ impl UserRoutes {
             }
             Err(e) => {
                 log::error!("Failed to delete user: {}", e);
-                Err(actix_web::error::ErrorInternalServerError(e))
+                Err(AppError::from(e))
             }
         }
     }
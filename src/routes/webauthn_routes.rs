@@ -0,0 +1,222 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use crate::configs::common::ApplicationConfig;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::get_connection;
+use crate::models::user_model::UserModel;
+use crate::schema::users;
+use crate::schemas::mfa_schemas::ReauthenticationRequest;
+use crate::schemas::webauthn_schemas::{
+    AssertionRequest, RegistrationRequest, WebAuthnLoginOptionsRequest, WebAuthnMfaOptionsRequest,
+    WebAuthnMfaVerifyRequest,
+};
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::mfa_service::MfaService;
+use crate::services::ownership_service::OwnershipService;
+use crate::services::webauthn_service::WebAuthnService;
+
+pub struct WebAuthnRoutes;
+
+impl WebAuthnRoutes {
+    pub async fn registration_options(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        let mut conn = get_connection(&pool).await;
+
+        let user = users::table
+            .find(user_id)
+            .get_result::<UserModel>(&mut conn)
+            .await;
+        let options = match user {
+            Ok(user) => {
+                WebAuthnService::registration_options(&mut conn, &user, &app_config.auth).await
+            }
+            Err(e) => Err(e),
+        };
+        match options {
+            Ok(options) => Ok(HttpResponse::Ok().json(options)),
+            Err(e) => {
+                log::error!("Failed to start WebAuthn registration: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn register(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        registration: web::Json<RegistrationRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        log::info!("Registering WebAuthn credential for user {}", user_id);
+        let mut conn = get_connection(&pool).await;
+
+        let credential = WebAuthnService::register(
+            &mut conn,
+            user_id,
+            registration.into_inner(),
+            &app_config.auth,
+        )
+        .await;
+        match credential {
+            Ok(credential) => Ok(HttpResponse::Ok().json(credential)),
+            Err(e) => {
+                log::error!("Failed to register WebAuthn credential: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn list(
+        pool: web::Data<DbPool>,
+        id: web::Path<Uuid>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let user_id = id.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        let mut conn = get_connection(&pool).await;
+
+        let credentials = WebAuthnService::list_credentials(&mut conn, user_id).await;
+        match credentials {
+            Ok(credentials) => Ok(HttpResponse::Ok().json(credentials)),
+            Err(e) => {
+                log::error!("Failed to list WebAuthn credentials: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn delete(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        path: web::Path<(Uuid, Uuid)>,
+        reauth: web::Json<ReauthenticationRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        auth: AuthExtractorService,
+    ) -> Result<impl Responder, AppError> {
+        let (user_id, id) = path.into_inner();
+        OwnershipService::require_user(&auth, user_id)?;
+        log::info!("Deleting WebAuthn credential {} of user {}", id, user_id);
+        let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+        MfaService::reauthenticate(
+            &mut conn,
+            user_id,
+            &reauth,
+            client_ip.as_deref(),
+            &app_config.auth,
+        )
+        .await?;
+
+        let deleted = WebAuthnService::delete_credential(&mut conn, user_id, id).await;
+        match deleted {
+            Ok(0) => Err(AppError::NotFound("Not Found".to_string())),
+            Ok(_) => Ok(HttpResponse::NoContent().finish()),
+            Err(e) => {
+                log::error!("Failed to delete WebAuthn credential: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn login_options(
+        pool: web::Data<DbPool>,
+        options: web::Json<WebAuthnLoginOptionsRequest>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let options =
+            WebAuthnService::login_options(&mut conn, options.into_inner(), &app_config.auth).await;
+        match options {
+            Ok(options) => Ok(HttpResponse::Ok().json(options)),
+            Err(e) => {
+                log::error!("Failed to start WebAuthn login: {}", e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+
+    pub async fn login(
+        pool: web::Data<DbPool>,
+        assertion: web::Json<AssertionRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let token = WebAuthnService::login(
+            &mut conn,
+            assertion.into_inner(),
+            &app_config.auth,
+            &key_ring,
+        )
+        .await;
+        match token {
+            Ok(token) => Ok(HttpResponse::Ok().json(token)),
+            Err(e) => {
+                log::error!("Failed to login with WebAuthn: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn mfa_options(
+        pool: web::Data<DbPool>,
+        options: web::Json<WebAuthnMfaOptionsRequest>,
+        app_config: web::Data<ApplicationConfig>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+
+        let options =
+            WebAuthnService::mfa_options(&mut conn, options.into_inner(), &app_config.auth).await;
+        match options {
+            Ok(options) => Ok(HttpResponse::Ok().json(options)),
+            Err(e) => {
+                log::error!("Failed to start WebAuthn MFA: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn verify_mfa(
+        req: HttpRequest,
+        pool: web::Data<DbPool>,
+        verify: web::Json<WebAuthnMfaVerifyRequest>,
+        app_config: web::Data<ApplicationConfig>,
+        key_ring: web::Data<KeyRing>,
+    ) -> Result<impl Responder, AppError> {
+        let mut conn = get_connection(&pool).await;
+        let client_ip = LockoutService::client_ip(&req);
+
+        let token = WebAuthnService::verify_mfa(
+            &mut conn,
+            verify.into_inner(),
+            client_ip.as_deref(),
+            &app_config.auth,
+            &key_ring,
+        )
+        .await;
+        match token {
+            Ok(token) => Ok(HttpResponse::Ok().json(token)),
+            Err(e) => {
+                log::error!("Failed to verify WebAuthn MFA: {}", e);
+                Err(e)
+            }
+        }
+    }
+}
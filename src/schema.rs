@@ -1,5 +1,22 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    authorization_codes (id) {
+        id -> Uuid,
+        code_hash -> Varchar,
+        client_id -> Uuid,
+        user_id -> Uuid,
+        redirect_uri -> Varchar,
+        code_challenge -> Varchar,
+        code_challenge_method -> Varchar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        scope -> Varchar,
+        nonce -> Nullable<Varchar>,
+    }
+}
+
 diesel::table! {
     classes (id) {
         id -> Uuid,
@@ -10,6 +27,161 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    email_verification_tokens (id) {
+        id -> Uuid,
+        token_hash -> Varchar,
+        user_id -> Uuid,
+        email -> Varchar,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    login_lockouts (id) {
+        id -> Uuid,
+        scope -> Varchar,
+        key -> Varchar,
+        failures -> Int4,
+        last_failure_at -> Timestamp,
+        locked_until -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    mail_outbox (id) {
+        id -> Uuid,
+        recipient -> Varchar,
+        subject -> Varchar,
+        text_body -> Text,
+        html_body -> Nullable<Text>,
+        attempts -> Int4,
+        last_error -> Nullable<Varchar>,
+        next_attempt_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+        failed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    mfa_challenges (id) {
+        id -> Uuid,
+        token_hash -> Varchar,
+        user_id -> Uuid,
+        attempts -> Int4,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oauth_clients (id) {
+        id -> Uuid,
+        name -> Varchar,
+        redirect_uris -> Array<Text>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        client_secret -> Nullable<Varchar>,
+        scopes -> Array<Text>,
+        tenant_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    password_history (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        password_hash -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    password_reset_tokens (id) {
+        id -> Uuid,
+        token_hash -> Varchar,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    permissions (id) {
+        id -> Uuid,
+        name -> Varchar,
+        description -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    rate_limit_buckets (key) {
+        key -> Varchar,
+        tokens -> Float8,
+        allowed -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    recovery_codes (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        code_hash -> Varchar,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    refresh_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        family_id -> Uuid,
+        token_hash -> Varchar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        scope -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    revoked_tokens (jti) {
+        jti -> Uuid,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        revoked_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    role_permissions (role_id, permission_id) {
+        role_id -> Uuid,
+        permission_id -> Uuid,
+    }
+}
+
+diesel::table! {
+    roles (id) {
+        id -> Uuid,
+        name -> Varchar,
+        description -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        mfa_required -> Bool,
+    }
+}
+
 diesel::table! {
     schedules (id) {
         id -> Uuid,
@@ -33,6 +205,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    signing_keys (kid) {
+        kid -> Varchar,
+        algorithm -> Varchar,
+        private_key -> Text,
+        created_at -> Timestamp,
+        activated_at -> Nullable<Timestamp>,
+        retired_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     students (id) {
         id -> Uuid,
@@ -47,6 +230,30 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    totp_factors (user_id) {
+        user_id -> Uuid,
+        secret -> Varchar,
+        confirmed_at -> Nullable<Timestamp>,
+        last_used_step -> Nullable<Int8>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_roles (user_id, role_id) {
+        user_id -> Uuid,
+        role_id -> Uuid,
+    }
+}
+
+diesel::table! {
+    user_token_revocations (user_id) {
+        user_id -> Uuid,
+        revoked_before -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Uuid,
@@ -56,13 +263,84 @@ diesel::table! {
         is_admin -> Bool,
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        email_verified_at -> Nullable<Timestamp>,
+        password_changed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webauthn_challenges (id) {
+        id -> Uuid,
+        challenge_hash -> Varchar,
+        user_id -> Nullable<Uuid>,
+        ceremony -> Varchar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webauthn_credentials (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        credential_id -> Varchar,
+        public_key -> Varchar,
+        sign_count -> Int8,
+        transports -> Nullable<Varchar>,
+        name -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
     }
 }
 
+diesel::joinable!(authorization_codes -> oauth_clients (client_id));
+diesel::joinable!(authorization_codes -> users (user_id));
 diesel::joinable!(classes -> students (student_id));
+diesel::joinable!(email_verification_tokens -> users (user_id));
+diesel::joinable!(mfa_challenges -> users (user_id));
+diesel::joinable!(oauth_clients -> schools (tenant_id));
+diesel::joinable!(password_history -> users (user_id));
+diesel::joinable!(password_reset_tokens -> users (user_id));
+diesel::joinable!(recovery_codes -> users (user_id));
+diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(role_permissions -> permissions (permission_id));
+diesel::joinable!(role_permissions -> roles (role_id));
 diesel::joinable!(schedules -> classes (class_id));
 diesel::joinable!(schedules -> students (student_id));
 diesel::joinable!(students -> schools (school_id));
 diesel::joinable!(students -> users (user_id));
+diesel::joinable!(totp_factors -> users (user_id));
+diesel::joinable!(user_roles -> roles (role_id));
+diesel::joinable!(user_roles -> users (user_id));
+diesel::joinable!(webauthn_challenges -> users (user_id));
+diesel::joinable!(webauthn_credentials -> users (user_id));
 
-diesel::allow_tables_to_appear_in_same_query!(classes, schedules, schools, students, users,);
+diesel::allow_tables_to_appear_in_same_query!(
+    authorization_codes,
+    classes,
+    email_verification_tokens,
+    login_lockouts,
+    mail_outbox,
+    mfa_challenges,
+    oauth_clients,
+    password_history,
+    password_reset_tokens,
+    permissions,
+    rate_limit_buckets,
+    recovery_codes,
+    refresh_tokens,
+    revoked_tokens,
+    role_permissions,
+    roles,
+    schedules,
+    schools,
+    signing_keys,
+    students,
+    totp_factors,
+    user_roles,
+    user_token_revocations,
+    users,
+    webauthn_challenges,
+    webauthn_credentials,
+);
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::schemas::mfa_schemas::MfaChallengeResponse;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginRequest {
     pub email: String,
@@ -10,15 +12,56 @@ pub struct LoginRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
+    pub scope: String,
+    /// Only returned once, when the login confirmed a TOTP enrollment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
+    /// Set when the password is older than `password_max_age_days`. The token then carries no
+    /// scope and no permission, so it can do little else than change the password.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_change_required: bool,
+}
+
+/// Result of a password login: either tokens or a challenge to complete with a second factor
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Tokens(LoginResponse),
+    MfaChallenge(MfaChallengeResponse),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
+    pub jti: Uuid,
+    pub iss: String,
+    pub aud: Vec<String>,
     pub exp: i64,
+    pub nbf: i64,
     pub iat: i64,
     pub sub: Uuid,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub email: String,
     pub tenant_id: Option<Uuid>,
     pub admin: bool,
     pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<Uuid>,
+    /// OAuth client a user token was issued to, the authorized party of OpenID Connect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azp: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
 }
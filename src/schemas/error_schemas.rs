@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::password_policy_service::PasswordViolationDetail;
+
+/**
+ * Body of the error responses, as defined in RFC 7807, with the stable `code` of the error and
+ * the violations of a rejected password
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violations: Option<Vec<PasswordViolationDetail>>,
+}
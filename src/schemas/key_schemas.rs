@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SigningKeyResponse {
+    pub kid: String,
+    pub algorithm: String,
+    pub active: bool,
+    pub created_at: NaiveDateTime,
+    pub activated_at: Option<NaiveDateTime>,
+    pub retired_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SigningKeyCreate {
+    pub algorithm: String,
+    pub private_key: Option<String>,
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub challenge_token: String,
+    pub expires_in: i64,
+    /// The user has no confirmed factor yet and must enroll through `/auth/mfa/enroll`
+    pub enrollment_required: bool,
+    /// Factors the challenge can be completed with: `totp`, `webauthn`
+    pub methods: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaVerifyRequest {
+    pub challenge_token: String,
+    /// A TOTP code or one of the recovery codes
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaEnrollRequest {
+    pub challenge_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+/// Confirms the identity of the user before a change to their second factors
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReauthenticationRequest {
+    /// Required without a confirmed TOTP factor
+    pub password: Option<String>,
+    /// A TOTP code or one of the recovery codes, required with a confirmed TOTP factor
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpEnrollmentResponse {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
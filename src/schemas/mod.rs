@@ -1,6 +1,13 @@
 pub mod auth_schemas;
 pub mod class_schema;
+pub mod error_schemas;
+pub mod key_schemas;
+pub mod mfa_schemas;
+pub mod oauth_schemas;
+pub mod oidc_schemas;
+pub mod role_schemas;
 pub mod schedule_schemas;
 pub mod school_schemas;
 pub mod student_schemas;
 pub mod user_schemas;
+pub mod webauthn_schemas;
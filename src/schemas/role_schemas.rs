@@ -0,0 +1,49 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub mfa_required: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleCreate {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mfa_required: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleUpdate {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mfa_required: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PermissionResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PermissionCreate {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PermissionUpdate {
+    pub description: Option<String>,
+}
@@ -11,6 +11,7 @@ pub struct UserResponse {
     pub is_admin: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    pub email_verified_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -27,8 +28,35 @@ pub struct PasswordUpdate {
     pub new_password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordForgotRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordResetRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserUpdate {
     pub is_active: bool,
     pub is_admin: bool,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerifyRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailResendRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailChangeRequest {
+    pub new_email: String,
+    pub password: String,
+}
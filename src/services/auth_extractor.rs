@@ -1,36 +1,74 @@
 use std::future::{ready, Future};
 use std::pin::Pin;
+use std::time::Duration;
 
 use actix_web::http::header::HeaderValue;
-use actix_web::{http, web, FromRequest, HttpRequest};
+use actix_web::{http, web, FromRequest, HttpMessage, HttpRequest};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::configs::common::ApplicationConfig;
-
+use crate::helper::enums::Tenant;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::services::key_service::{KeyRing, KeyService};
+use crate::services::revocation_service::{RevocationCache, RevocationService};
 use crate::services::token_service::TokenService;
 
-#[derive(Debug, Serialize, Deserialize)]
+const KEY_RELOAD_MIN_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthExtractorService {
+    pub jti: Uuid,
+    pub exp: i64,
     pub id: Uuid,
     pub tenant_id: Option<Uuid>,
     pub email: String,
     pub admin: bool,
     pub active: bool,
+    /// Set when the token was issued to an OAuth client rather than a user
+    pub client_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    /// Permissions granted by the roles of the user when the token was issued
+    pub permissions: Vec<String>,
 }
 
 impl AuthExtractorService {
+    /**
+     * Checks whether the roles of the principal grant the given permission
+     *
+     * @param permission: &str
+     * @return bool
+     */
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+
+    /**
+     * Returns the tenant the principal acts within. Administrators are not limited to a tenant,
+     * client tokens act within the school of their client
+     *
+     * @return Tenant
+     */
+    pub fn tenant(&self) -> Tenant {
+        match (self.admin, self.tenant_id) {
+            (true, _) => Tenant::All,
+            (false, Some(tenant_id)) => Tenant::School(tenant_id),
+            (false, None) => Tenant::Unassigned,
+        }
+    }
+
     /**
      * Extracts the token from the request header
      *
      * @param req: &HttpRequest
-     * @return Result<String, actix_web::Error>
+     * @return Result<String, AppError>
      */
-    fn extract_token(req: &HttpRequest) -> Result<String, actix_web::Error> {
+    fn extract_token(req: &HttpRequest) -> Result<String, AppError> {
         let auth_header: Option<&HeaderValue> = req.headers().get(http::header::AUTHORIZATION);
         if auth_header.is_none() {
             log::error!("No authorization header found");
-            return Err(actix_web::error::ErrorUnauthorized("Unauthorized"));
+            return Err(AppError::Unauthorized("Missing bearer token".to_string()));
         }
         let token = auth_header
             .unwrap()
@@ -40,7 +78,7 @@ impl AuthExtractorService {
             .replace("Bearer ", "");
         if token.is_empty() {
             log::error!("No token found");
-            return Err(actix_web::error::ErrorUnauthorized("Unauthorized"));
+            return Err(AppError::Unauthorized("Missing bearer token".to_string()));
         }
         Ok(token)
     }
@@ -65,38 +103,95 @@ impl FromRequest for AuthExtractorService {
      * @return Self::Future
      */
     fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        // Already authenticated by `AuthMiddleware`
+        if let Some(auth) = req.extensions().get::<AuthExtractorService>() {
+            return Box::pin(ready(Ok(auth.clone())));
+        }
         let start = std::time::Instant::now();
         let token = match Self::extract_token(req) {
             Ok(token) => token,
-            Err(e) => return Box::pin(ready(Err(e))),
+            Err(e) => return Box::pin(ready(Err(e.into()))),
         };
 
-        let data = match req.app_data::<web::Data<ApplicationConfig>>() {
-            Some(data) => data,
-            None => {
-                log::error!("No application data found");
-                return Box::pin(ready(Err(actix_web::error::ErrorInternalServerError(
-                    "Internal Server Error",
-                ))));
+        let (key_ring, configs) = match (
+            req.app_data::<web::Data<KeyRing>>(),
+            req.app_data::<web::Data<ApplicationConfig>>(),
+        ) {
+            (Some(key_ring), Some(configs)) => (key_ring.clone(), configs.clone()),
+            _ => {
+                log::error!("No key ring found");
+                return Box::pin(ready(Err(AppError::Internal(
+                    "No key ring found".to_string(),
+                )
+                .into())));
             }
         };
 
-        let auth_config = data.auth.clone();
+        let (pool, revocation_cache) = match (
+            req.app_data::<web::Data<DbPool>>(),
+            req.app_data::<web::Data<RevocationCache>>(),
+        ) {
+            (Some(pool), Some(revocation_cache)) => (pool.clone(), revocation_cache.clone()),
+            _ => {
+                log::error!("No revocation store found");
+                return Box::pin(ready(Err(AppError::Internal(
+                    "No revocation store found".to_string(),
+                )
+                .into())));
+            }
+        };
 
         Box::pin(async move {
-            let token_claims = TokenService::decode(&token, &auth_config).await;
+            let mut conn = pool.get().await.map_err(|e| {
+                log::error!("Failed to get pool: {}", e);
+                AppError::Internal(e.to_string())
+            })?;
+            // The key may have been generated by another worker since our last reload
+            if TokenService::find_key(&token, &key_ring).is_err() {
+                if let Err(e) =
+                    KeyService::reload_if_stale(&mut conn, &key_ring, KEY_RELOAD_MIN_AGE).await
+                {
+                    log::error!("Failed to reload signing keys: {}", e);
+                }
+            }
+            let token_claims = TokenService::decode(&token, &key_ring, &configs.auth).await;
             match token_claims {
                 Ok(claims) => {
+                    let revoked = RevocationService::is_revoked(
+                        &mut conn,
+                        &revocation_cache,
+                        claims.jti,
+                        claims.sub,
+                        claims.iat,
+                    )
+                    .await
+                    .map_err(|e| {
+                        log::error!("Failed to check token revocation: {}", e);
+                        AppError::from(e)
+                    })?;
                     log::debug!(
                         "Authentication Elapsed time: {:?}ms",
                         start.elapsed().as_millis()
                     );
+                    if revoked {
+                        return Err(
+                            AppError::Unauthorized("Token has been revoked".to_string()).into()
+                        );
+                    }
                     Ok(AuthExtractorService {
+                        jti: claims.jti,
+                        exp: claims.exp,
                         id: claims.sub,
                         tenant_id: claims.tenant_id,
                         email: claims.email,
                         admin: claims.admin,
                         active: claims.active,
+                        client_id: claims.client_id,
+                        scopes: claims
+                            .scope
+                            .map(|scope| scope.split_whitespace().map(String::from).collect())
+                            .unwrap_or_default(),
+                        permissions: claims.permissions,
                     })
                 }
                 Err(e) => {
@@ -105,9 +200,47 @@ impl FromRequest for AuthExtractorService {
                         start.elapsed().as_millis()
                     );
                     log::error!("Failed to authenticate token: {}", e);
-                    return Err(actix_web::error::ErrorUnauthorized("Unauthorized"));
+                    Err(AppError::Unauthorized("Invalid or expired token".to_string()).into())
                 }
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tenant() {
+        let school_id = Uuid::new_v4();
+        let client_id = Uuid::new_v4();
+        let client = AuthExtractorService {
+            jti: Uuid::new_v4(),
+            exp: 0,
+            id: client_id,
+            tenant_id: Some(school_id),
+            email: String::new(),
+            admin: false,
+            active: true,
+            client_id: Some(client_id),
+            scopes: vec!["schools:read".to_string()],
+            permissions: Vec::new(),
+        };
+        assert_eq!(client.tenant(), Tenant::School(school_id));
+        assert_eq!(client.tenant().school_ids(), Some(vec![school_id]));
+
+        let unassigned = AuthExtractorService {
+            tenant_id: None,
+            ..client.clone()
+        };
+        assert_eq!(unassigned.tenant(), Tenant::Unassigned);
+
+        let admin = AuthExtractorService {
+            admin: true,
+            client_id: None,
+            ..client
+        };
+        assert_eq!(admin.tenant(), Tenant::All);
+    }
+}
@@ -1,67 +1,388 @@
 use chrono::Duration;
 use diesel::result::Error;
 use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
 use diesel::QueryDsl;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
 
 use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
 
 use crate::models::user_model::UserModel;
-use crate::schema::users;
-use crate::schemas::auth_schemas::{LoginRequest, LoginResponse, TokenClaims};
+use crate::schema::{students, users};
+use crate::schemas::auth_schemas::{
+    LoginOutcome, LoginRequest, LoginResponse, LogoutRequest, RefreshRequest, TokenClaims,
+};
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::email_verification_service::EmailVerificationService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::mfa_service::MfaService;
 use crate::services::password_service::PasswordService;
+use crate::services::refresh_token_service::RefreshTokenService;
+use crate::services::revocation_service::{RevocationCache, RevocationService};
+use crate::services::role_service::RoleService;
+use crate::services::scope_service::ScopeService;
 use crate::services::token_service::TokenService;
 
 pub struct AuthService;
 
 impl AuthService {
+    /**
+     * Checks the credentials of a user and issues tokens. Users with a confirmed second factor,
+     * or with a role requiring one, get an MFA challenge to complete at `/auth/mfa/verify`.
+     * Unverified accounts are rejected when `email_verification_required` is set. A password hash
+     * made with an outdated algorithm or outdated parameters is replaced once it has matched.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param login_request: LoginRequest
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginOutcome, AppError>
+     */
     pub async fn login(
         conn: &mut AsyncPgConnection,
         login_request: LoginRequest,
+        client_ip: Option<&str>,
         auth_config: &AuthConfig,
-    ) -> Result<LoginResponse, Error> {
+        key_ring: &KeyRing,
+    ) -> Result<LoginOutcome, AppError> {
+        let user = Self::verify_credentials(conn, &login_request, client_ip, auth_config).await?;
+        let user = PasswordService::rehash_if_needed(conn, user, &login_request.password).await?;
+        EmailVerificationService::check_login(&user, auth_config)?;
+        if MfaService::is_required(conn, &user).await? {
+            let challenge = MfaService::challenge(conn, &user, auth_config).await?;
+            return Ok(LoginOutcome::MfaChallenge(challenge));
+        }
+        LockoutService::record_success(conn, &user.email).await?;
+        let tokens = Self::issue_tokens(conn, user, None, None, auth_config, key_ring).await?;
+        Ok(LoginOutcome::Tokens(tokens))
+    }
+
+    /**
+     * Checks the credentials of a user, who must be active. Failures count towards the lockout of
+     * the account and of the client address, which callers check first with
+     * `LockoutService::require_unlocked`.
+     * Callers clear the failures with `LockoutService::record_success` once the second factor
+     * passed too, so that wrong MFA codes count towards the same lockout.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param login_request: &LoginRequest
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @return Result<UserModel, AppError>
+     */
+    pub async fn verify_credentials(
+        conn: &mut AsyncPgConnection,
+        login_request: &LoginRequest,
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+    ) -> Result<UserModel, AppError> {
         let user = users::table
             .filter(users::email.eq(&login_request.email))
             .get_result::<UserModel>(conn)
-            .await;
+            .await
+            .optional();
 
         match user {
-            Ok(_user) => {
+            Ok(Some(_user))
+                if PasswordService::verify(&login_request.password, &_user.password) =>
+            {
                 log::info!("User found: {}", &login_request.email);
-                if !PasswordService::verify(&login_request.password.to_string(), &_user.password) {
-                    log::error!("Wrong credentials for user {}", &login_request.email);
-                    return Err(Error::NotFound);
-                }
-                let creation_time = chrono::Utc::now().timestamp();
-                let expiration_time = chrono::Utc::now().timestamp()
-                    + Duration::minutes(auth_config.token_expire_minutes).num_seconds();
-
-                let _token = TokenService::encode(
-                    &auth_config.secret_key,
-                    TokenClaims {
-                        exp: expiration_time,
-                        iat: creation_time,
-                        sub: _user.id,
-                        email: _user.email,
-                        tenant_id: None, // @TODO: Replace when Student table implemented
-                        admin: _user.is_admin,
-                        active: _user.is_active,
-                    },
-                )
-                .await;
-
-                match _token {
-                    Err(_e) => {
-                        log::error!("Failed to encode payload: {}", _e);
-                        return Err(Error::NotFound);
-                    }
-                    Ok(tok) => Ok(LoginResponse { token: tok }),
-                }
+                Self::require_active(&_user)?;
+                Ok(_user)
+            }
+            Ok(_) => {
+                log::error!("Wrong credentials for user {}", &login_request.email);
+                LockoutService::record_failure(conn, &login_request.email, client_ip, auth_config)
+                    .await?;
+                Err(AppError::Unauthorized("Unauthorized".to_string()))
             }
             Err(_e) => {
                 log::error!("Failed to get user: {}", _e);
-                return Err(_e);
+                Err(AppError::from(_e))
             }
         }
     }
+
+    /**
+     * Rejects users who have been deactivated
+     *
+     * @param user: &UserModel
+     * @return Result<(), AppError>
+     */
+    pub fn require_active(user: &UserModel) -> Result<(), AppError> {
+        if !user.is_active {
+            log::error!("User {} is not active", user.id);
+            return Err(AppError::Unauthorized("Unauthorized".to_string()));
+        }
+        Ok(())
+    }
+
+    /**
+     * Exchanges a refresh token for a new access and refresh token pair
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param refresh_request: RefreshRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginResponse, AppError>
+     */
+    pub async fn refresh(
+        conn: &mut AsyncPgConnection,
+        refresh_request: RefreshRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<LoginResponse, AppError> {
+        let consumed = RefreshTokenService::consume(conn, &refresh_request.refresh_token).await?;
+        let user = users::table
+            .find(consumed.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        if let Err(e) = Self::require_active(&user) {
+            RefreshTokenService::revoke_family(conn, consumed.family_id).await?;
+            return Err(e);
+        }
+        Self::issue_tokens(
+            conn,
+            user,
+            Some(consumed.family_id),
+            consumed.scope,
+            auth_config,
+            key_ring,
+        )
+        .await
+    }
+
+    /**
+     * Revokes the access token of the current session and, if given, its refresh token
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param revocation_cache: &RevocationCache
+     * @param auth: &AuthExtractorService
+     * @param logout_request: Option<LogoutRequest>
+     * @return Result<(), Error>
+     */
+    pub async fn logout(
+        conn: &mut AsyncPgConnection,
+        revocation_cache: &RevocationCache,
+        auth: &AuthExtractorService,
+        logout_request: Option<LogoutRequest>,
+    ) -> Result<(), Error> {
+        RevocationService::revoke_token(conn, revocation_cache, auth.jti, auth.id, auth.exp)
+            .await?;
+        if let Some(refresh_token) = logout_request.and_then(|request| request.refresh_token) {
+            RefreshTokenService::revoke(conn, &refresh_token, auth.id).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Issues an access token and a refresh token for the user. The access token scope is the
+     * requested scope limited to what the user is entitled to, and tokens of a family started by
+     * an OAuth client name that client as their authorized party. Once the password has expired,
+     * the access token carries no scope, no permission, no admin rights and no tenant until the
+     * password is changed.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: UserModel
+     * @param family_id: Option<Uuid>
+     * @param requested_scope: Option<String>
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginResponse, AppError>
+     */
+    pub async fn issue_tokens(
+        conn: &mut AsyncPgConnection,
+        user: UserModel,
+        family_id: Option<Uuid>,
+        requested_scope: Option<String>,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<LoginResponse, AppError> {
+        let password_change_required = PasswordService::is_expired(&user, auth_config);
+        let (permissions, scope, tenant_id) = if password_change_required {
+            log::warn!("Password of user {} has expired", user.id);
+            (Vec::new(), String::new(), None)
+        } else {
+            let permissions = RoleService::user_permissions(conn, &user).await?;
+            let scope = ScopeService::grant(
+                requested_scope.as_deref(),
+                &ScopeService::entitled_scopes(&permissions),
+            )
+            .join(" ");
+            let tenant_id = Self::tenant_of(conn, user.id).await?;
+            (permissions, scope, tenant_id)
+        };
+        let authorized_party = match family_id {
+            Some(family_id) => RefreshTokenService::client_of(conn, family_id).await?,
+            None => None,
+        };
+        let user_id = user.id;
+        let claims = Self::claims(
+            user,
+            authorized_party,
+            tenant_id,
+            permissions,
+            &scope,
+            password_change_required,
+            auth_config,
+        );
+
+        let _token = TokenService::encode(&key_ring.active(), claims).await;
+
+        match _token {
+            Err(_e) => {
+                log::error!("Failed to encode payload: {}", _e);
+                Err(AppError::Internal(format!(
+                    "Failed to encode payload: {}",
+                    _e
+                )))
+            }
+            Ok(tok) => {
+                let refresh_token = RefreshTokenService::issue(
+                    conn,
+                    user_id,
+                    family_id,
+                    requested_scope,
+                    auth_config,
+                )
+                .await?;
+                Ok(LoginResponse {
+                    token: tok,
+                    refresh_token,
+                    scope,
+                    recovery_codes: None,
+                    password_change_required,
+                })
+            }
+        }
+    }
+
+    /**
+     * Builds the claims of the access token of a user, issued to the authorized party when it
+     * comes from an OAuth client. A restricted token, issued while the password has expired,
+     * grants neither admin rights nor a tenant.
+     *
+     * @param user: UserModel
+     * @param authorized_party: Option<Uuid>
+     * @param tenant_id: Option<Uuid>
+     * @param permissions: Vec<String>
+     * @param scope: &str
+     * @param restricted: bool
+     * @param auth_config: &AuthConfig
+     * @return TokenClaims
+     */
+    pub fn claims(
+        user: UserModel,
+        authorized_party: Option<Uuid>,
+        tenant_id: Option<Uuid>,
+        permissions: Vec<String>,
+        scope: &str,
+        restricted: bool,
+        auth_config: &AuthConfig,
+    ) -> TokenClaims {
+        let creation_time = chrono::Utc::now().timestamp();
+        let expiration_time =
+            creation_time + Duration::minutes(auth_config.token_expire_minutes).num_seconds();
+        TokenClaims {
+            jti: Uuid::new_v4(),
+            iss: auth_config.issuer.clone(),
+            aud: auth_config.audiences(),
+            exp: expiration_time,
+            nbf: creation_time,
+            iat: creation_time,
+            sub: user.id,
+            email: user.email,
+            tenant_id: tenant_id.filter(|_| !restricted),
+            admin: user.is_admin && !restricted,
+            active: user.is_active,
+            client_id: None,
+            azp: authorized_party,
+            scope: Some(scope.to_string()),
+            permissions: match restricted {
+                true => Vec::new(),
+                false => permissions,
+            },
+        }
+    }
+
+    /**
+     * Finds the tenant of a user, which is the school their student profile belongs to
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<Option<Uuid>, Error>
+     */
+    async fn tenant_of(conn: &mut AsyncPgConnection, user_id: Uuid) -> Result<Option<Uuid>, Error> {
+        students::table
+            .filter(students::user_id.eq(user_id))
+            .select(students::school_id)
+            .order(students::created_at)
+            .first::<Uuid>(conn)
+            .await
+            .optional()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_claims() {
+        let auth_config = AuthConfig {
+            token_expire_minutes: 10,
+            password_max_age_days: 90,
+            ..AuthConfig::default()
+        };
+        let tenant_id = Some(Uuid::new_v4());
+        let permissions = vec!["users:read".to_string()];
+        let admin = UserModel::new("admin@domain.com".to_string(), String::new(), true, true);
+
+        let claims = AuthService::claims(
+            admin,
+            None,
+            tenant_id,
+            permissions.clone(),
+            "users:read",
+            false,
+            &auth_config,
+        );
+        assert!(claims.admin);
+        assert_eq!(claims.tenant_id, tenant_id);
+        assert_eq!(claims.permissions, permissions);
+
+        let mut expired = UserModel::new("admin@domain.com".to_string(), String::new(), true, true);
+        expired.password_changed_at -= Duration::days(91);
+        let restricted = PasswordService::is_expired(&expired, &auth_config);
+        assert!(restricted);
+        let claims = AuthService::claims(
+            expired,
+            None,
+            tenant_id,
+            permissions,
+            "",
+            restricted,
+            &auth_config,
+        );
+        assert!(!claims.admin);
+        assert_eq!(claims.tenant_id, None);
+        assert!(claims.permissions.is_empty());
+        assert_eq!(claims.scope, Some(String::new()));
+    }
+
+    #[tokio::test]
+    async fn test_require_active() {
+        let mut user = UserModel::new("user@domain.com".to_string(), String::new(), true, false);
+        assert!(AuthService::require_active(&user).is_ok());
+
+        user.is_active = false;
+        assert_eq!(
+            AuthService::require_active(&user),
+            Err(AppError::Unauthorized("Unauthorized".to_string()))
+        );
+    }
 }
@@ -0,0 +1,286 @@
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
+use crate::models::email_verification_token_model::EmailVerificationTokenModel;
+use crate::models::user_model::UserModel;
+use crate::schema::{email_verification_tokens, users};
+use crate::schemas::user_schemas::{EmailChangeRequest, EmailResendRequest, EmailVerifyRequest};
+use crate::services::lockout_service::LockoutService;
+use crate::services::mail_template_service::{MailTemplate, MailTemplateService};
+use crate::services::outbox_service::OutboxService;
+use crate::services::password_service::PasswordService;
+use crate::services::refresh_token_service::RefreshTokenService;
+
+pub struct EmailVerificationService;
+
+impl EmailVerificationService {
+    /**
+     * Queues an email with a single-use token confirming `email` for the user. The address is
+     * the current one of the user after signup, or the requested one when changing it. Sending
+     * a new token invalidates the previous ones.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param email: &str
+     * @param auth_config: &AuthConfig
+     * @return Result<(), Error>
+     */
+    pub async fn send(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        email: &str,
+        auth_config: &AuthConfig,
+    ) -> Result<(), Error> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::update(
+            email_verification_tokens::table
+                .filter(email_verification_tokens::user_id.eq(user_id))
+                .filter(email_verification_tokens::used_at.is_null()),
+        )
+        .set(email_verification_tokens::used_at.eq(now))
+        .execute(conn)
+        .await?;
+        let token = RefreshTokenService::generate();
+        diesel::insert_into(email_verification_tokens::table)
+            .values(&EmailVerificationTokenModel::new(
+                RefreshTokenService::hash(&token),
+                user_id,
+                email.to_string(),
+                now + Duration::hours(auth_config.email_verification_expire_hours),
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create email verification token: {}", e);
+                e
+            })?;
+
+        OutboxService::enqueue(
+            conn,
+            MailTemplateService::render(
+                MailTemplate::EmailVerification,
+                email,
+                &[
+                    (
+                        "verify_url",
+                        format!("{}?token={}", auth_config.email_verification_url, token),
+                    ),
+                    (
+                        "expire_hours",
+                        auth_config.email_verification_expire_hours.to_string(),
+                    ),
+                ],
+            ),
+        )
+        .await
+    }
+
+    /**
+     * Sends a new verification email. Unknown, inactive and already verified accounts are
+     * ignored silently, so the outcome never discloses whether an account exists.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param resend_request: EmailResendRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<(), Error>
+     */
+    pub async fn resend(
+        conn: &mut AsyncPgConnection,
+        resend_request: EmailResendRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<(), Error> {
+        let user = users::table
+            .filter(users::email.eq(&resend_request.email))
+            .get_result::<UserModel>(conn)
+            .await
+            .optional()?;
+        match user {
+            Some(user) if user.is_active && user.email_verified_at.is_none() => {
+                Self::send(conn, user.id, &user.email, auth_config).await
+            }
+            _ => {
+                log::info!("Verification requested for an unknown, inactive or verified account");
+                Ok(())
+            }
+        }
+    }
+
+    /**
+     * Starts an email change. The password of the user is required and counts towards the
+     * lockout like a login. The new address only replaces the current one once it is confirmed
+     * with the token mailed to it.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param change_request: EmailChangeRequest
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @return Result<(), AppError>
+     */
+    pub async fn request_change(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        change_request: EmailChangeRequest,
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+    ) -> Result<(), AppError> {
+        let user = users::table
+            .find(user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        if !PasswordService::verify(&change_request.password, &user.password) {
+            log::error!("Wrong credentials for user {}", user.email);
+            LockoutService::record_failure(conn, &user.email, client_ip, auth_config).await?;
+            return Err(AppError::Unauthorized("Wrong credentials".to_string()));
+        }
+        if change_request.new_email == user.email {
+            return Err(AppError::Validation(
+                "New email is the current email".to_string(),
+            ));
+        }
+        Self::require_available(conn, &change_request.new_email, user.id).await?;
+        Ok(Self::send(conn, user.id, &change_request.new_email, auth_config).await?)
+    }
+
+    /**
+     * Confirms an email address with a verification token. When the token was issued for a new
+     * address, it replaces the current one, which is notified of the change.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param verify_request: EmailVerifyRequest
+     * @return Result<UserModel, AppError>
+     */
+    pub async fn verify(
+        conn: &mut AsyncPgConnection,
+        verify_request: EmailVerifyRequest,
+    ) -> Result<UserModel, AppError> {
+        let now = chrono::Utc::now().naive_utc();
+        let token = email_verification_tokens::table
+            .filter(
+                email_verification_tokens::token_hash
+                    .eq(RefreshTokenService::hash(&verify_request.token)),
+            )
+            .filter(email_verification_tokens::used_at.is_null())
+            .filter(email_verification_tokens::expires_at.gt(now))
+            .get_result::<EmailVerificationTokenModel>(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| {
+                log::error!("Invalid email verification token");
+                AppError::Validation("Invalid or expired token".to_string())
+            })?;
+        let user = users::table
+            .find(token.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        let changed = token.email != user.email;
+        if changed {
+            Self::require_available(conn, &token.email, user.id).await?;
+        }
+
+        let consumed = diesel::update(
+            email_verification_tokens::table
+                .find(token.id)
+                .filter(email_verification_tokens::used_at.is_null()),
+        )
+        .set(email_verification_tokens::used_at.eq(now))
+        .execute(conn)
+        .await?;
+        if consumed == 0 {
+            log::error!("Email verification token {} was already used", token.id);
+            return Err(AppError::Validation("Invalid or expired token".to_string()));
+        }
+
+        let verified = diesel::update(&user)
+            .set((
+                users::email.eq(&token.email),
+                users::email_verified_at.eq(now),
+                users::updated_at.eq(now),
+            ))
+            .get_result::<UserModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to verify email of user {}: {}", user.id, e);
+                e
+            })?;
+        if changed {
+            log::info!("User {} changed their email address", user.id);
+            OutboxService::enqueue(
+                conn,
+                MailTemplateService::render(
+                    MailTemplate::EmailChanged,
+                    &user.email,
+                    &[("new_email", token.email.clone())],
+                ),
+            )
+            .await?;
+        }
+        Ok(verified)
+    }
+
+    /**
+     * Rejects the login of an unverified account when `email_verification_required` is set
+     *
+     * @param user: &UserModel
+     * @param auth_config: &AuthConfig
+     * @return Result<(), AppError>
+     */
+    pub fn check_login(user: &UserModel, auth_config: &AuthConfig) -> Result<(), AppError> {
+        if auth_config.email_verification_required && user.email_verified_at.is_none() {
+            log::error!("Email address of user {} is not verified", user.id);
+            return Err(AppError::Forbidden(
+                "Email address is not verified".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn require_available(
+        conn: &mut AsyncPgConnection,
+        email: &str,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        let taken = users::table
+            .filter(users::email.eq(email))
+            .filter(users::id.ne(user_id))
+            .count()
+            .get_result::<i64>(conn)
+            .await?;
+        if taken > 0 {
+            log::error!("Email address is already used by another account");
+            return Err(AppError::Conflict("Email already in use".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_login() {
+        let mut user = UserModel::new("user@domain.com".to_string(), String::new(), true, false);
+        let auth_config = AuthConfig {
+            email_verification_required: true,
+            ..AuthConfig::default()
+        };
+        assert_eq!(
+            EmailVerificationService::check_login(&user, &auth_config),
+            Err(AppError::Forbidden(
+                "Email address is not verified".to_string()
+            ))
+        );
+        assert!(EmailVerificationService::check_login(&user, &AuthConfig::default()).is_ok());
+
+        user.email_verified_at = Some(chrono::Utc::now().naive_utc());
+        assert!(EmailVerificationService::check_login(&user, &auth_config).is_ok());
+    }
+}
@@ -0,0 +1,715 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use diesel::ExpressionMethods;
+use diesel::PgSortExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+    EllipticCurveKeyType, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType,
+    PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use rand::RngCore;
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair, RsaPublicKeyComponents,
+    ECDSA_P256_SHA256_FIXED_SIGNING,
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
+use crate::helper::type_alias::DbPool;
+use crate::models::signing_key_model::SigningKeyModel;
+use crate::schema::signing_keys;
+use crate::schemas::key_schemas::{SigningKeyCreate, SigningKeyResponse};
+
+const DEFAULT_SECRET_KEY_ID: &str = "default";
+
+/**
+ * A key used to sign and verify tokens. Asymmetric keys also expose their public part as a JWK
+ */
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding_key: EncodingKey,
+    pub decoding_key: DecodingKey,
+    pub jwk: Option<Jwk>,
+}
+
+impl SigningKey {
+    /**
+     * Creates an HS256 key from a shared secret
+     *
+     * @param kid: &str
+     * @param secret: &str
+     * @return SigningKey
+     */
+    pub fn from_secret(kid: &str, secret: &str) -> Self {
+        Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret.as_ref()),
+            decoding_key: DecodingKey::from_secret(secret.as_ref()),
+            jwk: None,
+        }
+    }
+
+    /**
+     * Creates a key from its stored material: the shared secret for HS256, a PEM encoded
+     * private key otherwise
+     *
+     * @param kid: Option<&str>
+     * @param algorithm: Algorithm
+     * @param material: &str
+     * @return Result<SigningKey, Error>
+     */
+    pub fn from_material(
+        kid: Option<&str>,
+        algorithm: Algorithm,
+        material: &str,
+    ) -> Result<Self, Error> {
+        match algorithm {
+            Algorithm::HS256 => Ok(Self::from_secret(
+                kid.unwrap_or(DEFAULT_SECRET_KEY_ID),
+                material,
+            )),
+            _ => Self::from_pem(kid, algorithm, material.as_bytes()),
+        }
+    }
+
+    /**
+     * Creates an asymmetric key from a PEM encoded private key. The public key is derived from
+     * it and, unless a key id is given, its RFC 7638 thumbprint is used as key id.
+     *
+     * @param kid: Option<&str>
+     * @param algorithm: Algorithm
+     * @param private_key: &[u8]
+     * @return Result<SigningKey, Error>
+     */
+    pub fn from_pem(
+        kid: Option<&str>,
+        algorithm: Algorithm,
+        private_key: &[u8],
+    ) -> Result<Self, Error> {
+        let pem = pem::parse(private_key).map_err(|e| {
+            log::error!("Failed to parse private key: {}", e);
+            Error::from(ErrorKind::InvalidKeyFormat)
+        })?;
+        let (encoding_key, parameters) = match algorithm {
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {
+                let key_pair = match pem.tag() {
+                    "RSA PRIVATE KEY" => RsaKeyPair::from_der(pem.contents()),
+                    _ => RsaKeyPair::from_pkcs8(pem.contents()),
+                }
+                .map_err(|e| {
+                    log::error!("Invalid RSA private key: {}", e);
+                    Error::from(ErrorKind::InvalidRsaKey(e.to_string()))
+                })?;
+                let components = RsaPublicKeyComponents::<Vec<u8>>::from(key_pair.public());
+                (
+                    EncodingKey::from_rsa_pem(private_key)?,
+                    AlgorithmParameters::RSA(RSAKeyParameters {
+                        key_type: RSAKeyType::RSA,
+                        n: URL_SAFE_NO_PAD.encode(components.n),
+                        e: URL_SAFE_NO_PAD.encode(components.e),
+                    }),
+                )
+            }
+            Algorithm::ES256 => {
+                let key_pair = EcdsaKeyPair::from_pkcs8(
+                    &ECDSA_P256_SHA256_FIXED_SIGNING,
+                    pem.contents(),
+                    &SystemRandom::new(),
+                )
+                .map_err(|e| {
+                    log::error!("Invalid EC private key: {}", e);
+                    Error::from(ErrorKind::InvalidEcdsaKey)
+                })?;
+                // Uncompressed point: 0x04 || x || y
+                let point = key_pair.public_key().as_ref();
+                (
+                    EncodingKey::from_ec_pem(private_key)?,
+                    AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+                        key_type: EllipticCurveKeyType::EC,
+                        curve: EllipticCurve::P256,
+                        x: URL_SAFE_NO_PAD.encode(&point[1..33]),
+                        y: URL_SAFE_NO_PAD.encode(&point[33..65]),
+                    }),
+                )
+            }
+            Algorithm::EdDSA => {
+                let key_pair =
+                    Ed25519KeyPair::from_pkcs8_maybe_unchecked(pem.contents()).map_err(|e| {
+                        log::error!("Invalid Ed25519 private key: {}", e);
+                        Error::from(ErrorKind::InvalidKeyFormat)
+                    })?;
+                (
+                    EncodingKey::from_ed_pem(private_key)?,
+                    AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                    }),
+                )
+            }
+            _ => {
+                log::error!("Unsupported signing algorithm: {:?}", algorithm);
+                return Err(Error::from(ErrorKind::InvalidAlgorithm));
+            }
+        };
+
+        let kid = match kid {
+            Some(kid) => kid.to_string(),
+            None => Self::thumbprint(&parameters),
+        };
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::from_str(&format!("{:?}", algorithm))?),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        };
+        Ok(Self {
+            kid,
+            algorithm,
+            encoding_key,
+            decoding_key: DecodingKey::from_jwk(&jwk)?,
+            jwk: Some(jwk),
+        })
+    }
+
+    /**
+     * Computes the RFC 7638 thumbprint of a public key
+     *
+     * @param parameters: &AlgorithmParameters
+     * @return String
+     */
+    fn thumbprint(parameters: &AlgorithmParameters) -> String {
+        // Members are in lexicographic order, as required by the RFC
+        let canonical = match parameters {
+            AlgorithmParameters::RSA(rsa) => {
+                format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, rsa.e, rsa.n)
+            }
+            AlgorithmParameters::EllipticCurve(ec) => format!(
+                r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+                ec.x, ec.y
+            ),
+            AlgorithmParameters::OctetKeyPair(okp) => {
+                format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, okp.x)
+            }
+            AlgorithmParameters::OctetKey(oct) => {
+                format!(r#"{{"k":"{}","kty":"oct"}}"#, oct.value)
+            }
+        };
+        URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+    }
+}
+
+/**
+ * The set of keys tokens can be verified with. Exactly one of them, the most recently
+ * promoted, is used for signing. Keys are shared between workers through the database.
+ */
+pub struct KeyRing {
+    state: RwLock<KeyRingState>,
+}
+
+struct KeyRingState {
+    active: Arc<SigningKey>,
+    keys: HashMap<String, Arc<SigningKey>>,
+    loaded_at: Instant,
+}
+
+impl KeyRing {
+    pub fn new(active: SigningKey, keys: Vec<SigningKey>) -> Self {
+        Self {
+            state: RwLock::new(Self::build_state(active, keys)),
+        }
+    }
+
+    fn build_state(active: SigningKey, keys: Vec<SigningKey>) -> KeyRingState {
+        let active = Arc::new(active);
+        let mut keys: HashMap<String, Arc<SigningKey>> = keys
+            .into_iter()
+            .map(|key| (key.kid.clone(), Arc::new(key)))
+            .collect();
+        keys.insert(active.kid.clone(), active.clone());
+        KeyRingState {
+            active,
+            keys,
+            loaded_at: Instant::now(),
+        }
+    }
+
+    /**
+     * Returns the key new tokens are signed with
+     *
+     * @return Arc<SigningKey>
+     */
+    pub fn active(&self) -> Arc<SigningKey> {
+        self.state.read().unwrap().active.clone()
+    }
+
+    /**
+     * Returns the verification key with the given id
+     *
+     * @param kid: &str
+     * @return Option<Arc<SigningKey>>
+     */
+    pub fn get(&self, kid: &str) -> Option<Arc<SigningKey>> {
+        self.state.read().unwrap().keys.get(kid).cloned()
+    }
+
+    /**
+     * Returns the public keys of every key tokens can be verified with
+     *
+     * @return JwkSet
+     */
+    pub fn jwks(&self) -> JwkSet {
+        let state = self.state.read().unwrap();
+        let mut keys: Vec<Jwk> = state
+            .keys
+            .values()
+            .filter_map(|key| key.jwk.clone())
+            .collect();
+        keys.sort_by(|a, b| a.common.key_id.cmp(&b.common.key_id));
+        JwkSet { keys }
+    }
+
+    fn replace(&self, active: SigningKey, keys: Vec<SigningKey>) {
+        *self.state.write().unwrap() = Self::build_state(active, keys);
+    }
+
+    fn loaded_since(&self) -> Duration {
+        self.state.read().unwrap().loaded_at.elapsed()
+    }
+}
+
+pub struct KeyService;
+
+impl KeyService {
+    /**
+     * Reads the signing key described by the auth configuration. It is used to bootstrap the
+     * key ring when the database holds no key yet.
+     *
+     * @param auth_config: &AuthConfig
+     * @return Result<SigningKeyModel, Error>
+     */
+    pub fn configured_key(auth_config: &AuthConfig) -> Result<SigningKeyModel, Error> {
+        let algorithm = Algorithm::from_str(&auth_config.signing_algorithm)?;
+        let material = match algorithm {
+            Algorithm::HS256 => auth_config.secret_key.clone(),
+            _ => {
+                let path = auth_config.signing_key_path.as_ref().ok_or_else(|| {
+                    log::error!("A private key is required for {:?} signing", algorithm);
+                    Error::from(ErrorKind::InvalidKeyFormat)
+                })?;
+                std::fs::read_to_string(path).map_err(|e| {
+                    log::error!("Failed to read private key {}: {}", path, e);
+                    Error::from(ErrorKind::InvalidKeyFormat)
+                })?
+            }
+        };
+        let signing_key =
+            SigningKey::from_material(auth_config.signing_key_id.as_deref(), algorithm, &material)?;
+        Ok(SigningKeyModel::new(
+            signing_key.kid,
+            format!("{:?}", algorithm),
+            material,
+            Some(chrono::Utc::now().naive_utc()),
+        ))
+    }
+
+    /**
+     * Builds the key ring from the database, registering the configured key first if the
+     * database holds no key at all
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param configured_key: SigningKeyModel
+     * @return Result<KeyRing, AppError>
+     */
+    pub async fn initialize(
+        conn: &mut AsyncPgConnection,
+        configured_key: SigningKeyModel,
+    ) -> Result<KeyRing, AppError> {
+        let count = signing_keys::table.count().get_result::<i64>(conn).await?;
+        if count == 0 {
+            log::info!("Registering configured signing key {}", configured_key.kid);
+            diesel::insert_into(signing_keys::table)
+                .values(&configured_key)
+                .on_conflict_do_nothing()
+                .execute(conn)
+                .await?;
+        }
+        let (active, keys) = Self::load(conn).await?;
+        log::info!(
+            "Loaded {} verification keys, signing with {:?} key {}",
+            keys.len() + 1,
+            active.algorithm,
+            active.kid
+        );
+        Ok(KeyRing::new(active, keys))
+    }
+
+    /**
+     * Loads every key which is not retired, returning the active key separately
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @return Result<(SigningKey, Vec<SigningKey>), AppError>
+     */
+    async fn load(conn: &mut AsyncPgConnection) -> Result<(SigningKey, Vec<SigningKey>), AppError> {
+        let models = signing_keys::table
+            .filter(signing_keys::retired_at.is_null())
+            .order(signing_keys::activated_at.desc().nulls_last())
+            .get_results::<SigningKeyModel>(conn)
+            .await?;
+
+        let active_kid = match models.as_slice() {
+            [latest, ..] if latest.activated_at.is_some() => latest.kid.clone(),
+            _ => {
+                log::error!("No active signing key found");
+                return Err(AppError::Internal("No active signing key".to_string()));
+            }
+        };
+        let mut active = None;
+        let mut keys = Vec::new();
+        for model in models {
+            let signing_key = Algorithm::from_str(&model.algorithm).and_then(|algorithm| {
+                SigningKey::from_material(Some(&model.kid), algorithm, &model.private_key)
+            });
+            match signing_key {
+                Ok(signing_key) if signing_key.kid == active_kid => active = Some(signing_key),
+                Ok(signing_key) => keys.push(signing_key),
+                Err(e) => log::error!("Failed to load signing key {}: {}", model.kid, e),
+            }
+        }
+        let active = active.ok_or_else(|| {
+            AppError::Internal(format!("Failed to load active signing key {}", active_kid))
+        })?;
+        Ok((active, keys))
+    }
+
+    /**
+     * Reloads the key ring from the database
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param key_ring: &KeyRing
+     * @return Result<(), AppError>
+     */
+    pub async fn reload(conn: &mut AsyncPgConnection, key_ring: &KeyRing) -> Result<(), AppError> {
+        let (active, keys) = Self::load(conn).await?;
+        if active.kid != key_ring.active().kid {
+            log::info!("Signing key changed to {}", active.kid);
+        }
+        key_ring.replace(active, keys);
+        Ok(())
+    }
+
+    /**
+     * Reloads the key ring unless it was loaded less than `min_age` ago. Used when a token
+     * references a key this worker does not know yet.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param key_ring: &KeyRing
+     * @param min_age: Duration
+     * @return Result<(), AppError>
+     */
+    pub async fn reload_if_stale(
+        conn: &mut AsyncPgConnection,
+        key_ring: &KeyRing,
+        min_age: Duration,
+    ) -> Result<(), AppError> {
+        if key_ring.loaded_since() < min_age {
+            return Ok(());
+        }
+        Self::reload(conn, key_ring).await
+    }
+
+    /**
+     * Reloads the key ring forever, so that every worker and replica picks up rotations
+     *
+     * @param pool: DbPool
+     * @param key_ring: actix_web::web::Data<KeyRing>
+     * @param interval: Duration
+     */
+    pub async fn refresh_periodically(
+        pool: DbPool,
+        key_ring: actix_web::web::Data<KeyRing>,
+        interval: Duration,
+    ) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            let reloaded = match pool.get().await {
+                Ok(mut conn) => Self::reload(&mut conn, &key_ring).await,
+                Err(e) => {
+                    log::error!("Failed to get pool: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = reloaded {
+                log::error!("Failed to reload signing keys: {}", e);
+            }
+        }
+    }
+
+    /**
+     * Lists every key, retired ones included
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @return Result<Vec<SigningKeyResponse>, diesel::result::Error>
+     */
+    pub async fn list(
+        conn: &mut AsyncPgConnection,
+    ) -> Result<Vec<SigningKeyResponse>, diesel::result::Error> {
+        let models = signing_keys::table
+            .order(signing_keys::created_at.desc())
+            .get_results::<SigningKeyModel>(conn)
+            .await?;
+        let active_kid = models
+            .iter()
+            .filter(|model| model.retired_at.is_none() && model.activated_at.is_some())
+            .max_by_key(|model| model.activated_at)
+            .map(|model| model.kid.clone());
+        Ok(models
+            .into_iter()
+            .map(|model| Self::to_response(model, active_kid.as_deref()))
+            .collect())
+    }
+
+    /**
+     * Generates a new key, or imports the given private key. The key is published for
+     * verification right away but only signs once promoted, so that verifiers can pick it up
+     * beforehand.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param data: SigningKeyCreate
+     * @return Result<SigningKeyResponse, AppError>
+     */
+    pub async fn generate(
+        conn: &mut AsyncPgConnection,
+        data: SigningKeyCreate,
+    ) -> Result<SigningKeyResponse, AppError> {
+        let signing_key = Algorithm::from_str(&data.algorithm).and_then(|algorithm| {
+            let material = match data.private_key {
+                Some(private_key) => private_key,
+                None => Self::generate_material(algorithm)?,
+            };
+            let kid = match algorithm {
+                Algorithm::HS256 => Some(Uuid::new_v4().to_string()),
+                _ => None,
+            };
+            let signing_key = SigningKey::from_material(kid.as_deref(), algorithm, &material)?;
+            Ok((signing_key.kid, format!("{:?}", algorithm), material))
+        });
+        let (kid, algorithm, material) = match signing_key {
+            Ok(signing_key) => signing_key,
+            Err(e) => {
+                log::error!("Failed to create signing key: {}", e);
+                return Err(AppError::Validation(format!("Invalid signing key: {}", e)));
+            }
+        };
+
+        let created_key = diesel::insert_into(signing_keys::table)
+            .values(&SigningKeyModel::new(kid, algorithm, material, None))
+            .get_result::<SigningKeyModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create signing key: {}", e);
+                e
+            })?;
+        log::info!("Signing key {} created", created_key.kid);
+        Ok(Self::to_response(created_key, None))
+    }
+
+    /**
+     * Makes a key the one new tokens are signed with. Previously active keys keep verifying
+     * tokens until they are retired.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param kid: &str
+     * @return Result<SigningKeyResponse, diesel::result::Error>
+     */
+    pub async fn promote(
+        conn: &mut AsyncPgConnection,
+        kid: &str,
+    ) -> Result<SigningKeyResponse, diesel::result::Error> {
+        let promoted_key = diesel::update(
+            signing_keys::table
+                .find(kid)
+                .filter(signing_keys::retired_at.is_null()),
+        )
+        .set(signing_keys::activated_at.eq(chrono::Utc::now().naive_utc()))
+        .get_result::<SigningKeyModel>(conn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to promote signing key {}: {}", kid, e);
+            e
+        })?;
+        log::info!("Signing key {} promoted", kid);
+        Ok(Self::to_response(promoted_key, Some(kid)))
+    }
+
+    /**
+     * Retires a key. Tokens signed with it are no longer accepted. The active key can not be
+     * retired.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param kid: &str
+     * @return Result<SigningKeyResponse, AppError>
+     */
+    pub async fn retire(
+        conn: &mut AsyncPgConnection,
+        kid: &str,
+    ) -> Result<SigningKeyResponse, AppError> {
+        let (active, _) = Self::load(conn).await?;
+        if active.kid == kid {
+            log::error!("Signing key {} is active and can not be retired", kid);
+            return Err(AppError::Conflict(
+                "The active signing key can not be retired".to_string(),
+            ));
+        }
+        let retired_key = diesel::update(
+            signing_keys::table
+                .find(kid)
+                .filter(signing_keys::retired_at.is_null()),
+        )
+        .set(signing_keys::retired_at.eq(chrono::Utc::now().naive_utc()))
+        .get_result::<SigningKeyModel>(conn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to retire signing key {}: {}", kid, e);
+            e
+        })?;
+        log::info!("Signing key {} retired", kid);
+        Ok(Self::to_response(retired_key, None))
+    }
+
+    /**
+     * Generates the private material of a new key
+     *
+     * @param algorithm: Algorithm
+     * @return Result<String, Error>
+     */
+    fn generate_material(algorithm: Algorithm) -> Result<String, Error> {
+        let rng = SystemRandom::new();
+        let pkcs8 = match algorithm {
+            Algorithm::HS256 => {
+                let mut secret = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut secret);
+                return Ok(STANDARD.encode(secret));
+            }
+            Algorithm::ES256 => {
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            }
+            Algorithm::EdDSA => Ed25519KeyPair::generate_pkcs8(&rng),
+            _ => {
+                log::error!(
+                    "Can not generate {:?} keys, a private key is required",
+                    algorithm
+                );
+                return Err(Error::from(ErrorKind::InvalidAlgorithm));
+            }
+        }
+        .map_err(|_| Error::from(ErrorKind::InvalidKeyFormat))?;
+        Ok(pem::encode(&pem::Pem::new(
+            "PRIVATE KEY",
+            pkcs8.as_ref().to_vec(),
+        )))
+    }
+
+    fn to_response(model: SigningKeyModel, active_kid: Option<&str>) -> SigningKeyResponse {
+        SigningKeyResponse {
+            active: active_kid == Some(model.kid.as_str()),
+            kid: model.kid,
+            algorithm: model.algorithm,
+            created_at: model.created_at,
+            activated_at: model.activated_at,
+            retired_at: model.retired_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_pem(der: &[u8]) -> Vec<u8> {
+        pem::encode(&pem::Pem::new("PRIVATE KEY", der.to_vec())).into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_from_pem_ed25519() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let signing_key =
+            SigningKey::from_pem(None, Algorithm::EdDSA, &to_pem(pkcs8.as_ref())).unwrap();
+        let jwk = signing_key.jwk.unwrap();
+        assert_eq!(signing_key.algorithm, Algorithm::EdDSA);
+        assert_eq!(jwk.common.key_id, Some(signing_key.kid.clone()));
+        assert_eq!(signing_key.kid.len(), 43);
+    }
+
+    #[tokio::test]
+    async fn test_from_pem_ecdsa() {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .unwrap();
+        let signing_key =
+            SigningKey::from_pem(Some("ec-key"), Algorithm::ES256, &to_pem(pkcs8.as_ref()))
+                .unwrap();
+        assert_eq!(signing_key.kid, "ec-key");
+        assert!(matches!(
+            signing_key.jwk.unwrap().algorithm,
+            AlgorithmParameters::EllipticCurve(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_from_pem_wrong_algorithm() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let signing_key = SigningKey::from_pem(None, Algorithm::ES256, &to_pem(pkcs8.as_ref()));
+        assert!(signing_key.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_material() {
+        for algorithm in [Algorithm::HS256, Algorithm::ES256, Algorithm::EdDSA] {
+            let material = KeyService::generate_material(algorithm).unwrap();
+            assert!(SigningKey::from_material(None, algorithm, &material).is_ok());
+        }
+        assert!(KeyService::generate_material(Algorithm::RS256).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_key_ring() {
+        let active = SigningKey::from_secret("active", "PEP+DnYqfglRX+vextkRcA=");
+        let material = KeyService::generate_material(Algorithm::EdDSA).unwrap();
+        let previous = SigningKey::from_material(None, Algorithm::EdDSA, &material).unwrap();
+        let previous_kid = previous.kid.clone();
+        let key_ring = KeyRing::new(active, vec![previous]);
+
+        assert_eq!(key_ring.active().kid, "active");
+        assert!(key_ring.get("active").is_some());
+        assert!(key_ring.get(&previous_kid).is_some());
+        assert!(key_ring.get("unknown").is_none());
+        // Shared secrets are never published
+        assert_eq!(key_ring.jwks().keys.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_from_secret() {
+        let signing_key = SigningKey::from_secret("default", "PEP+DnYqfglRX+vextkRcA=");
+        assert_eq!(signing_key.algorithm, Algorithm::HS256);
+        assert!(signing_key.jwk.is_none());
+    }
+}
@@ -0,0 +1,330 @@
+use actix_web::HttpRequest;
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::sql_types::{BigInt, Integer, Text, Timestamp};
+use diesel::QueryDsl;
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryableByName};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
+use crate::models::login_lockout_model::LoginLockoutModel;
+use crate::schema::login_lockouts;
+
+/// Failed attempts counted per account, keyed by the lowercase email
+pub const SCOPE_ACCOUNT: &str = "account";
+/// Failed attempts counted per client address
+pub const SCOPE_IP: &str = "ip";
+
+/// Longest exponent of the backoff, so that the delay can not overflow
+const MAX_BACKOFF_EXPONENT: i32 = 20;
+
+/// Counts a failure in a single statement, so that concurrent failures are all counted. The
+/// counter starts over when the previous failure is older than the window.
+const COUNT_FAILURE_SQL: &str = r#"
+INSERT INTO "login_lockouts" AS "lockout"
+    ("id", "scope", "key", "failures", "last_failure_at", "created_at")
+VALUES ($1, $2, $3, 1, $4, $4)
+ON CONFLICT ("scope", "key") DO UPDATE
+    SET "failures" = CASE
+            WHEN "lockout"."last_failure_at" > $4 - $5 * INTERVAL '1 second'
+                THEN "lockout"."failures" + 1
+            ELSE 1
+        END,
+        "last_failure_at" = $4
+RETURNING "failures"
+"#;
+
+#[derive(QueryableByName)]
+struct FailureCount {
+    #[diesel(sql_type = Integer)]
+    failures: i32,
+}
+
+/// How fast failed attempts lock a key
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockoutPolicy {
+    /// Failures after which the key is locked out
+    pub threshold: i32,
+    /// First delay of the backoff below the threshold, none when zero
+    pub backoff_seconds: i64,
+    /// First lockout, doubled with every further failure
+    pub lockout_seconds: i64,
+    /// Longest delay or lockout
+    pub max_seconds: i64,
+}
+
+impl LockoutPolicy {
+    pub fn account(auth_config: &AuthConfig) -> Self {
+        Self {
+            threshold: auth_config.lockout_account_threshold,
+            backoff_seconds: auth_config.lockout_backoff_seconds,
+            lockout_seconds: auth_config.lockout_seconds,
+            max_seconds: auth_config.lockout_max_seconds,
+        }
+    }
+
+    /// Addresses do not back off before their threshold, since they may be shared by many users
+    pub fn ip(auth_config: &AuthConfig) -> Self {
+        Self {
+            threshold: auth_config.lockout_ip_threshold,
+            backoff_seconds: 0,
+            lockout_seconds: auth_config.lockout_seconds,
+            max_seconds: auth_config.lockout_max_seconds,
+        }
+    }
+
+    /**
+     * How long a key is locked after `failures` consecutive failures: an exponential backoff
+     * below the threshold, then a lockout doubling with every further failure
+     *
+     * @param failures: i32
+     * @return Option<Duration>
+     */
+    pub fn delay(&self, failures: i32) -> Option<Duration> {
+        let seconds = if failures >= self.threshold {
+            let exponent = (failures - self.threshold).min(MAX_BACKOFF_EXPONENT) as u32;
+            self.lockout_seconds.saturating_mul(1 << exponent)
+        } else if self.backoff_seconds > 0 {
+            let exponent = (failures - 1).clamp(0, MAX_BACKOFF_EXPONENT) as u32;
+            self.backoff_seconds.saturating_mul(1 << exponent)
+        } else {
+            return None;
+        };
+        Some(Duration::seconds(seconds.min(self.max_seconds)))
+    }
+}
+
+pub struct LockoutService;
+
+impl LockoutService {
+    /**
+     * Address of the client, as seen by the server. Forwarding headers are ignored since any
+     * client can set them.
+     *
+     * @param req: &HttpRequest
+     * @return Option<String>
+     */
+    pub fn client_ip(req: &HttpRequest) -> Option<String> {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }
+
+    /**
+     * Rejects the request with `429 Too Many Requests` and a `Retry-After` header while the
+     * account or the client address is locked. Checked before the credentials, so a locked
+     * account does not disclose whether a password is right.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param email: &str
+     * @param client_ip: Option<&str>
+     * @return Result<(), AppError>
+     */
+    pub async fn require_unlocked(
+        conn: &mut AsyncPgConnection,
+        email: &str,
+        client_ip: Option<&str>,
+    ) -> Result<(), AppError> {
+        let now = chrono::Utc::now().naive_utc();
+        let locked_until = login_lockouts::table
+            .filter(login_lockouts::locked_until.gt(now))
+            .filter(
+                login_lockouts::scope
+                    .eq(SCOPE_ACCOUNT)
+                    .and(login_lockouts::key.eq(Self::account_key(email)))
+                    .or(login_lockouts::scope
+                        .eq(SCOPE_IP)
+                        .and(login_lockouts::key.eq(client_ip.unwrap_or_default()))),
+            )
+            .select(diesel::dsl::max(login_lockouts::locked_until))
+            .get_result::<Option<chrono::NaiveDateTime>>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to check login lockouts: {}", e);
+                AppError::from(e)
+            })?;
+        match locked_until {
+            Some(locked_until) => {
+                let retry_after = (locked_until - now).num_seconds().max(1);
+                log::warn!(
+                    "Rejected login of a locked account or address for {}s",
+                    retry_after
+                );
+                Err(AppError::TooManyRequests {
+                    detail: "Too many failed attempts".to_string(),
+                    retry_after: retry_after as u64,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Counts a failed attempt against the account and the client address, and locks them once
+     * they reach their threshold. Failures older than `lockout_window_seconds` are forgotten.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param email: &str
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @return Result<(), Error>
+     */
+    pub async fn record_failure(
+        conn: &mut AsyncPgConnection,
+        email: &str,
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+    ) -> Result<(), Error> {
+        Self::count_failure(
+            conn,
+            SCOPE_ACCOUNT,
+            &Self::account_key(email),
+            &LockoutPolicy::account(auth_config),
+            auth_config,
+        )
+        .await?;
+        if let Some(client_ip) = client_ip {
+            Self::count_failure(
+                conn,
+                SCOPE_IP,
+                client_ip,
+                &LockoutPolicy::ip(auth_config),
+                auth_config,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Clears the failed attempts of an account after a successful login. The counter of the
+     * client address is kept, so that an attacker can not reset it with an account of their own.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param email: &str
+     * @return Result<(), Error>
+     */
+    pub async fn record_success(conn: &mut AsyncPgConnection, email: &str) -> Result<(), Error> {
+        Self::unlock(conn, SCOPE_ACCOUNT, &Self::account_key(email))
+            .await
+            .map(|_| ())
+    }
+
+    /**
+     * Lists the accounts and addresses that are currently locked
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @return Result<Vec<LoginLockoutModel>, Error>
+     */
+    pub async fn list(conn: &mut AsyncPgConnection) -> Result<Vec<LoginLockoutModel>, Error> {
+        login_lockouts::table
+            .filter(login_lockouts::locked_until.gt(chrono::Utc::now().naive_utc()))
+            .order(login_lockouts::locked_until.desc())
+            .get_results::<LoginLockoutModel>(conn)
+            .await
+    }
+
+    /**
+     * Removes the lock and the failed attempts of an account or an address
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param scope: &str
+     * @param key: &str
+     * @return Result<usize, Error>
+     */
+    pub async fn unlock(
+        conn: &mut AsyncPgConnection,
+        scope: &str,
+        key: &str,
+    ) -> Result<usize, Error> {
+        diesel::delete(
+            login_lockouts::table
+                .filter(login_lockouts::scope.eq(scope))
+                .filter(login_lockouts::key.eq(key)),
+        )
+        .execute(conn)
+        .await
+    }
+
+    pub fn account_key(email: &str) -> String {
+        email.trim().to_lowercase()
+    }
+
+    async fn count_failure(
+        conn: &mut AsyncPgConnection,
+        scope: &str,
+        key: &str,
+        policy: &LockoutPolicy,
+        auth_config: &AuthConfig,
+    ) -> Result<(), Error> {
+        let now = chrono::Utc::now().naive_utc();
+        let failures = diesel::sql_query(COUNT_FAILURE_SQL)
+            .bind::<diesel::sql_types::Uuid, _>(Uuid::new_v4())
+            .bind::<Text, _>(scope)
+            .bind::<Text, _>(key)
+            .bind::<Timestamp, _>(now)
+            .bind::<BigInt, _>(auth_config.lockout_window_seconds)
+            .get_result::<FailureCount>(conn)
+            .await?
+            .failures;
+        let delay = policy.delay(failures);
+        let locked_until = delay.map(|delay| now + delay);
+
+        // A concurrent failure counted after this one sets its own, longer lock
+        diesel::update(
+            login_lockouts::table
+                .filter(login_lockouts::scope.eq(scope))
+                .filter(login_lockouts::key.eq(key))
+                .filter(login_lockouts::failures.eq(failures)),
+        )
+        .set(login_lockouts::locked_until.eq(locked_until))
+        .execute(conn)
+        .await?;
+        if failures >= policy.threshold {
+            log::warn!(
+                "Locked {} {} for {}s after {} failed attempts",
+                scope,
+                key,
+                delay.map(|delay| delay.num_seconds()).unwrap_or_default(),
+                failures
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_delay() {
+        let policy = LockoutPolicy {
+            threshold: 5,
+            backoff_seconds: 1,
+            lockout_seconds: 900,
+            max_seconds: 3600,
+        };
+        assert_eq!(policy.delay(1), Some(Duration::seconds(1)));
+        assert_eq!(policy.delay(4), Some(Duration::seconds(8)));
+        assert_eq!(policy.delay(5), Some(Duration::seconds(900)));
+        assert_eq!(policy.delay(6), Some(Duration::seconds(1800)));
+        assert_eq!(policy.delay(8), Some(Duration::seconds(3600)));
+        assert_eq!(policy.delay(100), Some(Duration::seconds(3600)));
+
+        let policy = LockoutPolicy {
+            backoff_seconds: 0,
+            ..policy
+        };
+        assert_eq!(policy.delay(4), None);
+        assert_eq!(policy.delay(5), Some(Duration::seconds(900)));
+    }
+
+    #[tokio::test]
+    async fn test_account_key() {
+        assert_eq!(
+            LockoutService::account_key(" User@Example.com "),
+            "user@example.com"
+        );
+    }
+}
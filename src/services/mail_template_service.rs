@@ -0,0 +1,121 @@
+use crate::services::mailer_service::Email;
+
+/// Emails sent by the application. Each one has a plain text and an HTML template under
+/// `src/templates/mail`, where `{{name}}` placeholders are replaced with the given variables
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MailTemplate {
+    PasswordReset,
+    EmailVerification,
+    EmailChanged,
+}
+
+impl MailTemplate {
+    fn subject(&self) -> &'static str {
+        match self {
+            MailTemplate::PasswordReset => "Reset your password",
+            MailTemplate::EmailVerification => "Confirm your email address",
+            MailTemplate::EmailChanged => "Your email address was changed",
+        }
+    }
+
+    fn text(&self) -> &'static str {
+        match self {
+            MailTemplate::PasswordReset => include_str!("../templates/mail/password_reset.txt"),
+            MailTemplate::EmailVerification => {
+                include_str!("../templates/mail/email_verification.txt")
+            }
+            MailTemplate::EmailChanged => include_str!("../templates/mail/email_changed.txt"),
+        }
+    }
+
+    fn html(&self) -> &'static str {
+        match self {
+            MailTemplate::PasswordReset => include_str!("../templates/mail/password_reset.html"),
+            MailTemplate::EmailVerification => {
+                include_str!("../templates/mail/email_verification.html")
+            }
+            MailTemplate::EmailChanged => include_str!("../templates/mail/email_changed.html"),
+        }
+    }
+}
+
+pub struct MailTemplateService;
+
+impl MailTemplateService {
+    /**
+     * Renders a template into an email. Variables are escaped in the HTML body
+     *
+     * @param template: MailTemplate
+     * @param to: &str
+     * @param variables: &[(&str, String)]
+     * @return Email
+     */
+    pub fn render(template: MailTemplate, to: &str, variables: &[(&str, String)]) -> Email {
+        Email {
+            to: to.to_string(),
+            subject: template.subject().to_string(),
+            text_body: Self::substitute(template.text(), variables, false),
+            html_body: Some(Self::substitute(template.html(), variables, true)),
+        }
+    }
+
+    fn substitute(template: &str, variables: &[(&str, String)], escape: bool) -> String {
+        variables
+            .iter()
+            .fold(template.to_string(), |body, (name, value)| {
+                let value = if escape {
+                    Self::escape_html(value)
+                } else {
+                    value.clone()
+                };
+                body.replace(&format!("{{{{{}}}}}", name), &value)
+            })
+    }
+
+    fn escape_html(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| match c {
+                '&' => "&amp;".to_string(),
+                '<' => "&lt;".to_string(),
+                '>' => "&gt;".to_string(),
+                '"' => "&quot;".to_string(),
+                '\'' => "&#39;".to_string(),
+                c => c.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render() {
+        let email = MailTemplateService::render(
+            MailTemplate::PasswordReset,
+            "user@example.com",
+            &[
+                (
+                    "reset_url",
+                    "http://localhost/reset?token=a&b=\"<x>\"".to_string(),
+                ),
+                ("expire_minutes", "30".to_string()),
+            ],
+        );
+        assert_eq!(email.to, "user@example.com");
+        assert_eq!(email.subject, "Reset your password");
+        assert!(email
+            .text_body
+            .contains("http://localhost/reset?token=a&b=\"<x>\""));
+        assert!(email.text_body.contains("expires in 30 minutes"));
+        assert!(!email.text_body.contains("{{"));
+
+        let html_body = email.html_body.unwrap();
+        assert!(html_body
+            .contains("href=\"http://localhost/reset?token=a&amp;b=&quot;&lt;x&gt;&quot;\""));
+        assert!(!html_body.contains("<x>"));
+        assert!(!html_body.contains("{{"));
+    }
+}
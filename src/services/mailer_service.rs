@@ -0,0 +1,316 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use lettre::message::header::ContentType;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use crate::configs::common::MailConfig;
+
+/// Outgoing email. The HTML body is optional, the plain text body is always sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: Option<String>,
+}
+
+/**
+ * Delivers outgoing emails. Flows do not call it directly: they queue their emails in the outbox,
+ * which hands them to the configured implementation and retries failed deliveries.
+ */
+pub trait Mailer: Send + Sync {
+    /**
+     * Sends an email
+     *
+     * @param email: &Email
+     * @return Result<(), String>
+     */
+    fn send(&self, email: &Email) -> Result<(), String>;
+}
+
+/// Writes emails to the log instead of sending them. Meant for development only
+pub struct ConsoleMailer {
+    from: String,
+}
+
+impl ConsoleMailer {
+    pub fn new(from: &str) -> Self {
+        Self {
+            from: from.to_string(),
+        }
+    }
+}
+
+impl Mailer for ConsoleMailer {
+    fn send(&self, email: &Email) -> Result<(), String> {
+        log::info!(
+            "Mail from {} to {}: {}\n{}",
+            self.from,
+            email.to,
+            email.subject,
+            email.text_body
+        );
+        Ok(())
+    }
+}
+
+/// Drops every email as an `.eml` file into a folder, for tests and local inspection
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: &str, dir: &str) -> Self {
+        Self {
+            from: from.to_string(),
+            dir: PathBuf::from(dir),
+        }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: &Email) -> Result<(), String> {
+        let message = MailerService::message(&self.from, email)?;
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let path = self.dir.join(format!("{}.eml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, message.formatted()).map_err(|e| e.to_string())?;
+        log::info!("Mail to {} written to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+/// Sends emails through an SMTP relay, over a connection upgraded with STARTTLS. Plaintext is only
+/// used when configured, for a local relay such as a sidecar or a development sink
+pub struct SmtpMailer {
+    from: String,
+    transport: SmtpTransport,
+}
+
+impl SmtpMailer {
+    /**
+     * Sets up the transport to the relay, requiring STARTTLS unless `plaintext` is set. Credentials
+     * are never sent in plaintext.
+     *
+     * @param from: &str
+     * @param host: &str
+     * @param port: u16
+     * @param username: Option<String>
+     * @param password: Option<String>
+     * @param plaintext: bool
+     * @return Result<Self, String>
+     */
+    pub fn new(
+        from: &str,
+        host: &str,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+        plaintext: bool,
+    ) -> Result<Self, String> {
+        let builder = if plaintext {
+            if username.is_some() {
+                return Err("SMTP credentials can not be sent in plaintext".to_string());
+            }
+            log::warn!("Emails are sent to {} without encryption", host);
+            SmtpTransport::builder_dangerous(host)
+        } else {
+            SmtpTransport::starttls_relay(host).map_err(|e| e.to_string())?
+        };
+        let mut builder = builder.port(port);
+        if let Some(username) = username {
+            builder = builder.credentials(Credentials::new(username, password.unwrap_or_default()));
+        }
+        Ok(Self {
+            from: from.to_string(),
+            transport: builder.build(),
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<(), String> {
+        let message = MailerService::message(&self.from, email)?;
+        self.transport
+            .send(&message)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+pub struct MailerService;
+
+impl MailerService {
+    /**
+     * Builds the mailer selected by `MAIL_BACKEND`: `console`, `file` or `smtp`
+     *
+     * @param mail_config: &MailConfig
+     * @return Result<Arc<dyn Mailer>, String>
+     */
+    pub fn configured(mail_config: &MailConfig) -> Result<Arc<dyn Mailer>, String> {
+        match mail_config.mail_backend.as_str() {
+            "console" => Ok(Arc::new(ConsoleMailer::new(&mail_config.mail_from))),
+            "file" => Ok(Arc::new(FileMailer::new(
+                &mail_config.mail_from,
+                &mail_config.mail_file_dir,
+            ))),
+            "smtp" => Ok(Arc::new(SmtpMailer::new(
+                &mail_config.mail_from,
+                &mail_config.smtp_host,
+                mail_config.smtp_port,
+                mail_config.smtp_username.clone(),
+                mail_config.smtp_password.clone(),
+                mail_config.smtp_plaintext,
+            )?)),
+            backend => Err(format!("Unsupported mail backend {}", backend)),
+        }
+    }
+
+    /**
+     * Builds the MIME message of an email, as `multipart/alternative` when it has an HTML body
+     *
+     * @param from: &str
+     * @param email: &Email
+     * @return Result<Message, String>
+     */
+    pub fn message(from: &str, email: &Email) -> Result<Message, String> {
+        let from = from.parse::<Mailbox>().map_err(|e| e.to_string())?;
+        let to = email.to.parse::<Mailbox>().map_err(|e| e.to_string())?;
+        let builder = Message::builder()
+            .from(from)
+            .to(to)
+            .subject(email.subject.clone());
+        let message = match &email.html_body {
+            Some(html_body) => builder.multipart(MultiPart::alternative_plain_html(
+                email.text_body.clone(),
+                html_body.clone(),
+            )),
+            None => builder
+                .header(ContentType::TEXT_PLAIN)
+                .body(email.text_body.clone()),
+        };
+        message.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn email() -> Email {
+        Email {
+            to: "user@example.com".to_string(),
+            subject: "Hello".to_string(),
+            text_body: "Plain body".to_string(),
+            html_body: Some("<p>HTML body</p>".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_mailer() {
+        let dir = std::env::temp_dir().join(format!("mails-{}", uuid::Uuid::new_v4()));
+        let mailer = FileMailer::new("no-reply@localhost", dir.to_str().unwrap());
+        mailer.send(&email()).unwrap();
+
+        let files = std::fs::read_dir(&dir).unwrap().collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        let content = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(content.contains("Subject: Hello"));
+        assert!(content.contains("To: user@example.com"));
+        assert!(content.contains("multipart/alternative"));
+        assert!(content.contains("Plain body"));
+        assert!(content.contains("<p>HTML body</p>"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalid_recipient() {
+        let mut email = email();
+        email.to = "not an address".to_string();
+        assert!(MailerService::message("no-reply@localhost", &email).is_err());
+    }
+
+    /// Accepts a single SMTP session and returns the received message data
+    fn smtp_sink(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+        let mut data = String::new();
+        let mut in_data = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            if in_data {
+                if line == ".\r\n" {
+                    in_data = false;
+                    writer.write_all(b"250 OK\r\n").unwrap();
+                } else {
+                    data.push_str(&line);
+                }
+                continue;
+            }
+            let command = line.to_uppercase();
+            if command.starts_with("EHLO") {
+                writer.write_all(b"250 localhost\r\n").unwrap();
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                writer.write_all(b"354 End data with .\r\n").unwrap();
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 Bye\r\n").unwrap();
+                break;
+            } else {
+                writer.write_all(b"250 OK\r\n").unwrap();
+            }
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = std::thread::spawn(move || smtp_sink(listener));
+
+        let mailer =
+            SmtpMailer::new("no-reply@localhost", "127.0.0.1", port, None, None, true).unwrap();
+        mailer.send(&email()).unwrap();
+        drop(mailer);
+
+        let data = sink.join().unwrap();
+        assert!(data.contains("Subject: Hello"));
+        assert!(data.contains("Plain body"));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_mailer_requires_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = std::thread::spawn(move || smtp_sink(listener));
+
+        // The sink does not offer STARTTLS, so nothing is sent
+        let mailer =
+            SmtpMailer::new("no-reply@localhost", "127.0.0.1", port, None, None, false).unwrap();
+        assert!(mailer.send(&email()).is_err());
+        drop(mailer);
+        assert!(!sink.join().unwrap().contains("Subject: Hello"));
+
+        let credentials = SmtpMailer::new(
+            "no-reply@localhost",
+            "127.0.0.1",
+            port,
+            Some("user".to_string()),
+            Some("password".to_string()),
+            true,
+        );
+        assert!(credentials.is_err());
+    }
+}
@@ -0,0 +1,679 @@
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::upsert::excluded;
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use rand::RngCore;
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
+use crate::models::mfa_challenge_model::MfaChallengeModel;
+use crate::models::recovery_code_model::RecoveryCodeModel;
+use crate::models::totp_factor_model::TotpFactorModel;
+use crate::models::user_model::UserModel;
+use crate::schema::{mfa_challenges, recovery_codes, totp_factors, users};
+use crate::schemas::auth_schemas::LoginResponse;
+use crate::schemas::mfa_schemas::{
+    MfaChallengeResponse, MfaEnrollRequest, MfaVerifyRequest, ReauthenticationRequest,
+    TotpEnrollmentResponse,
+};
+use crate::services::auth_service::AuthService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::password_service::PasswordService;
+use crate::services::refresh_token_service::RefreshTokenService;
+use crate::services::role_service::RoleService;
+use crate::services::totp_service::TotpService;
+use crate::services::webauthn_service::WebAuthnService;
+
+/// Number of wrong codes after which a challenge can no longer be used
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+/// Number of recovery codes generated on enrollment
+const RECOVERY_CODE_COUNT: usize = 10;
+const METHOD_TOTP: &str = "totp";
+const METHOD_WEBAUTHN: &str = "webauthn";
+
+/**
+ * Second factor of the login. A password login of a user with a confirmed TOTP factor, a
+ * WebAuthn credential, or a role requiring MFA, only yields a short-lived challenge token which
+ * is exchanged for tokens together with a TOTP code, a recovery code or a WebAuthn assertion.
+ */
+pub struct MfaService;
+
+impl MfaService {
+    /**
+     * Checks whether a user must complete a second factor to log in
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @return Result<bool, Error>
+     */
+    pub async fn is_required(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+    ) -> Result<bool, Error> {
+        if !Self::methods(conn, user.id).await?.is_empty() {
+            return Ok(true);
+        }
+        RoleService::mfa_required(conn, user).await
+    }
+
+    /**
+     * Lists the second factors a user can complete a challenge with
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<Vec<String>, Error>
+     */
+    pub async fn methods(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, Error> {
+        let mut methods = Vec::new();
+        let factor = Self::factor(conn, user_id).await?;
+        if factor.is_some_and(|factor| factor.confirmed_at.is_some()) {
+            methods.push(METHOD_TOTP.to_string());
+        }
+        if WebAuthnService::has_credentials(conn, user_id).await? {
+            methods.push(METHOD_WEBAUTHN.to_string());
+        }
+        Ok(methods)
+    }
+
+    /**
+     * Creates a challenge for a user whose password was verified
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param auth_config: &AuthConfig
+     * @return Result<MfaChallengeResponse, Error>
+     */
+    pub async fn challenge(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        auth_config: &AuthConfig,
+    ) -> Result<MfaChallengeResponse, Error> {
+        let token = RefreshTokenService::generate();
+        let expires_at = chrono::Utc::now().naive_utc()
+            + Duration::seconds(auth_config.mfa_challenge_expire_seconds);
+        diesel::insert_into(mfa_challenges::table)
+            .values(&MfaChallengeModel::new(
+                RefreshTokenService::hash(&token),
+                user.id,
+                expires_at,
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create MFA challenge: {}", e);
+                e
+            })?;
+        let methods = Self::methods(conn, user.id).await?;
+        Ok(MfaChallengeResponse {
+            mfa_required: true,
+            challenge_token: token,
+            expires_in: auth_config.mfa_challenge_expire_seconds,
+            enrollment_required: methods.is_empty(),
+            methods,
+        })
+    }
+
+    /**
+     * Starts a TOTP enrollment, replacing any unconfirmed one. The factor becomes active once a
+     * first code is confirmed.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param auth_config: &AuthConfig
+     * @return Result<TotpEnrollmentResponse, AppError>
+     */
+    pub async fn enroll(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        auth_config: &AuthConfig,
+    ) -> Result<TotpEnrollmentResponse, AppError> {
+        let factor = Self::factor(conn, user.id).await?;
+        if factor.is_some_and(|factor| factor.confirmed_at.is_some()) {
+            log::error!("User {} already has a confirmed TOTP factor", user.id);
+            return Err(AppError::Conflict(
+                "A TOTP factor is already enrolled".to_string(),
+            ));
+        }
+
+        let secret = TotpService::generate_secret();
+        diesel::insert_into(totp_factors::table)
+            .values(&TotpFactorModel::new(user.id, secret.clone()))
+            .on_conflict(totp_factors::user_id)
+            .do_update()
+            .set((
+                totp_factors::secret.eq(excluded(totp_factors::secret)),
+                totp_factors::last_used_step.eq(None::<i64>),
+                totp_factors::created_at.eq(excluded(totp_factors::created_at)),
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to enroll TOTP factor for user {}: {}", user.id, e);
+                e
+            })?;
+        Ok(TotpEnrollmentResponse {
+            provisioning_uri: TotpService::provisioning_uri(
+                &auth_config.totp_issuer,
+                &user.email,
+                &secret,
+            ),
+            secret,
+        })
+    }
+
+    /**
+     * Starts a TOTP enrollment during a login that requires MFA but has no factor yet. The
+     * challenge only proves the password, so it can not add a factor next to an existing one.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param enroll_request: MfaEnrollRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<TotpEnrollmentResponse, AppError>
+     */
+    pub async fn enroll_with_challenge(
+        conn: &mut AsyncPgConnection,
+        enroll_request: MfaEnrollRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<TotpEnrollmentResponse, AppError> {
+        let challenge = Self::find_challenge(conn, &enroll_request.challenge_token).await?;
+        let user = users::table
+            .find(challenge.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        if let Err(e) = Self::require_no_factor(&Self::methods(conn, user.id).await?) {
+            log::error!("User {} already has a second factor", user.id);
+            return Err(e);
+        }
+        Self::enroll(conn, &user, auth_config).await
+    }
+
+    /**
+     * Checks that a user has no second factor, so that one can be enrolled with a challenge
+     *
+     * @param methods: &[String]
+     * @return Result<(), AppError>
+     */
+    pub fn require_no_factor(methods: &[String]) -> Result<(), AppError> {
+        match methods.is_empty() {
+            true => Ok(()),
+            false => Err(AppError::Forbidden(
+                "A second factor is already enrolled".to_string(),
+            )),
+        }
+    }
+
+    /**
+     * Confirms a TOTP enrollment with a first code and generates the recovery codes
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param code: &str
+     * @return Result<Vec<String>, AppError> the recovery codes, in clear
+     */
+    pub async fn confirm(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let factor = totp_factors::table
+            .find(user_id)
+            .filter(totp_factors::confirmed_at.is_null())
+            .get_result::<TotpFactorModel>(conn)
+            .await?;
+        if !Self::verify_totp(conn, &factor, code).await? {
+            log::error!("Wrong TOTP code for user {}", user_id);
+            return Err(AppError::Validation("Invalid code".to_string()));
+        }
+        Ok(Self::activate(conn, user_id).await?)
+    }
+
+    /**
+     * Confirms the identity of a user before a change to their second factors, with a TOTP or
+     * recovery code when they have a confirmed factor, or else their password. A wrong answer
+     * counts towards the lockout like a login.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param reauth: &ReauthenticationRequest
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @return Result<(), AppError>
+     */
+    pub async fn reauthenticate(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        reauth: &ReauthenticationRequest,
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+    ) -> Result<(), AppError> {
+        let user = users::table
+            .find(user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        LockoutService::require_unlocked(conn, &user.email, client_ip).await?;
+
+        let factor = Self::factor(conn, user.id)
+            .await?
+            .filter(|factor| factor.confirmed_at.is_some());
+        let valid = match (&factor, &reauth.code, &reauth.password) {
+            (Some(factor), Some(code), _) => Self::check_code(conn, factor, code).await?,
+            (None, _, Some(password)) => PasswordService::verify(password, &user.password),
+            (Some(_), None, _) => {
+                return Err(AppError::Validation(
+                    "A TOTP or recovery code is required".to_string(),
+                ))
+            }
+            (None, _, None) => {
+                return Err(AppError::Validation("The password is required".to_string()))
+            }
+        };
+        if !valid {
+            log::error!("Wrong credentials for user {}", user.email);
+            LockoutService::record_failure(conn, &user.email, client_ip, auth_config).await?;
+            return Err(AppError::Unauthorized("Wrong credentials".to_string()));
+        }
+        LockoutService::record_success(conn, &user.email).await?;
+        Ok(())
+    }
+
+    /**
+     * Removes the TOTP factor and the recovery codes of a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn disable(conn: &mut AsyncPgConnection, user_id: Uuid) -> Result<usize, Error> {
+        diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user_id)))
+            .execute(conn)
+            .await?;
+        diesel::delete(totp_factors::table.find(user_id))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to disable TOTP factor of user {}: {}", user_id, e);
+                e
+            })
+    }
+
+    /**
+     * Replaces the recovery codes of a user with a confirmed factor
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<Vec<String>, Error> the recovery codes, in clear
+     */
+    pub async fn regenerate_recovery_codes(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, Error> {
+        totp_factors::table
+            .find(user_id)
+            .filter(totp_factors::confirmed_at.is_not_null())
+            .get_result::<TotpFactorModel>(conn)
+            .await?;
+        Self::generate_recovery_codes(conn, user_id).await
+    }
+
+    /**
+     * Exchanges a challenge and a TOTP or recovery code for tokens. Each wrong code counts
+     * against the challenge and towards the lockout of the account, so that logging in again
+     * for a fresh challenge does not allow unlimited guesses. A login confirming a pending
+     * enrollment returns the recovery codes.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param verify_request: MfaVerifyRequest
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginResponse, AppError>
+     */
+    pub async fn verify(
+        conn: &mut AsyncPgConnection,
+        verify_request: MfaVerifyRequest,
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<LoginResponse, AppError> {
+        let unauthorized = || AppError::Unauthorized("Unauthorized".to_string());
+        let challenge = Self::attempt(conn, &verify_request.challenge_token).await?;
+        let user = users::table
+            .find(challenge.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        LockoutService::require_unlocked(conn, &user.email, client_ip).await?;
+
+        let factor = Self::factor(conn, user.id)
+            .await?
+            .ok_or_else(unauthorized)?;
+        let pending = factor.confirmed_at.is_none();
+        if pending && !Self::methods(conn, user.id).await?.is_empty() {
+            log::error!(
+                "User {} has a second factor besides the pending TOTP enrollment",
+                user.id
+            );
+            return Err(unauthorized());
+        }
+        let valid = match pending {
+            true => Self::verify_totp(conn, &factor, &verify_request.code).await?,
+            false => Self::check_code(conn, &factor, &verify_request.code).await?,
+        };
+        if !valid {
+            log::error!("Wrong MFA code for user {}", user.id);
+            LockoutService::record_failure(conn, &user.email, client_ip, auth_config).await?;
+            return Err(unauthorized());
+        }
+        let recovery_codes = match pending {
+            true => Some(Self::activate(conn, user.id).await?),
+            false => None,
+        };
+
+        let mut tokens = Self::complete(conn, &challenge, auth_config, key_ring).await?;
+        tokens.recovery_codes = recovery_codes;
+        Ok(tokens)
+    }
+
+    /**
+     * Finds a challenge that can still be completed
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param challenge_token: &str
+     * @return Result<MfaChallengeModel, AppError>
+     */
+    pub async fn find_challenge(
+        conn: &mut AsyncPgConnection,
+        challenge_token: &str,
+    ) -> Result<MfaChallengeModel, AppError> {
+        mfa_challenges::table
+            .filter(mfa_challenges::token_hash.eq(RefreshTokenService::hash(challenge_token)))
+            .filter(mfa_challenges::consumed_at.is_null())
+            .filter(mfa_challenges::expires_at.gt(chrono::Utc::now().naive_utc()))
+            .filter(mfa_challenges::attempts.lt(MAX_CHALLENGE_ATTEMPTS))
+            .get_result::<MfaChallengeModel>(conn)
+            .await
+            .optional()?
+            .ok_or_else(Self::invalid_challenge)
+    }
+
+    /**
+     * Counts an attempt to complete a challenge. Called before the second factor is checked, so
+     * wrong factors use up the challenge.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param challenge_token: &str
+     * @return Result<MfaChallengeModel, AppError>
+     */
+    pub async fn attempt(
+        conn: &mut AsyncPgConnection,
+        challenge_token: &str,
+    ) -> Result<MfaChallengeModel, AppError> {
+        diesel::update(
+            mfa_challenges::table
+                .filter(mfa_challenges::token_hash.eq(RefreshTokenService::hash(challenge_token)))
+                .filter(mfa_challenges::consumed_at.is_null())
+                .filter(mfa_challenges::expires_at.gt(chrono::Utc::now().naive_utc()))
+                .filter(mfa_challenges::attempts.lt(MAX_CHALLENGE_ATTEMPTS)),
+        )
+        .set(mfa_challenges::attempts.eq(mfa_challenges::attempts + 1))
+        .get_result::<MfaChallengeModel>(conn)
+        .await
+        .optional()?
+        .ok_or_else(Self::invalid_challenge)
+    }
+
+    fn invalid_challenge() -> AppError {
+        log::error!("Invalid MFA challenge");
+        AppError::Unauthorized("Unauthorized".to_string())
+    }
+
+    /**
+     * Consumes a challenge whose second factor was checked, clears the failed attempts of the
+     * account and issues the tokens
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param challenge: &MfaChallengeModel
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginResponse, AppError>
+     */
+    pub async fn complete(
+        conn: &mut AsyncPgConnection,
+        challenge: &MfaChallengeModel,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<LoginResponse, AppError> {
+        let consumed = diesel::update(
+            mfa_challenges::table
+                .find(challenge.id)
+                .filter(mfa_challenges::consumed_at.is_null()),
+        )
+        .set(mfa_challenges::consumed_at.eq(chrono::Utc::now().naive_utc()))
+        .execute(conn)
+        .await?;
+        if consumed == 0 {
+            log::error!("MFA challenge {} was already used", challenge.id);
+            return Err(AppError::Unauthorized("Unauthorized".to_string()));
+        }
+
+        let user = users::table
+            .find(challenge.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        AuthService::require_active(&user)?;
+        LockoutService::record_success(conn, &user.email).await?;
+        AuthService::issue_tokens(conn, user, None, None, auth_config, key_ring).await
+    }
+
+    /**
+     * Checks a TOTP or recovery code of a user who has a confirmed factor. Used codes can not be
+     * used again.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param factor: &TotpFactorModel
+     * @param code: &str
+     * @return Result<bool, Error>
+     */
+    pub async fn check_code(
+        conn: &mut AsyncPgConnection,
+        factor: &TotpFactorModel,
+        code: &str,
+    ) -> Result<bool, Error> {
+        if factor.confirmed_at.is_none() {
+            return Ok(false);
+        }
+        if Self::verify_totp(conn, factor, code).await? {
+            return Ok(true);
+        }
+        let used = diesel::update(
+            recovery_codes::table
+                .filter(recovery_codes::user_id.eq(factor.user_id))
+                .filter(recovery_codes::code_hash.eq(RefreshTokenService::hash(
+                    &Self::normalize_recovery_code(code),
+                )))
+                .filter(recovery_codes::used_at.is_null()),
+        )
+        .set(recovery_codes::used_at.eq(chrono::Utc::now().naive_utc()))
+        .execute(conn)
+        .await?;
+        Ok(used > 0)
+    }
+
+    /**
+     * Checks the second factor of a login that can not go through a challenge. Users without a
+     * confirmed factor who are required to use MFA can not log in this way until they enrolled.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param code: Option<&str>
+     * @return Result<bool, Error>
+     */
+    pub async fn check_login(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        code: Option<&str>,
+    ) -> Result<bool, Error> {
+        if !Self::is_required(conn, user).await? {
+            return Ok(true);
+        }
+        match (Self::factor(conn, user.id).await?, code) {
+            (Some(factor), Some(code)) => Self::check_code(conn, &factor, code).await,
+            _ => Ok(false),
+        }
+    }
+
+    /**
+     * Finds the TOTP factor of a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<Option<TotpFactorModel>, Error>
+     */
+    pub async fn factor(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Option<TotpFactorModel>, Error> {
+        totp_factors::table
+            .find(user_id)
+            .get_result::<TotpFactorModel>(conn)
+            .await
+            .optional()
+    }
+
+    /**
+     * Checks a TOTP code and records its step, so the same code can not be replayed even by a
+     * concurrent request
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param factor: &TotpFactorModel
+     * @param code: &str
+     * @return Result<bool, Error>
+     */
+    async fn verify_totp(
+        conn: &mut AsyncPgConnection,
+        factor: &TotpFactorModel,
+        code: &str,
+    ) -> Result<bool, Error> {
+        let step = match TotpService::verify(
+            &factor.secret,
+            code,
+            chrono::Utc::now().timestamp(),
+            factor.last_used_step,
+        ) {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+        let updated = diesel::update(
+            totp_factors::table.find(factor.user_id).filter(
+                totp_factors::last_used_step
+                    .is_null()
+                    .or(totp_factors::last_used_step.lt(step)),
+            ),
+        )
+        .set(totp_factors::last_used_step.eq(step))
+        .execute(conn)
+        .await?;
+        Ok(updated > 0)
+    }
+
+    async fn activate(conn: &mut AsyncPgConnection, user_id: Uuid) -> Result<Vec<String>, Error> {
+        diesel::update(totp_factors::table.find(user_id))
+            .set(totp_factors::confirmed_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(conn)
+            .await?;
+        log::info!("TOTP factor confirmed for user {}", user_id);
+        Self::generate_recovery_codes(conn, user_id).await
+    }
+
+    async fn generate_recovery_codes(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, Error> {
+        let codes = (0..RECOVERY_CODE_COUNT)
+            .map(|_| Self::generate_recovery_code())
+            .collect::<Vec<String>>();
+        let new_codes = codes
+            .iter()
+            .map(|code| RecoveryCodeModel::new(user_id, RefreshTokenService::hash(code)))
+            .collect::<Vec<RecoveryCodeModel>>();
+
+        diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user_id)))
+            .execute(conn)
+            .await?;
+        diesel::insert_into(recovery_codes::table)
+            .values(&new_codes)
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Failed to create recovery codes for user {}: {}",
+                    user_id,
+                    e
+                );
+                e
+            })?;
+        Ok(codes)
+    }
+
+    fn generate_recovery_code() -> String {
+        let mut bytes = [0u8; 5];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let code = bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        format!("{}-{}", &code[..5], &code[5..])
+    }
+
+    fn normalize_recovery_code(code: &str) -> String {
+        let code = code
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match code.len() {
+            10 => format!("{}-{}", &code[..5], &code[5..]),
+            _ => code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recovery_code() {
+        let code = MfaService::generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(MfaService::normalize_recovery_code(&code), code);
+        assert_eq!(
+            MfaService::normalize_recovery_code(" AB12C DE34F "),
+            "ab12c-de34f"
+        );
+        assert_eq!(
+            MfaService::normalize_recovery_code("ab12c-de34f"),
+            "ab12c-de34f"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_require_no_factor() {
+        assert!(MfaService::require_no_factor(&[]).is_ok());
+
+        let passkey_only = vec![METHOD_WEBAUTHN.to_string()];
+        let denied = MfaService::require_no_factor(&passkey_only).unwrap_err();
+        assert_eq!(denied.status_code(), StatusCode::FORBIDDEN);
+
+        let totp = vec![METHOD_TOTP.to_string()];
+        assert!(MfaService::require_no_factor(&totp).is_err());
+    }
+}
@@ -1,4 +1,25 @@
 pub mod auth_extractor;
 pub mod auth_service;
+pub mod email_verification_service;
+pub mod key_service;
+pub mod lockout_service;
+pub mod mail_template_service;
+pub mod mailer_service;
+pub mod mfa_service;
+pub mod oauth_service;
+pub mod oidc_service;
+pub mod outbox_service;
+pub mod ownership_service;
+pub mod password_policy_service;
 pub mod password_service;
+pub mod permission_extractor;
+pub mod rate_limit_service;
+pub mod refresh_token_service;
+pub mod requirement_extractor;
+pub mod revocation_service;
+pub mod role_service;
+pub mod scope_extractor;
+pub mod scope_service;
 pub mod token_service;
+pub mod totp_service;
+pub mod webauthn_service;
@@ -0,0 +1,783 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::OAuthError;
+use crate::models::authorization_code_model::AuthorizationCodeModel;
+use crate::models::oauth_client_model::OAuthClientModel;
+use crate::models::user_model::UserModel;
+use crate::schema::{authorization_codes, oauth_clients, users};
+use crate::schemas::auth_schemas::{RefreshRequest, TokenClaims};
+use crate::schemas::oauth_schemas::{
+    AuthorizeRequest, IntrospectionResponse, TokenActionRequest, TokenRequest, TokenResponse,
+};
+use crate::services::auth_service::AuthService;
+use crate::services::key_service::KeyRing;
+use crate::services::oidc_service::{OidcService, OIDC_SCOPES};
+use crate::services::password_service::PasswordService;
+use crate::services::refresh_token_service::RefreshTokenService;
+use crate::services::revocation_service::{RevocationCache, RevocationService};
+use crate::services::token_service::TokenService;
+
+const CODE_CHALLENGE_METHOD: &str = "S256";
+
+pub struct OAuthService;
+
+impl OAuthService {
+    /**
+     * Checks that the client is registered and that the redirect URI is in its allowlist.
+     * Errors here must not be redirected to the client.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param client_id: &str
+     * @param redirect_uri: &str
+     * @return Result<OAuthClientModel, OAuthError>
+     */
+    pub async fn find_client(
+        conn: &mut AsyncPgConnection,
+        client_id: &str,
+        redirect_uri: &str,
+    ) -> Result<OAuthClientModel, OAuthError> {
+        let client_id = Uuid::parse_str(client_id)
+            .map_err(|_| OAuthError::InvalidClient("Unknown client".to_string()))?;
+        let client = oauth_clients::table
+            .find(client_id)
+            .get_result::<OAuthClientModel>(conn)
+            .await
+            .map_err(|e| match e {
+                Error::NotFound => OAuthError::InvalidClient("Unknown client".to_string()),
+                e => e.into(),
+            })?;
+        if !client.redirect_uris.iter().any(|uri| uri == redirect_uri) {
+            log::error!(
+                "Redirect URI {} is not allowed for client {}",
+                redirect_uri,
+                client.id
+            );
+            return Err(OAuthError::InvalidRequest(
+                "Redirect URI not allowed".to_string(),
+            ));
+        }
+        Ok(client)
+    }
+
+    /**
+     * Authenticates a confidential client with its secret
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param client_id: Option<&str>
+     * @param client_secret: Option<&str>
+     * @return Result<OAuthClientModel, OAuthError>
+     */
+    pub async fn authenticate_client(
+        conn: &mut AsyncPgConnection,
+        client_id: Option<&str>,
+        client_secret: Option<&str>,
+    ) -> Result<OAuthClientModel, OAuthError> {
+        let invalid_client = || OAuthError::InvalidClient("Invalid client credentials".to_string());
+        let (client_id, client_secret) = match (client_id, client_secret) {
+            (Some(client_id), Some(client_secret)) => (client_id, client_secret),
+            _ => return Err(invalid_client()),
+        };
+        let client_id = Uuid::parse_str(client_id).map_err(|_| invalid_client())?;
+        let client = oauth_clients::table
+            .find(client_id)
+            .get_result::<OAuthClientModel>(conn)
+            .await
+            .map_err(|e| match e {
+                Error::NotFound => invalid_client(),
+                e => e.into(),
+            })?;
+        match &client.client_secret {
+            Some(hash) if PasswordService::verify(client_secret, hash) => Ok(client),
+            _ => {
+                log::error!("Wrong credentials for client {}", client.id);
+                Err(invalid_client())
+            }
+        }
+    }
+
+    /**
+     * Issues an authorization code for the user once the request has been validated
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param client: &OAuthClientModel
+     * @param user: &UserModel
+     * @param authorize_request: &AuthorizeRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<String, OAuthError>
+     */
+    pub async fn authorize(
+        conn: &mut AsyncPgConnection,
+        client: &OAuthClientModel,
+        user: &UserModel,
+        authorize_request: &AuthorizeRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<String, OAuthError> {
+        if authorize_request.response_type != "code" {
+            return Err(OAuthError::UnsupportedResponseType(
+                "Only the code response type is supported".to_string(),
+            ));
+        }
+        let code_challenge = match &authorize_request.code_challenge {
+            Some(code_challenge) if !code_challenge.is_empty() => code_challenge,
+            _ => {
+                return Err(OAuthError::InvalidRequest(
+                    "code_challenge is required".to_string(),
+                ))
+            }
+        };
+        if authorize_request.code_challenge_method.as_deref() != Some(CODE_CHALLENGE_METHOD) {
+            return Err(OAuthError::InvalidRequest(
+                "code_challenge_method must be S256".to_string(),
+            ));
+        }
+        let allowed_scopes: Vec<String> = OIDC_SCOPES
+            .iter()
+            .map(|scope| scope.to_string())
+            .chain(client.scopes.iter().cloned())
+            .collect();
+        let scope = match &authorize_request.scope {
+            Some(scope) => Self::grant_scopes(Some(scope), &allowed_scopes)?.join(" "),
+            None => String::new(),
+        };
+        if !user.is_active {
+            log::error!("User {} is not active", user.id);
+            return Err(OAuthError::AccessDenied("User is not active".to_string()));
+        }
+
+        let code = RefreshTokenService::generate();
+        let expires_at = chrono::Utc::now().naive_utc()
+            + Duration::seconds(auth_config.authorization_code_expire_seconds);
+        diesel::insert_into(authorization_codes::table)
+            .values(&AuthorizationCodeModel::new(
+                RefreshTokenService::hash(&code),
+                client.id,
+                user.id,
+                authorize_request.redirect_uri.clone(),
+                code_challenge.clone(),
+                CODE_CHALLENGE_METHOD.to_string(),
+                expires_at,
+                scope,
+                authorize_request.nonce.clone(),
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create authorization code: {}", e);
+                e
+            })?;
+        log::info!(
+            "Authorization code issued to client {} for user {}",
+            client.id,
+            user.id
+        );
+        Ok(code)
+    }
+
+    /**
+     * Handles a token request for the authorization code, client credentials and refresh token
+     * grants
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token_request: TokenRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<TokenResponse, OAuthError>
+     */
+    pub async fn token(
+        conn: &mut AsyncPgConnection,
+        token_request: TokenRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<TokenResponse, OAuthError> {
+        let tokens = match token_request.grant_type.as_str() {
+            "authorization_code" => {
+                return Self::exchange_code(conn, token_request, auth_config, key_ring).await
+            }
+            "client_credentials" => {
+                return Self::client_credentials(conn, token_request, auth_config, key_ring).await
+            }
+            "refresh_token" => {
+                let refresh_token = token_request.refresh_token.ok_or_else(|| {
+                    OAuthError::InvalidRequest("refresh_token is required".to_string())
+                })?;
+                Self::authenticate_refresh(
+                    conn,
+                    token_request.client_id.as_deref(),
+                    token_request.client_secret.as_deref(),
+                    &refresh_token,
+                )
+                .await?;
+                AuthService::refresh(
+                    conn,
+                    RefreshRequest { refresh_token },
+                    auth_config,
+                    key_ring,
+                )
+                .await?
+            }
+            grant_type => {
+                return Err(OAuthError::UnsupportedGrantType(format!(
+                    "Unsupported grant type {}",
+                    grant_type
+                )))
+            }
+        };
+        Ok(TokenResponse {
+            access_token: tokens.token,
+            token_type: "Bearer".to_string(),
+            expires_in: Duration::minutes(auth_config.token_expire_minutes).num_seconds(),
+            refresh_token: Some(tokens.refresh_token),
+            scope: Some(tokens.scope),
+            id_token: None,
+        })
+    }
+
+    /**
+     * Checks that a refresh token is presented by the client it was issued to, authenticated
+     * with its secret when the client is confidential. Tokens that are no longer active are left
+     * to the refresh itself, which detects their reuse.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param client_id: Option<&str>
+     * @param client_secret: Option<&str>
+     * @param refresh_token: &str
+     * @return Result<(), OAuthError>
+     */
+    async fn authenticate_refresh(
+        conn: &mut AsyncPgConnection,
+        client_id: Option<&str>,
+        client_secret: Option<&str>,
+        refresh_token: &str,
+    ) -> Result<(), OAuthError> {
+        let client_id = client_id
+            .ok_or_else(|| OAuthError::InvalidRequest("client_id is required".to_string()))?;
+        let stored = match RefreshTokenService::find_active(conn, refresh_token).await {
+            Ok(stored) => stored,
+            Err(Error::NotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let owner = match RefreshTokenService::client_of(conn, stored.family_id).await? {
+            Some(owner) if owner.to_string() == client_id => owner,
+            _ => {
+                log::error!(
+                    "Refresh token family {} presented by the wrong client",
+                    stored.family_id
+                );
+                return Err(OAuthError::InvalidGrant(
+                    "Invalid refresh token".to_string(),
+                ));
+            }
+        };
+        let client = oauth_clients::table
+            .find(owner)
+            .get_result::<OAuthClientModel>(conn)
+            .await?;
+        if client.client_secret.is_some() {
+            Self::authenticate_client(conn, Some(client_id), client_secret).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Issues an access token to a confidential client acting on its own behalf. The token
+     * subject is the client id, its tenant is the school of the client, and no refresh token is
+     * issued.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token_request: TokenRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<TokenResponse, OAuthError>
+     */
+    async fn client_credentials(
+        conn: &mut AsyncPgConnection,
+        token_request: TokenRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<TokenResponse, OAuthError> {
+        let client = Self::authenticate_client(
+            conn,
+            token_request.client_id.as_deref(),
+            token_request.client_secret.as_deref(),
+        )
+        .await?;
+        let scope = Self::grant_scopes(token_request.scope.as_deref(), &client.scopes)?.join(" ");
+
+        let creation_time = chrono::Utc::now().timestamp();
+        let expires_in = Duration::minutes(auth_config.token_expire_minutes).num_seconds();
+        let token = TokenService::encode(
+            &key_ring.active(),
+            TokenClaims {
+                jti: Uuid::new_v4(),
+                iss: auth_config.issuer.clone(),
+                aud: auth_config.audiences(),
+                exp: creation_time + expires_in,
+                nbf: creation_time,
+                iat: creation_time,
+                sub: client.id,
+                email: String::new(),
+                tenant_id: client.tenant_id,
+                admin: false,
+                active: true,
+                client_id: Some(client.id),
+                azp: None,
+                scope: Some(scope.clone()),
+                permissions: Vec::new(),
+            },
+        )
+        .await
+        .map_err(|e| OAuthError::ServerError(e.to_string()))?;
+        log::info!("Access token issued to client {}", client.id);
+        Ok(TokenResponse {
+            access_token: token,
+            token_type: "Bearer".to_string(),
+            expires_in,
+            refresh_token: None,
+            scope: Some(scope),
+            id_token: None,
+        })
+    }
+
+    /**
+     * Resolves the scopes granted for a request. Without a requested scope every scope of
+     * the client is granted.
+     *
+     * @param requested: Option<&str>
+     * @param allowed: &[String]
+     * @return Result<Vec<String>, OAuthError>
+     */
+    pub fn grant_scopes(
+        requested: Option<&str>,
+        allowed: &[String],
+    ) -> Result<Vec<String>, OAuthError> {
+        let requested = match requested {
+            Some(requested) if !requested.trim().is_empty() => requested,
+            _ => return Ok(allowed.to_vec()),
+        };
+        let mut scopes: Vec<String> = Vec::new();
+        for scope in requested.split_whitespace() {
+            if !allowed.iter().any(|allowed| allowed == scope) {
+                return Err(OAuthError::InvalidScope(format!(
+                    "Scope {} is not allowed",
+                    scope
+                )));
+            }
+            if !scopes.iter().any(|granted| granted == scope) {
+                scopes.push(scope.to_string());
+            }
+        }
+        Ok(scopes)
+    }
+
+    /**
+     * Exchanges an authorization code for tokens, including an ID token when the openid scope
+     * was granted. The code is checked against the client, its redirect URI and the PKCE
+     * verifier before it is used. A code can only be used once: replaying it revokes the refresh
+     * tokens issued from it.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token_request: TokenRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<TokenResponse, OAuthError>
+     */
+    async fn exchange_code(
+        conn: &mut AsyncPgConnection,
+        token_request: TokenRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<TokenResponse, OAuthError> {
+        let token_request_secret = token_request.client_secret.clone();
+        let (code, redirect_uri, client_id, code_verifier) = match token_request {
+            TokenRequest {
+                code: Some(code),
+                redirect_uri: Some(redirect_uri),
+                client_id: Some(client_id),
+                code_verifier: Some(code_verifier),
+                ..
+            } => (code, redirect_uri, client_id, code_verifier),
+            _ => {
+                return Err(OAuthError::InvalidRequest(
+                    "code, redirect_uri, client_id and code_verifier are required".to_string(),
+                ))
+            }
+        };
+        let invalid_grant = || OAuthError::InvalidGrant("Invalid authorization code".to_string());
+
+        let stored = authorization_codes::table
+            .filter(authorization_codes::code_hash.eq(RefreshTokenService::hash(&code)))
+            .get_result::<AuthorizationCodeModel>(conn)
+            .await
+            .map_err(|e| match e {
+                Error::NotFound => invalid_grant(),
+                e => e.into(),
+            })?;
+
+        let now = chrono::Utc::now().naive_utc();
+        if stored.expires_at <= now {
+            log::error!("Authorization code {} has expired", stored.id);
+            return Err(invalid_grant());
+        }
+        if stored.client_id.to_string() != client_id || stored.redirect_uri != redirect_uri {
+            log::error!(
+                "Authorization code {} presented by the wrong client",
+                stored.id
+            );
+            return Err(invalid_grant());
+        }
+        let client = oauth_clients::table
+            .find(stored.client_id)
+            .get_result::<OAuthClientModel>(conn)
+            .await?;
+        if client.client_secret.is_some() {
+            Self::authenticate_client(conn, Some(&client_id), token_request_secret.as_deref())
+                .await?;
+        }
+        if !Self::verify_challenge(&code_verifier, &stored.code_challenge) {
+            log::error!("Wrong code verifier for authorization code {}", stored.id);
+            return Err(invalid_grant());
+        }
+
+        // Consumed only once the request is known to be valid, so that a wrong request can not
+        // use up the code. The refresh token family of the exchange is the code id, so a replay
+        // can revoke it.
+        let consumed = diesel::update(
+            authorization_codes::table
+                .find(stored.id)
+                .filter(authorization_codes::consumed_at.is_null()),
+        )
+        .set(authorization_codes::consumed_at.eq(now))
+        .execute(conn)
+        .await?;
+        if consumed == 0 {
+            log::warn!(
+                "Authorization code {} reused. Revoking issued tokens",
+                stored.id
+            );
+            RefreshTokenService::revoke_family(conn, stored.id).await?;
+            return Err(invalid_grant());
+        }
+
+        let user = users::table
+            .find(stored.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        if !user.is_active {
+            log::error!("User {} is not active", user.id);
+            return Err(invalid_grant());
+        }
+        let id_token = if OidcService::has_scope(&stored.scope, "openid") {
+            Some(OidcService::id_token(conn, &user, &stored, auth_config, key_ring).await?)
+        } else {
+            None
+        };
+        let requested_scope = (!stored.scope.is_empty()).then(|| stored.scope.clone());
+        let tokens = AuthService::issue_tokens(
+            conn,
+            user,
+            Some(stored.id),
+            requested_scope,
+            auth_config,
+            key_ring,
+        )
+        .await?;
+        Ok(TokenResponse {
+            access_token: tokens.token,
+            token_type: "Bearer".to_string(),
+            expires_in: Duration::minutes(auth_config.token_expire_minutes).num_seconds(),
+            refresh_token: Some(tokens.refresh_token),
+            scope: Some(tokens.scope),
+            id_token,
+        })
+    }
+
+    /**
+     * Tells a resource server whether a token is active and who it was issued to (RFC 7662).
+     * Tokens that are unknown, expired or revoked are reported as inactive.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param revocation_cache: &RevocationCache
+     * @param request: TokenActionRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<IntrospectionResponse, OAuthError>
+     */
+    pub async fn introspect(
+        conn: &mut AsyncPgConnection,
+        revocation_cache: &RevocationCache,
+        request: TokenActionRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<IntrospectionResponse, OAuthError> {
+        let client = Self::authenticate_client(
+            conn,
+            request.client_id.as_deref(),
+            request.client_secret.as_deref(),
+        )
+        .await?;
+        log::info!("Client {} introspecting a token", client.id);
+
+        if request.token_type_hint.as_deref() != Some("refresh_token") {
+            if let Ok(claims) = TokenService::decode(&request.token, key_ring, auth_config).await {
+                let revoked = RevocationService::is_revoked(
+                    conn,
+                    revocation_cache,
+                    claims.jti,
+                    claims.sub,
+                    claims.iat,
+                )
+                .await?;
+                if revoked {
+                    return Ok(IntrospectionResponse::default());
+                }
+                return Ok(IntrospectionResponse {
+                    active: true,
+                    sub: Some(claims.sub),
+                    exp: Some(claims.exp),
+                    iat: Some(claims.iat),
+                    client_id: Self::token_owner(&claims),
+                    scope: claims.scope,
+                    token_type: Some("Bearer".to_string()),
+                    iss: Some(claims.iss),
+                    jti: Some(claims.jti),
+                });
+            }
+        }
+        match RefreshTokenService::find_active(conn, &request.token).await {
+            Ok(refresh_token) => Ok(IntrospectionResponse {
+                active: true,
+                sub: Some(refresh_token.user_id),
+                exp: Some(refresh_token.expires_at.and_utc().timestamp()),
+                iat: Some(refresh_token.created_at.and_utc().timestamp()),
+                token_type: Some("refresh_token".to_string()),
+                iss: Some(auth_config.issuer.clone()),
+                ..Default::default()
+            }),
+            Err(Error::NotFound) => Ok(IntrospectionResponse::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /**
+     * Revokes an access or refresh token (RFC 7009). Revoking a refresh token revokes its
+     * whole family. Clients can only revoke the tokens issued to them. Unknown tokens are
+     * ignored, so clients can not probe for valid tokens.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param revocation_cache: &RevocationCache
+     * @param request: TokenActionRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<(), OAuthError>
+     */
+    pub async fn revoke(
+        conn: &mut AsyncPgConnection,
+        revocation_cache: &RevocationCache,
+        request: TokenActionRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<(), OAuthError> {
+        let client = Self::authenticate_client(
+            conn,
+            request.client_id.as_deref(),
+            request.client_secret.as_deref(),
+        )
+        .await?;
+
+        if request.token_type_hint.as_deref() != Some("refresh_token") {
+            if let Ok(claims) = TokenService::decode(&request.token, key_ring, auth_config).await {
+                Self::require_token_owner(&client, Self::token_owner(&claims), &claims.jti)?;
+                RevocationService::revoke_token(
+                    conn,
+                    revocation_cache,
+                    claims.jti,
+                    claims.sub,
+                    claims.exp,
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+        match RefreshTokenService::find_active(conn, &request.token).await {
+            Ok(refresh_token) => {
+                let owner = RefreshTokenService::client_of(conn, refresh_token.family_id).await?;
+                Self::require_token_owner(&client, owner, &refresh_token.family_id)?;
+                RefreshTokenService::revoke_family(conn, refresh_token.family_id).await?;
+                log::info!(
+                    "Client {} revoked refresh token family {}",
+                    client.id,
+                    refresh_token.family_id
+                );
+                Ok(())
+            }
+            Err(Error::NotFound) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /**
+     * Finds the OAuth client an access token was issued to: the client itself for client
+     * credentials tokens, or the authorized party for user tokens
+     *
+     * @param claims: &TokenClaims
+     * @return Option<Uuid>
+     */
+    pub fn token_owner(claims: &TokenClaims) -> Option<Uuid> {
+        claims.client_id.or(claims.azp)
+    }
+
+    /**
+     * Rejects a request on a token that was not issued to the client, as required by RFC 7009
+     * section 2.1. Tokens issued outside of OAuth belong to no client.
+     *
+     * @param client: &OAuthClientModel
+     * @param owner: Option<Uuid>
+     * @param token_id: &Uuid
+     * @return Result<(), OAuthError>
+     */
+    pub fn require_token_owner(
+        client: &OAuthClientModel,
+        owner: Option<Uuid>,
+        token_id: &Uuid,
+    ) -> Result<(), OAuthError> {
+        if owner == Some(client.id) {
+            return Ok(());
+        }
+        log::error!(
+            "Client {} is not allowed to revoke token {}",
+            client.id,
+            token_id
+        );
+        Err(OAuthError::UnauthorizedClient(
+            "The token was issued to another client".to_string(),
+        ))
+    }
+
+    /**
+     * Checks a PKCE code verifier against the S256 challenge of the authorization request
+     *
+     * @param code_verifier: &str
+     * @param code_challenge: &str
+     * @return bool
+     */
+    pub fn verify_challenge(code_verifier: &str, code_challenge: &str) -> bool {
+        // RFC 7636 section 4.1
+        if !(43..=128).contains(&code_verifier.len()) {
+            return false;
+        }
+        URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes())) == code_challenge
+    }
+
+    /**
+     * Builds the location the user agent is sent back to, with the given query parameters
+     *
+     * @param redirect_uri: &str
+     * @param params: &[(&str, &str)]
+     * @return String
+     */
+    pub fn redirect_location(redirect_uri: &str, params: &[(&str, &str)]) -> String {
+        let separator = if redirect_uri.contains('?') { '&' } else { '?' };
+        let query = serde_urlencoded::to_string(params).unwrap_or_default();
+        format!("{}{}{}", redirect_uri, separator, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_verify_challenge() {
+        // RFC 7636 appendix B
+        let code_verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let code_challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+        assert!(OAuthService::verify_challenge(
+            code_verifier,
+            code_challenge
+        ));
+        assert!(!OAuthService::verify_challenge(
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXK",
+            code_challenge
+        ));
+        assert!(!OAuthService::verify_challenge("short", code_challenge));
+    }
+
+    #[tokio::test]
+    async fn test_grant_scopes() {
+        let allowed = vec!["schools:read".to_string(), "students:read".to_string()];
+        assert_eq!(OAuthService::grant_scopes(None, &allowed).unwrap(), allowed);
+        assert_eq!(
+            OAuthService::grant_scopes(Some("students:read students:read"), &allowed).unwrap(),
+            vec!["students:read".to_string()]
+        );
+        assert!(OAuthService::grant_scopes(Some("schools:read users:write"), &allowed).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_require_token_owner() {
+        let client_a = OAuthClientModel::new("a".to_string(), Vec::new(), None, Vec::new(), None);
+        let client_b = OAuthClientModel::new("b".to_string(), Vec::new(), None, Vec::new(), None);
+        let now = chrono::Utc::now().timestamp();
+        let claims = TokenClaims {
+            jti: Uuid::new_v4(),
+            iss: String::new(),
+            aud: Vec::new(),
+            exp: now + 60,
+            nbf: now,
+            iat: now,
+            sub: client_a.id,
+            email: String::new(),
+            tenant_id: None,
+            admin: false,
+            active: true,
+            client_id: Some(client_a.id),
+            azp: None,
+            scope: None,
+            permissions: Vec::new(),
+        };
+        let owner = OAuthService::token_owner(&claims);
+        assert!(OAuthService::require_token_owner(&client_a, owner, &claims.jti).is_ok());
+        assert!(matches!(
+            OAuthService::require_token_owner(&client_b, owner, &claims.jti),
+            Err(OAuthError::UnauthorizedClient(_))
+        ));
+
+        let user_token = TokenClaims {
+            sub: Uuid::new_v4(),
+            client_id: None,
+            azp: Some(client_a.id),
+            ..claims
+        };
+        let owner = OAuthService::token_owner(&user_token);
+        assert!(OAuthService::require_token_owner(&client_a, owner, &user_token.jti).is_ok());
+        assert!(OAuthService::require_token_owner(&client_b, owner, &user_token.jti).is_err());
+        assert!(OAuthService::require_token_owner(&client_b, None, &user_token.jti).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_location() {
+        assert_eq!(
+            OAuthService::redirect_location(
+                "https://app.domain.com/callback",
+                &[("code", "abc"), ("state", "a b&c")]
+            ),
+            "https://app.domain.com/callback?code=abc&state=a+b%26c"
+        );
+        assert_eq!(
+            OAuthService::redirect_location(
+                "com.domain.app:/callback?flow=login",
+                &[("error", "access_denied")]
+            ),
+            "com.domain.app:/callback?flow=login&error=access_denied"
+        );
+    }
+}
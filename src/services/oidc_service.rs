@@ -0,0 +1,214 @@
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
+use crate::models::authorization_code_model::AuthorizationCodeModel;
+use crate::models::student_model::StudentModel;
+use crate::models::user_model::UserModel;
+use crate::schema::{students, users};
+use crate::schemas::oidc_schemas::{IdTokenClaims, OpenIdConfiguration, UserInfoResponse};
+use crate::services::key_service::KeyRing;
+use crate::services::scope_service::API_SCOPES;
+use crate::services::token_service::TokenService;
+
+/// Scopes defined by OpenID Connect, available to every client
+pub const OIDC_SCOPES: [&str; 3] = ["openid", "profile", "email"];
+
+pub struct OidcService;
+
+impl OidcService {
+    /**
+     * Builds the discovery document. Endpoints are published under the issuer URL
+     *
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return OpenIdConfiguration
+     */
+    pub fn configuration(auth_config: &AuthConfig, key_ring: &KeyRing) -> OpenIdConfiguration {
+        let issuer = auth_config.issuer.trim_end_matches('/');
+        let to_strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        OpenIdConfiguration {
+            issuer: auth_config.issuer.clone(),
+            authorization_endpoint: format!("{}/oauth/authorize", issuer),
+            token_endpoint: format!("{}/oauth/token", issuer),
+            userinfo_endpoint: format!("{}/userinfo", issuer),
+            jwks_uri: format!("{}/.well-known/jwks.json", issuer),
+            introspection_endpoint: format!("{}/oauth/introspect", issuer),
+            revocation_endpoint: format!("{}/oauth/revoke", issuer),
+            response_types_supported: to_strings(&["code"]),
+            grant_types_supported: to_strings(&[
+                "authorization_code",
+                "client_credentials",
+                "refresh_token",
+            ]),
+            subject_types_supported: to_strings(&["public"]),
+            id_token_signing_alg_values_supported: vec![format!(
+                "{:?}",
+                key_ring.active().algorithm
+            )],
+            scopes_supported: OIDC_SCOPES
+                .iter()
+                .chain(API_SCOPES.iter())
+                .map(|scope| scope.to_string())
+                .collect(),
+            token_endpoint_auth_methods_supported: to_strings(&[
+                "none",
+                "client_secret_basic",
+                "client_secret_post",
+            ]),
+            code_challenge_methods_supported: to_strings(&["S256"]),
+            claims_supported: to_strings(&[
+                "iss",
+                "sub",
+                "aud",
+                "exp",
+                "iat",
+                "auth_time",
+                "nonce",
+                "email",
+                "email_verified",
+                "given_name",
+                "family_name",
+            ]),
+        }
+    }
+
+    /**
+     * Issues the ID token of an authorization code exchange. Profile and email claims are
+     * only included when their scope was granted.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param code: &AuthorizationCodeModel
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<String, AppError>
+     */
+    pub async fn id_token(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        code: &AuthorizationCodeModel,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<String, AppError> {
+        let (given_name, family_name) = if Self::has_scope(&code.scope, "profile") {
+            Self::names(conn, user.id).await?
+        } else {
+            (None, None)
+        };
+        let creation_time = chrono::Utc::now().timestamp();
+        let id_token = TokenService::encode(
+            &key_ring.active(),
+            IdTokenClaims {
+                iss: auth_config.issuer.clone(),
+                sub: user.id,
+                aud: code.client_id.to_string(),
+                exp: creation_time
+                    + Duration::minutes(auth_config.token_expire_minutes).num_seconds(),
+                iat: creation_time,
+                // The user authenticated when the code was issued
+                auth_time: code.created_at.and_utc().timestamp(),
+                nonce: code.nonce.clone(),
+                email: Self::has_scope(&code.scope, "email").then(|| user.email.clone()),
+                email_verified: Self::has_scope(&code.scope, "email")
+                    .then_some(user.email_verified_at.is_some()),
+                given_name,
+                family_name,
+            },
+        )
+        .await;
+        id_token.map_err(|e| {
+            log::error!("Failed to encode id token: {}", e);
+            AppError::Internal(format!("Failed to encode id token: {}", e))
+        })
+    }
+
+    /**
+     * Returns the claims about the authenticated user allowed by the scope of the token, like
+     * the ID token
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param scope: &str
+     * @return Result<UserInfoResponse, Error>
+     */
+    pub async fn user_info(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        scope: &str,
+    ) -> Result<UserInfoResponse, Error> {
+        let user = users::table
+            .find(user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        let (given_name, family_name) = if Self::has_scope(scope, "profile") {
+            Self::names(conn, user.id).await?
+        } else {
+            (None, None)
+        };
+        let email = Self::has_scope(scope, "email");
+        Ok(UserInfoResponse {
+            sub: user.id,
+            email_verified: email.then(|| user.email_verified_at.is_some()),
+            email: email.then_some(user.email),
+            given_name,
+            family_name,
+        })
+    }
+
+    /**
+     * Finds the names of a user from their oldest student profile, the one their tenant is
+     * taken from, if they have one
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<(Option<String>, Option<String>), Error>
+     */
+    async fn names(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<(Option<String>, Option<String>), Error> {
+        let student = students::table
+            .filter(students::user_id.eq(user_id))
+            .order((students::created_at, students::id))
+            .first::<StudentModel>(conn)
+            .await;
+        match student {
+            Ok(student) => Ok((Some(student.first_name), Some(student.last_name))),
+            Err(Error::NotFound) => Ok((None, None)),
+            Err(e) => {
+                log::error!("Failed to get student of user {}: {}", user_id, e);
+                Err(e)
+            }
+        }
+    }
+
+    /**
+     * Checks whether a space separated scope contains the given scope
+     *
+     * @param scope: &str
+     * @param name: &str
+     * @return bool
+     */
+    pub fn has_scope(scope: &str, name: &str) -> bool {
+        scope.split_whitespace().any(|scope| scope == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_has_scope() {
+        assert!(OidcService::has_scope("openid profile", "openid"));
+        assert!(OidcService::has_scope("openid  profile", "profile"));
+        assert!(!OidcService::has_scope("openid profile", "email"));
+        assert!(!OidcService::has_scope("", "openid"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::QueryDsl;
+use diesel::{BoolExpressionMethods, ExpressionMethods};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::configs::common::MailConfig;
+use crate::helper::type_alias::DbPool;
+use crate::models::mail_outbox_model::MailOutboxModel;
+use crate::schema::mail_outbox;
+use crate::services::mailer_service::{Email, Mailer};
+
+/// Number of emails delivered per poll
+const BATCH_SIZE: i64 = 50;
+/// Delay before the first retry, doubled on every further attempt
+const RETRY_BASE_SECONDS: i64 = 30;
+/// Longest delay between two attempts
+const RETRY_MAX_SECONDS: i64 = 6 * 60 * 60;
+/// How long a worker holds an email it is sending before another one may pick it up
+const CLAIM_SECONDS: i64 = 5 * 60;
+
+pub struct OutboxService;
+
+impl OutboxService {
+    /**
+     * Queues an email. It is stored with the caller's changes, so it survives restarts and is
+     * delivered by the outbox worker
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param email: Email
+     * @return Result<(), Error>
+     */
+    pub async fn enqueue(conn: &mut AsyncPgConnection, email: Email) -> Result<(), Error> {
+        diesel::insert_into(mail_outbox::table)
+            .values(&MailOutboxModel::new(email))
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                log::error!("Failed to queue email: {}", e);
+                e
+            })
+    }
+
+    /**
+     * Sends the emails that are due. Each email is claimed before it is sent, so concurrent
+     * workers never deliver the same email twice. Failed deliveries are retried with an
+     * exponential backoff until `mail_max_attempts` is reached. Once an email is sent or given
+     * up on, its body is cleared, since it may hold single-use tokens.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param mailer: &Arc<dyn Mailer>
+     * @param mail_config: &MailConfig
+     * @return Result<usize, Error> the number of emails sent
+     */
+    pub async fn deliver_due(
+        conn: &mut AsyncPgConnection,
+        mailer: &Arc<dyn Mailer>,
+        mail_config: &MailConfig,
+    ) -> Result<usize, Error> {
+        let now = chrono::Utc::now().naive_utc();
+        let due = mail_outbox::table
+            .filter(mail_outbox::sent_at.is_null())
+            .filter(mail_outbox::failed_at.is_null())
+            .filter(mail_outbox::next_attempt_at.le(now))
+            .order(mail_outbox::next_attempt_at)
+            .limit(BATCH_SIZE)
+            .get_results::<MailOutboxModel>(conn)
+            .await?;
+
+        let mut sent = 0;
+        for message in due {
+            let claimed = diesel::update(
+                mail_outbox::table
+                    .find(message.id)
+                    .filter(mail_outbox::sent_at.is_null())
+                    .filter(mail_outbox::next_attempt_at.eq(message.next_attempt_at)),
+            )
+            .set(mail_outbox::next_attempt_at.eq(now + Duration::seconds(CLAIM_SECONDS)))
+            .execute(conn)
+            .await?;
+            if claimed == 0 {
+                continue;
+            }
+
+            let sender = mailer.clone();
+            let email = message.email();
+            let result = tokio::task::spawn_blocking(move || sender.send(&email))
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+            let attempts = message.attempts + 1;
+            let finished_at = chrono::Utc::now().naive_utc();
+            match result {
+                Ok(()) => {
+                    diesel::update(mail_outbox::table.find(message.id))
+                        .set((
+                            mail_outbox::attempts.eq(attempts),
+                            mail_outbox::sent_at.eq(finished_at),
+                            mail_outbox::text_body.eq(""),
+                            mail_outbox::html_body.eq(None::<String>),
+                        ))
+                        .execute(conn)
+                        .await?;
+                    sent += 1;
+                }
+                Err(e) if attempts >= mail_config.mail_max_attempts => {
+                    log::error!(
+                        "Giving up on email {} after {} attempts: {}",
+                        message.id,
+                        attempts,
+                        e
+                    );
+                    diesel::update(mail_outbox::table.find(message.id))
+                        .set((
+                            mail_outbox::attempts.eq(attempts),
+                            mail_outbox::last_error.eq(e),
+                            mail_outbox::failed_at.eq(finished_at),
+                            mail_outbox::text_body.eq(""),
+                            mail_outbox::html_body.eq(None::<String>),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+                Err(e) => {
+                    log::error!("Failed to send email {}: {}", message.id, e);
+                    diesel::update(mail_outbox::table.find(message.id))
+                        .set((
+                            mail_outbox::attempts.eq(attempts),
+                            mail_outbox::last_error.eq(e),
+                            mail_outbox::next_attempt_at.eq(finished_at + Self::backoff(attempts)),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+            }
+        }
+        Ok(sent)
+    }
+
+    /**
+     * Deletes the emails sent or given up on more than `mail_outbox_retention_days` ago
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param mail_config: &MailConfig
+     * @return Result<usize, Error> the number of emails deleted
+     */
+    pub async fn purge(
+        conn: &mut AsyncPgConnection,
+        mail_config: &MailConfig,
+    ) -> Result<usize, Error> {
+        let retained_since =
+            chrono::Utc::now().naive_utc() - Duration::days(mail_config.mail_outbox_retention_days);
+        diesel::delete(
+            mail_outbox::table.filter(
+                mail_outbox::sent_at
+                    .lt(retained_since)
+                    .or(mail_outbox::failed_at.lt(retained_since)),
+            ),
+        )
+        .execute(conn)
+        .await
+    }
+
+    /**
+     * Delivers the outbox and purges the old emails forever
+     *
+     * @param pool: DbPool
+     * @param mailer: Arc<dyn Mailer>
+     * @param mail_config: MailConfig
+     */
+    pub async fn deliver_periodically(
+        pool: DbPool,
+        mailer: Arc<dyn Mailer>,
+        mail_config: MailConfig,
+    ) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            mail_config.mail_outbox_poll_seconds,
+        ));
+        loop {
+            interval.tick().await;
+            let mut conn = match pool.get().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::error!("Failed to get pool: {}", e);
+                    continue;
+                }
+            };
+            match Self::deliver_due(&mut conn, &mailer, &mail_config).await {
+                Ok(0) => {}
+                Ok(sent) => log::info!("Sent {} queued emails", sent),
+                Err(e) => log::error!("Failed to deliver queued emails: {}", e),
+            }
+            if let Err(e) = Self::purge(&mut conn, &mail_config).await {
+                log::error!("Failed to purge sent emails: {}", e);
+            }
+        }
+    }
+
+    /**
+     * Delay before the next attempt after `attempts` failed ones
+     *
+     * @param attempts: i32
+     * @return Duration
+     */
+    fn backoff(attempts: i32) -> Duration {
+        let exponent = attempts.clamp(1, 16) as u32 - 1;
+        Duration::seconds((RETRY_BASE_SECONDS << exponent).min(RETRY_MAX_SECONDS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_backoff() {
+        assert_eq!(OutboxService::backoff(1), Duration::seconds(30));
+        assert_eq!(OutboxService::backoff(2), Duration::seconds(60));
+        assert_eq!(OutboxService::backoff(4), Duration::seconds(240));
+        assert_eq!(OutboxService::backoff(30), Duration::seconds(6 * 60 * 60));
+    }
+}
@@ -0,0 +1,268 @@
+use diesel::result::Error;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::helper::exceptions::AppError;
+use crate::schema::{classes, schedules, students};
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::scope_extractor::{RequireScope, Scope};
+
+/**
+ * Policy deciding whether the caller may act on a resource. Administrators may act on every
+ * resource, users only on their own account and on the student records linked to it. Client
+ * tokens own no account and may only act on the student records of the school of their client.
+ */
+pub struct OwnershipService;
+
+impl OwnershipService {
+    /**
+     * Checks whether the caller is the given user or an administrator. Client tokens never own
+     * a user account.
+     *
+     * @param auth: &AuthExtractorService
+     * @param user_id: Uuid
+     * @return bool
+     */
+    pub fn can_access(auth: &AuthExtractorService, user_id: Uuid) -> bool {
+        auth.admin || (auth.client_id.is_none() && auth.id == user_id)
+    }
+
+    /**
+     * Checks whether the caller may act on a student record of the given user and school.
+     * Client tokens may act on the records of the school of their client.
+     *
+     * @param auth: &AuthExtractorService
+     * @param user_id: Uuid
+     * @param school_id: Uuid
+     * @return bool
+     */
+    fn can_access_record(auth: &AuthExtractorService, user_id: Uuid, school_id: Uuid) -> bool {
+        Self::can_access(auth, user_id)
+            || (auth.client_id.is_some() && auth.tenant().contains(school_id))
+    }
+
+    /**
+     * Rejects the request unless the caller is the given user or an administrator
+     *
+     * @param auth: &AuthExtractorService
+     * @param user_id: Uuid
+     * @return Result<(), AppError>
+     */
+    pub fn require_user(auth: &AuthExtractorService, user_id: Uuid) -> Result<(), AppError> {
+        if Self::can_access(auth, user_id) {
+            return Ok(());
+        }
+        log::error!("User {} is not allowed to access user {}", auth.id, user_id);
+        Err(AppError::Forbidden("Forbidden".to_string()))
+    }
+
+    /**
+     * Rejects the request when it grants admin rights and the caller is not an authenticated
+     * administrator, such as an anonymous sign up
+     *
+     * @param auth: Option<&AuthExtractorService>
+     * @param is_admin: bool
+     * @return Result<(), AppError>
+     */
+    pub fn require_admin_grant(
+        auth: Option<&AuthExtractorService>,
+        is_admin: bool,
+    ) -> Result<(), AppError> {
+        if !is_admin || auth.is_some_and(|auth| auth.admin) {
+            return Ok(());
+        }
+        log::error!(
+            "{} is not allowed to grant admin rights",
+            auth.map(|auth| format!("User {}", auth.id))
+                .unwrap_or_else(|| "Anonymous caller".to_string())
+        );
+        Err(AppError::Forbidden("Forbidden".to_string()))
+    }
+
+    /**
+     * Rejects the request unless the student belongs to the caller, or to the school of the
+     * client of the caller
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param auth: &RequireScope<S>
+     * @param student_id: Uuid
+     * @return Result<(), AppError>
+     */
+    pub async fn require_student<S: Scope>(
+        conn: &mut AsyncPgConnection,
+        auth: &RequireScope<S>,
+        student_id: Uuid,
+    ) -> Result<(), AppError> {
+        if auth.admin {
+            return Ok(());
+        }
+        let owner = students::table
+            .find(student_id)
+            .select((students::user_id, students::school_id))
+            .first::<(Uuid, Uuid)>(conn)
+            .await
+            .optional();
+        Self::require_owner(auth, owner, "student", student_id)
+    }
+
+    /**
+     * Rejects the request unless the class belongs to a student of the caller, or to the school of
+     * the client of the caller
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param auth: &RequireScope<S>
+     * @param class_id: Uuid
+     * @return Result<(), AppError>
+     */
+    pub async fn require_class<S: Scope>(
+        conn: &mut AsyncPgConnection,
+        auth: &RequireScope<S>,
+        class_id: Uuid,
+    ) -> Result<(), AppError> {
+        if auth.admin {
+            return Ok(());
+        }
+        let owner = classes::table
+            .inner_join(students::table)
+            .filter(classes::id.eq(class_id))
+            .select((students::user_id, students::school_id))
+            .first::<(Uuid, Uuid)>(conn)
+            .await
+            .optional();
+        Self::require_owner(auth, owner, "class", class_id)
+    }
+
+    /**
+     * Rejects the request unless the schedule belongs to a student of the caller, or to the school
+     * of the client of the caller
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param auth: &RequireScope<S>
+     * @param schedule_id: Uuid
+     * @return Result<(), AppError>
+     */
+    pub async fn require_schedule<S: Scope>(
+        conn: &mut AsyncPgConnection,
+        auth: &RequireScope<S>,
+        schedule_id: Uuid,
+    ) -> Result<(), AppError> {
+        if auth.admin {
+            return Ok(());
+        }
+        let owner = schedules::table
+            .inner_join(students::table)
+            .filter(schedules::id.eq(schedule_id))
+            .select((students::user_id, students::school_id))
+            .first::<(Uuid, Uuid)>(conn)
+            .await
+            .optional();
+        Self::require_owner(auth, owner, "schedule", schedule_id)
+    }
+
+    /**
+     * Compares the owning user and school of a resource with the caller. Missing resources are
+     * reported as forbidden so their existence is not disclosed.
+     *
+     * @param auth: &AuthExtractorService
+     * @param owner: Result<Option<(Uuid, Uuid)>, Error>
+     * @param resource: &str
+     * @param id: Uuid
+     * @return Result<(), AppError>
+     */
+    fn require_owner(
+        auth: &AuthExtractorService,
+        owner: Result<Option<(Uuid, Uuid)>, Error>,
+        resource: &str,
+        id: Uuid,
+    ) -> Result<(), AppError> {
+        match owner {
+            Ok(Some((user_id, school_id))) if Self::can_access_record(auth, user_id, school_id) => {
+                Ok(())
+            }
+            Ok(_) => {
+                log::error!(
+                    "User {} is not allowed to access {} {}",
+                    auth.id,
+                    resource,
+                    id
+                );
+                Err(AppError::Forbidden("Forbidden".to_string()))
+            }
+            Err(e) => {
+                log::error!("Failed to get owner of {} {}: {}", resource, id, e);
+                Err(AppError::from(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(id: Uuid, admin: bool, client_id: Option<Uuid>) -> AuthExtractorService {
+        AuthExtractorService {
+            jti: Uuid::new_v4(),
+            exp: 0,
+            id,
+            tenant_id: None,
+            email: String::new(),
+            admin,
+            active: true,
+            client_id,
+            scopes: Vec::new(),
+            permissions: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_can_access() {
+        let id = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let user = principal(id, false, None);
+        assert!(OwnershipService::can_access(&user, id));
+        assert!(!OwnershipService::can_access(&user, other));
+
+        let admin = principal(id, true, None);
+        assert!(OwnershipService::can_access(&admin, other));
+
+        let client = principal(id, false, Some(id));
+        assert!(!OwnershipService::can_access(&client, id));
+    }
+
+    #[tokio::test]
+    async fn test_can_access_record() {
+        let id = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let school = Uuid::new_v4();
+        let user = principal(id, false, None);
+        assert!(OwnershipService::can_access_record(&user, id, school));
+        assert!(!OwnershipService::can_access_record(&user, other, school));
+
+        let mut client = principal(id, false, Some(Uuid::new_v4()));
+        assert!(!OwnershipService::can_access_record(&client, other, school));
+        client.tenant_id = Some(school);
+        assert!(OwnershipService::can_access_record(&client, other, school));
+        assert!(!OwnershipService::can_access_record(
+            &client,
+            other,
+            Uuid::new_v4()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_grant() {
+        let id = Uuid::new_v4();
+        let user = principal(id, false, None);
+        let admin = principal(id, true, None);
+        assert!(OwnershipService::require_admin_grant(None, false).is_ok());
+        assert!(OwnershipService::require_admin_grant(Some(&user), false).is_ok());
+        assert!(OwnershipService::require_admin_grant(Some(&admin), true).is_ok());
+        assert_eq!(
+            OwnershipService::require_admin_grant(None, true),
+            Err(AppError::Forbidden("Forbidden".to_string()))
+        );
+        assert!(OwnershipService::require_admin_grant(Some(&user), true).is_err());
+    }
+}
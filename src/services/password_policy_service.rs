@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::configs::common::PasswordPolicyConfig;
+use crate::helper::exceptions::AppError;
+
+/// Passwords rejected whatever the configured breached list
+const COMMON_PASSWORDS: &str = include_str!("../data/common_passwords.txt");
+
+/// Policy checked by `PasswordPolicyService`, set once at startup by `configure`
+static POLICY: OnceLock<PasswordPolicy> = OnceLock::new();
+
+/// A rule of the password policy that a password breaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum PasswordViolation {
+    TooShort { min_length: usize },
+    TooLong { max_length: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    TooPredictable { min_entropy_bits: f64 },
+    ContainsEmail,
+    Breached,
+    RecentlyUsed { history_size: i64 },
+}
+
+impl PasswordViolation {
+    pub fn message(&self) -> String {
+        match self {
+            PasswordViolation::TooShort { min_length } => {
+                format!("Password must be at least {} characters long", min_length)
+            }
+            PasswordViolation::TooLong { max_length } => {
+                format!("Password must be at most {} characters long", max_length)
+            }
+            PasswordViolation::MissingLowercase => {
+                "Password must contain a lowercase letter".to_string()
+            }
+            PasswordViolation::MissingUppercase => {
+                "Password must contain an uppercase letter".to_string()
+            }
+            PasswordViolation::MissingDigit => "Password must contain a digit".to_string(),
+            PasswordViolation::MissingSymbol => "Password must contain a symbol".to_string(),
+            PasswordViolation::TooPredictable { .. } => "Password is too predictable".to_string(),
+            PasswordViolation::ContainsEmail => {
+                "Password must not contain the email address".to_string()
+            }
+            PasswordViolation::Breached => {
+                "Password appears in a list of breached passwords".to_string()
+            }
+            PasswordViolation::RecentlyUsed { history_size } => {
+                format!(
+                    "Password must differ from the last {} passwords",
+                    history_size
+                )
+            }
+        }
+    }
+}
+
+/// A violation with its message, as returned to the client
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordViolationDetail {
+    #[serde(flatten)]
+    pub violation: PasswordViolation,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub min_entropy_bits: f64,
+    pub reject_email: bool,
+    /// Lowercase breached passwords
+    pub breached: HashSet<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::new(&PasswordPolicyConfig::default(), "")
+    }
+}
+
+impl PasswordPolicy {
+    /**
+     * Builds the policy from the configuration, loading the breached password list file when
+     * one is configured
+     *
+     * @param password_policy_config: &PasswordPolicyConfig
+     * @return Result<PasswordPolicy, String>
+     */
+    pub fn from_config(password_policy_config: &PasswordPolicyConfig) -> Result<Self, String> {
+        let breached_list = match &password_policy_config.password_breached_list {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read breached password list {}: {}", path, e))?,
+            None => String::new(),
+        };
+        Ok(Self::new(password_policy_config, &breached_list))
+    }
+
+    /**
+     * Builds the policy with a breached password list, one password per line. Blank lines and
+     * lines starting with `#` are skipped.
+     *
+     * @param password_policy_config: &PasswordPolicyConfig
+     * @param breached_list: &str
+     * @return PasswordPolicy
+     */
+    pub fn new(password_policy_config: &PasswordPolicyConfig, breached_list: &str) -> Self {
+        let breached = COMMON_PASSWORDS
+            .lines()
+            .chain(breached_list.lines())
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect();
+        Self {
+            min_length: password_policy_config.password_min_length,
+            max_length: password_policy_config.password_max_length,
+            require_lowercase: password_policy_config.password_require_lowercase,
+            require_uppercase: password_policy_config.password_require_uppercase,
+            require_digit: password_policy_config.password_require_digit,
+            require_symbol: password_policy_config.password_require_symbol,
+            min_entropy_bits: password_policy_config.password_min_entropy_bits,
+            reject_email: password_policy_config.password_reject_email,
+            breached,
+        }
+    }
+
+    /**
+     * Lists every rule a password breaks, none when it is acceptable
+     *
+     * @param password: &str
+     * @param email: Option<&str> of the account, which the password must not contain
+     * @return Vec<PasswordViolation>
+     */
+    pub fn check(&self, password: &str, email: Option<&str>) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length {
+            violations.push(PasswordViolation::TooShort {
+                min_length: self.min_length,
+            });
+        }
+        if length > self.max_length {
+            violations.push(PasswordViolation::TooLong {
+                max_length: self.max_length,
+            });
+        }
+        let classes = [
+            (
+                self.require_lowercase,
+                password.chars().any(char::is_lowercase),
+                PasswordViolation::MissingLowercase,
+            ),
+            (
+                self.require_uppercase,
+                password.chars().any(char::is_uppercase),
+                PasswordViolation::MissingUppercase,
+            ),
+            (
+                self.require_digit,
+                password.chars().any(|c| c.is_ascii_digit()),
+                PasswordViolation::MissingDigit,
+            ),
+            (
+                self.require_symbol,
+                password.chars().any(|c| !c.is_alphanumeric()),
+                PasswordViolation::MissingSymbol,
+            ),
+        ];
+        for (required, present, violation) in classes {
+            if required && !present {
+                violations.push(violation);
+            }
+        }
+        if Self::entropy_bits(password) < self.min_entropy_bits {
+            violations.push(PasswordViolation::TooPredictable {
+                min_entropy_bits: self.min_entropy_bits,
+            });
+        }
+        if self.reject_email && email.is_some_and(|email| Self::contains_email(password, email)) {
+            violations.push(PasswordViolation::ContainsEmail);
+        }
+        if self.breached.contains(&password.to_lowercase()) {
+            violations.push(PasswordViolation::Breached);
+        }
+        violations
+    }
+
+    /**
+     * Rough estimate of the entropy of a password: each character is worth log2 of the size of
+     * the character classes used, except characters repeating or continuing a sequence from the
+     * previous one, which are worth a single bit
+     *
+     * @param password: &str
+     * @return f64
+     */
+    pub fn entropy_bits(password: &str) -> f64 {
+        let chars = password.chars().collect::<Vec<_>>();
+        let pool = [
+            (chars.iter().any(|c| c.is_ascii_lowercase()), 26),
+            (chars.iter().any(|c| c.is_ascii_uppercase()), 26),
+            (chars.iter().any(|c| c.is_ascii_digit()), 10),
+            (
+                chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' '),
+                33,
+            ),
+            (chars.iter().any(|c| !c.is_ascii()), 100),
+        ]
+        .iter()
+        .filter(|(used, _)| *used)
+        .map(|(_, size)| size)
+        .sum::<u32>();
+        if pool == 0 {
+            return 0.0;
+        }
+        let bits_per_char = (pool as f64).log2();
+        chars
+            .iter()
+            .enumerate()
+            .map(
+                |(i, c)| match i.checked_sub(1).map(|previous| chars[previous]) {
+                    Some(previous) if (*c as i64 - previous as i64).abs() <= 1 => 1.0,
+                    _ => bits_per_char,
+                },
+            )
+            .sum()
+    }
+
+    /// Whether the password contains the email address or its local part
+    fn contains_email(password: &str, email: &str) -> bool {
+        let password = password.to_lowercase();
+        let email = email.trim().to_lowercase();
+        let local_part = email.split('@').next().unwrap_or_default();
+        (!email.is_empty() && password.contains(&email))
+            || (local_part.chars().count() >= 3 && password.contains(local_part))
+    }
+}
+
+pub struct PasswordPolicyService;
+
+impl PasswordPolicyService {
+    /**
+     * Sets the policy checked by `validate` and `require_valid`. Until it is called, the default
+     * policy is used.
+     *
+     * @param password_policy_config: &PasswordPolicyConfig
+     * @return Result<(), String>
+     */
+    pub fn configure(password_policy_config: &PasswordPolicyConfig) -> Result<(), String> {
+        let policy = PasswordPolicy::from_config(password_policy_config)?;
+        log::info!(
+            "Password policy loaded with {} breached passwords",
+            policy.breached.len()
+        );
+        POLICY
+            .set(policy)
+            .map_err(|_| "Password policy is already configured".to_string())
+    }
+
+    pub fn policy() -> &'static PasswordPolicy {
+        POLICY.get_or_init(PasswordPolicy::default)
+    }
+
+    /**
+     * Checks a password against the configured policy
+     *
+     * @param password: &str
+     * @param email: Option<&str>
+     * @return Result<(), Vec<PasswordViolation>>
+     */
+    pub fn validate(password: &str, email: Option<&str>) -> Result<(), Vec<PasswordViolation>> {
+        let violations = Self::policy().check(password, email);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /**
+     * Rejects a password breaking the policy with `422 Unprocessable Entity` and the list of
+     * violations
+     *
+     * @param password: &str
+     * @param email: Option<&str>
+     * @return Result<(), AppError>
+     */
+    pub fn require_valid(password: &str, email: Option<&str>) -> Result<(), AppError> {
+        Self::validate(password, email).map_err(Self::reject)
+    }
+
+    /**
+     * Builds the error listing the violations of a password
+     *
+     * @param violations: Vec<PasswordViolation>
+     * @return AppError
+     */
+    pub fn reject(violations: Vec<PasswordViolation>) -> AppError {
+        log::error!("Password breaks the policy: {:?}", violations);
+        AppError::PasswordPolicy(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validate_wrong() {
+        let wrong_pwd_1 = "password";
+        let wrong_pwd_2 = "secrets";
+        let wrong_pd_3 = "12345678";
+        assert!(PasswordPolicyService::validate(wrong_pwd_1, None).is_err());
+        assert!(PasswordPolicyService::validate(wrong_pwd_2, None).is_err());
+        assert!(PasswordPolicyService::validate(wrong_pd_3, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_correct() {
+        let correct_pwd = "password123";
+        assert!(PasswordPolicyService::validate(correct_pwd, None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_length() {
+        let policy = PasswordPolicy {
+            max_length: 12,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(
+            policy.check("Xk7#", None),
+            vec![
+                PasswordViolation::TooShort { min_length: 8 },
+                PasswordViolation::TooPredictable {
+                    min_entropy_bits: 30.0
+                },
+            ]
+        );
+        assert_eq!(
+            policy.check("Xk7#pQ2!mZ9$w", None),
+            vec![PasswordViolation::TooLong { max_length: 12 }]
+        );
+        assert!(policy.check("Xk7#pQ2!mZ9$", None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_classes() {
+        let policy = PasswordPolicy {
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(
+            policy.check("correct horse battery", None),
+            vec![
+                PasswordViolation::MissingUppercase,
+                PasswordViolation::MissingDigit,
+            ]
+        );
+        assert_eq!(
+            policy.check("CorrectHorse7Battery", None),
+            vec![PasswordViolation::MissingSymbol]
+        );
+        assert!(policy.check("Correct-Horse7Battery", None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_email_and_breached() {
+        let policy = PasswordPolicy::new(&PasswordPolicyConfig::default(), "# list\nTr0ub4dor&3\n");
+        assert_eq!(
+            policy.check("jane.doe-rocks", Some("Jane.Doe@example.com")),
+            vec![PasswordViolation::ContainsEmail]
+        );
+        assert!(policy
+            .check("jane.doe-rocks", Some("someone@example.com"))
+            .is_empty());
+        assert!(policy.check("jane.doe-rocks", None).is_empty());
+        assert_eq!(
+            policy.check("tr0ub4dor&3", None),
+            vec![PasswordViolation::Breached]
+        );
+        assert_eq!(
+            policy.check("Password1", None),
+            vec![PasswordViolation::Breached]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_entropy_bits() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert_eq!(PasswordPolicy::entropy_bits(""), 0.0);
+        assert!(close(
+            PasswordPolicy::entropy_bits("aaaa"),
+            26f64.log2() + 3.0
+        ));
+        assert!(close(
+            PasswordPolicy::entropy_bits("abcd"),
+            26f64.log2() + 3.0
+        ));
+        assert!(close(
+            PasswordPolicy::entropy_bits("a1b2"),
+            4.0 * 36f64.log2()
+        ));
+        assert!(PasswordPolicy::entropy_bits("12345678") < 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_violation_json() {
+        let detail = PasswordViolationDetail {
+            message: PasswordViolation::TooShort { min_length: 8 }.message(),
+            violation: PasswordViolation::TooShort { min_length: 8 },
+        };
+        assert_eq!(
+            serde_json::to_value(detail).unwrap(),
+            serde_json::json!({
+                "code": "too_short",
+                "min_length": 8,
+                "message": "Password must be at least 8 characters long",
+            })
+        );
+    }
+}
@@ -1,39 +1,173 @@
+use std::sync::OnceLock;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::Duration;
 use diesel::result::Error;
 use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
 use diesel::QueryDsl;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
 
+use crate::configs::common::AuthConfig;
 use crate::helper::enums::Identifier;
+use crate::helper::exceptions::AppError;
+use crate::models::password_history_model::PasswordHistoryModel;
+use crate::models::password_reset_token_model::PasswordResetTokenModel;
 use crate::models::user_model::UserModel;
-use crate::schema::users;
-use crate::schemas::user_schemas::PasswordUpdate;
+use crate::schema::{password_history, password_reset_tokens, users};
+use crate::schemas::user_schemas::{PasswordForgotRequest, PasswordResetRequest, PasswordUpdate};
+use crate::services::lockout_service::LockoutService;
+use crate::services::mail_template_service::{MailTemplate, MailTemplateService};
+use crate::services::outbox_service::OutboxService;
+use crate::services::password_policy_service::PasswordViolation;
+use crate::services::refresh_token_service::RefreshTokenService;
+use crate::services::revocation_service::{RevocationCache, RevocationService};
+
+/// Argon2id parameters of new hashes, set once at startup by `PasswordService::configure`
+static HASH_PARAMS: OnceLock<Params> = OnceLock::new();
+
+/// Result of a password change: rejected when the new password was used recently
+#[derive(Debug, PartialEq)]
+pub enum PasswordChange {
+    Changed,
+    Rejected(Vec<PasswordViolation>),
+}
 
 pub struct PasswordService;
 
 impl PasswordService {
+    /**
+     * Sets the Argon2id parameters of new hashes from the configuration. Until it is called,
+     * the defaults of the `argon2` crate are used.
+     *
+     * @param auth_config: &AuthConfig
+     * @return Result<(), String>
+     */
+    pub fn configure(auth_config: &AuthConfig) -> Result<(), String> {
+        let params = Params::new(
+            auth_config.password_hash_memory_kib,
+            auth_config.password_hash_time_cost,
+            auth_config.password_hash_parallelism,
+            None,
+        )
+        .map_err(|e| format!("Invalid password hash parameters: {}", e))?;
+        HASH_PARAMS
+            .set(params)
+            .map_err(|_| "Password hashing is already configured".to_string())
+    }
+
+    /**
+     * Hashes a password with Argon2id into a PHC string
+     *
+     * @param password: &str
+     * @return String
+     */
     pub fn hash(password: &str) -> String {
-        bcrypt::hash(password, 12).unwrap()
+        let salt = SaltString::generate(&mut OsRng);
+        Self::hasher()
+            .hash_password(password.as_bytes(), &salt)
+            .expect("Failed to hash password")
+            .to_string()
     }
+
+    /**
+     * Checks a password against a hash. Argon2 PHC strings and the bcrypt hashes stored before
+     * Argon2id are both accepted; a malformed hash never matches.
+     *
+     * @param password: &str
+     * @param hash: &str
+     * @return bool
+     */
     pub fn verify(password: &str, hash: &str) -> bool {
-        bcrypt::verify(password, hash).unwrap()
+        if Self::is_bcrypt(hash) {
+            return bcrypt::verify(password, hash).unwrap_or_else(|e| {
+                log::error!("Failed to verify bcrypt hash: {}", e);
+                false
+            });
+        }
+        match PasswordHash::new(hash) {
+            Ok(parsed) => Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok(),
+            Err(e) => {
+                log::error!("Failed to parse password hash: {}", e);
+                false
+            }
+        }
     }
-    pub fn validate(password: &str) -> bool {
-        (password.len() >= 8) && (password != "password") && (password != "12345678")
+
+    /**
+     * Checks whether a hash was made with another algorithm or other parameters than the
+     * current ones, and should be replaced on the next successful login
+     *
+     * @param hash: &str
+     * @return bool
+     */
+    pub fn needs_rehash(hash: &str) -> bool {
+        if Self::is_bcrypt(hash) {
+            return true;
+        }
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        let current = Self::params();
+        parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+            || Params::try_from(&parsed).map_or(true, |params| {
+                params.m_cost() != current.m_cost()
+                    || params.t_cost() != current.t_cost()
+                    || params.p_cost() != current.p_cost()
+            })
     }
 
+    /**
+     * Replaces the stored hash of a user who just proved their password, when it was made with
+     * an outdated algorithm or outdated parameters
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: UserModel
+     * @param password: &str
+     * @return Result<UserModel, Error>
+     */
+    pub async fn rehash_if_needed(
+        conn: &mut AsyncPgConnection,
+        user: UserModel,
+        password: &str,
+    ) -> Result<UserModel, Error> {
+        if !Self::needs_rehash(&user.password) {
+            return Ok(user);
+        }
+        let user = diesel::update(&user)
+            .set(users::password.eq(Self::hash(password)))
+            .get_result::<UserModel>(conn)
+            .await?;
+        log::info!("Rehashed password of user {:?}", user.id);
+        Ok(user)
+    }
+
+    /**
+     * Changes the password of a user. A wrong old password counts towards the lockout of the
+     * account and of the client address, like a failed login. The new password is expected to
+     * have been checked with `PasswordPolicyService::require_valid`, and is rejected when it is
+     * one of the last `password_history_size` passwords of the user.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param id: &Identifier
+     * @param new_data: PasswordUpdate
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @return Result<PasswordChange, AppError>
+     */
     pub async fn update_password(
         conn: &mut AsyncPgConnection,
         id: &Identifier,
         new_data: PasswordUpdate,
-    ) -> Result<(), Error> {
-        if !Self::validate(&new_data.new_password) {
-            log::error!("Password length must be at least 8 characters");
-            // @TODO: Replace with custom error
-            return Err(Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::CheckViolation,
-                Box::new("Password length must be at least 8 characters".to_string()),
-            ));
-        }
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+    ) -> Result<PasswordChange, AppError> {
         let old_data = match id {
             Identifier::Id(id) => users::table.find(id).get_result::<UserModel>(conn).await?,
             Identifier::Email(email) => {
@@ -45,55 +179,299 @@ impl PasswordService {
         };
         if !PasswordService::verify(&new_data.old_password, &old_data.password) {
             log::error!("Wrong credentials for user {}", old_data.email);
-            // @TODO: Replace with custom error
-            return Err(Error::DatabaseError(
-                diesel::result::DatabaseErrorKind::CheckViolation,
-                Box::new("Wrong credentials".to_string()),
-            ));
+            LockoutService::record_failure(conn, &old_data.email, client_ip, auth_config).await?;
+            return Err(AppError::Unauthorized("Wrong credentials".to_string()));
         }
+        LockoutService::record_success(conn, &old_data.email).await?;
 
-        let hashed_password = PasswordService::hash(&new_data.new_password);
-        let user = diesel::update(&old_data)
-            .set((
-                users::password.eq(hashed_password),
-                users::updated_at.eq(chrono::Utc::now().naive_utc()),
-            ))
-            .get_result::<UserModel>(conn)
-            .await;
+        if let Some(violation) =
+            Self::check_reuse(conn, &old_data, &new_data.new_password, auth_config).await?
+        {
+            return Ok(PasswordChange::Rejected(vec![violation]));
+        }
+        let user =
+            Self::change_password(conn, &old_data, &new_data.new_password, auth_config).await;
 
         match user {
             Ok(user) => {
                 log::info!("User {:?} password updated successfully", user.id);
-                Ok(())
+                Ok(PasswordChange::Changed)
             }
             Err(e) => {
                 log::error!("Failed to update user: {}", e);
-                Err(e)
+                Err(AppError::from(e))
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /**
+     * Checks whether the password of a user is older than `password_max_age_days`. Users with an
+     * expired password only get restricted tokens until they change it.
+     *
+     * @param user: &UserModel
+     * @param auth_config: &AuthConfig
+     * @return bool
+     */
+    pub fn is_expired(user: &UserModel, auth_config: &AuthConfig) -> bool {
+        auth_config.password_max_age_days > 0
+            && user.password_changed_at + Duration::days(auth_config.password_max_age_days)
+                <= chrono::Utc::now().naive_utc()
+    }
 
-    #[tokio::test]
-    async fn test_validate_wrong() {
-        let wrong_pwd_1 = "password";
-        let wrong_pwd_2 = "secrets";
-        let wrong_pd_3 = "12345678";
-        assert!(!PasswordService::validate(wrong_pwd_1));
-        assert!(!PasswordService::validate(wrong_pwd_2));
-        assert!(!PasswordService::validate(wrong_pd_3));
+    /**
+     * Starts a password reset by queueing an email with a single-use reset token to the user. Unknown and
+     * inactive accounts are ignored silently, so the outcome never discloses whether an account
+     * exists. Requesting a new token invalidates the previous ones.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param forgot_request: PasswordForgotRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<(), Error>
+     */
+    pub async fn forgot_password(
+        conn: &mut AsyncPgConnection,
+        forgot_request: PasswordForgotRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<(), Error> {
+        let user = users::table
+            .filter(users::email.eq(&forgot_request.email))
+            .get_result::<UserModel>(conn)
+            .await
+            .optional()?;
+        let user = match user {
+            Some(user) if user.is_active => user,
+            _ => {
+                log::info!("Password reset requested for an unknown or inactive account");
+                return Ok(());
+            }
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        diesel::update(
+            password_reset_tokens::table
+                .filter(password_reset_tokens::user_id.eq(user.id))
+                .filter(password_reset_tokens::used_at.is_null()),
+        )
+        .set(password_reset_tokens::used_at.eq(now))
+        .execute(conn)
+        .await?;
+        let token = RefreshTokenService::generate();
+        diesel::insert_into(password_reset_tokens::table)
+            .values(&PasswordResetTokenModel::new(
+                RefreshTokenService::hash(&token),
+                user.id,
+                now + Duration::minutes(auth_config.password_reset_expire_minutes),
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create password reset token: {}", e);
+                e
+            })?;
+
+        OutboxService::enqueue(
+            conn,
+            MailTemplateService::render(
+                MailTemplate::PasswordReset,
+                &user.email,
+                &[
+                    (
+                        "reset_url",
+                        format!("{}?token={}", auth_config.password_reset_url, token),
+                    ),
+                    (
+                        "expire_minutes",
+                        auth_config.password_reset_expire_minutes.to_string(),
+                    ),
+                ],
+            ),
+        )
+        .await?;
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn test_validate_correct() {
-        let correct_pwd = "password123";
-        assert!(PasswordService::validate(correct_pwd));
+    /**
+     * Finds the user of a valid reset token without using it, so that the new password can be
+     * checked against the policy first
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token: &str
+     * @return Result<UserModel, AppError>
+     */
+    pub async fn reset_token_user(
+        conn: &mut AsyncPgConnection,
+        token: &str,
+    ) -> Result<UserModel, AppError> {
+        password_reset_tokens::table
+            .inner_join(users::table)
+            .filter(password_reset_tokens::token_hash.eq(RefreshTokenService::hash(token)))
+            .filter(password_reset_tokens::used_at.is_null())
+            .filter(password_reset_tokens::expires_at.gt(chrono::Utc::now().naive_utc()))
+            .select(users::all_columns)
+            .get_result::<UserModel>(conn)
+            .await
+            .optional()?
+            .ok_or_else(Self::invalid_reset_token)
+    }
+
+    /**
+     * Rejects a reset token that does not exist, has expired or was already used
+     *
+     * @return AppError
+     */
+    fn invalid_reset_token() -> AppError {
+        log::error!("Invalid password reset token");
+        AppError::Validation("Invalid or expired token".to_string())
+    }
+
+    /**
+     * Sets a new password with a reset token and revokes every session of the user. The new
+     * password is expected to have been checked with `PasswordPolicyService::require_valid`, and
+     * is rejected without using the token when it is one of the last passwords of the user.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param revocation_cache: &RevocationCache
+     * @param reset_request: PasswordResetRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<PasswordChange, AppError>
+     */
+    pub async fn reset_password(
+        conn: &mut AsyncPgConnection,
+        revocation_cache: &RevocationCache,
+        reset_request: PasswordResetRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<PasswordChange, AppError> {
+        let user = Self::reset_token_user(conn, &reset_request.token).await?;
+        if let Some(violation) =
+            Self::check_reuse(conn, &user, &reset_request.new_password, auth_config).await?
+        {
+            return Ok(PasswordChange::Rejected(vec![violation]));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let reset_token = diesel::update(
+            password_reset_tokens::table
+                .filter(
+                    password_reset_tokens::token_hash
+                        .eq(RefreshTokenService::hash(&reset_request.token)),
+                )
+                .filter(password_reset_tokens::used_at.is_null())
+                .filter(password_reset_tokens::expires_at.gt(now)),
+        )
+        .set(password_reset_tokens::used_at.eq(now))
+        .get_result::<PasswordResetTokenModel>(conn)
+        .await
+        .optional()?
+        .ok_or_else(Self::invalid_reset_token)?;
+
+        Self::change_password(conn, &user, &reset_request.new_password, auth_config).await?;
+        RevocationService::revoke_user(conn, revocation_cache, reset_token.user_id).await?;
+        log::info!("User {:?} password reset successfully", reset_token.user_id);
+        Ok(PasswordChange::Changed)
+    }
+
+    /**
+     * Rejects a password matching the current one or one of the previous passwords kept in the
+     * history, `password_history_size` passwords in all
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param password: &str
+     * @param auth_config: &AuthConfig
+     * @return Result<Option<PasswordViolation>, Error>
+     */
+    async fn check_reuse(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        password: &str,
+        auth_config: &AuthConfig,
+    ) -> Result<Option<PasswordViolation>, Error> {
+        if auth_config.password_history_size <= 0 {
+            return Ok(None);
+        }
+        let previous = password_history::table
+            .filter(password_history::user_id.eq(user.id))
+            .order(password_history::created_at.desc())
+            .limit(auth_config.password_history_size - 1)
+            .select(password_history::password_hash)
+            .get_results::<String>(conn)
+            .await?;
+        let reused = std::iter::once(&user.password)
+            .chain(previous.iter())
+            .any(|hash| Self::verify(password, hash));
+        if reused {
+            log::error!("User {} tried to reuse a recent password", user.id);
+            return Ok(Some(PasswordViolation::RecentlyUsed {
+                history_size: auth_config.password_history_size,
+            }));
+        }
+        Ok(None)
     }
 
+    /**
+     * Replaces the password of a user, keeping the previous hash in the history. Entries beyond
+     * `password_history_size` are dropped.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param password: &str
+     * @param auth_config: &AuthConfig
+     * @return Result<UserModel, Error>
+     */
+    async fn change_password(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        password: &str,
+        auth_config: &AuthConfig,
+    ) -> Result<UserModel, Error> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(password_history::table)
+            .values(&PasswordHistoryModel::new(user.id, user.password.clone()))
+            .execute(conn)
+            .await?;
+        let kept = password_history::table
+            .filter(password_history::user_id.eq(user.id))
+            .order(password_history::created_at.desc())
+            .limit((auth_config.password_history_size - 1).max(0))
+            .select(password_history::id)
+            .get_results::<Uuid>(conn)
+            .await?;
+        diesel::delete(
+            password_history::table
+                .filter(password_history::user_id.eq(user.id))
+                .filter(password_history::id.ne_all(kept)),
+        )
+        .execute(conn)
+        .await?;
+
+        diesel::update(user)
+            .set((
+                users::password.eq(Self::hash(password)),
+                users::password_changed_at.eq(now),
+                users::updated_at.eq(now),
+            ))
+            .get_result::<UserModel>(conn)
+            .await
+    }
+
+    fn hasher() -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, Self::params())
+    }
+
+    fn params() -> Params {
+        HASH_PARAMS.get().cloned().unwrap_or_default()
+    }
+
+    /// bcrypt hashes are not PHC strings, they start with `$2a$`, `$2b$`, `$2x$` or `$2y$`
+    fn is_bcrypt(hash: &str) -> bool {
+        hash.starts_with("$2")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
     #[tokio::test]
     async fn test_hash() {
         let password = "password123";
@@ -107,4 +485,48 @@ mod tests {
         let hashed_password = PasswordService::hash(password);
         assert!(PasswordService::verify(password, &hashed_password));
     }
+
+    #[tokio::test]
+    async fn test_hash_argon2id() {
+        let hashed_password = PasswordService::hash("password123");
+        assert!(hashed_password.starts_with("$argon2id$v=19$"));
+        assert!(!PasswordService::needs_rehash(&hashed_password));
+    }
+
+    #[tokio::test]
+    async fn test_verify_bcrypt() {
+        let hashed_password = bcrypt::hash("password123", 4).unwrap();
+        assert!(PasswordService::verify("password123", &hashed_password));
+        assert!(!PasswordService::verify("password124", &hashed_password));
+        assert!(PasswordService::needs_rehash(&hashed_password));
+    }
+
+    #[tokio::test]
+    async fn test_verify_malformed() {
+        assert!(!PasswordService::verify("password123", "not a hash"));
+        assert!(!PasswordService::verify("password123", "$2b$12$short"));
+        assert!(PasswordService::needs_rehash("not a hash"));
+    }
+
+    #[tokio::test]
+    async fn test_needs_rehash_params() {
+        let salt = SaltString::generate(&mut OsRng);
+        let weaker = Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            Params::new(8192, 1, 1, None).unwrap(),
+        )
+        .hash_password(b"password123", &salt)
+        .unwrap()
+        .to_string();
+        assert!(PasswordService::verify("password123", &weaker));
+        assert!(PasswordService::needs_rehash(&weaker));
+
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default())
+            .hash_password(b"password123", &salt)
+            .unwrap()
+            .to_string();
+        assert!(PasswordService::verify("password123", &argon2i));
+        assert!(PasswordService::needs_rehash(&argon2i));
+    }
 }
@@ -0,0 +1,22 @@
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::requirement_extractor::{require_extractor, requirements, Requirement};
+
+/**
+ * A permission a route can require
+ */
+pub trait Permission: Requirement {}
+
+requirements! {
+    Permission, "permission", AuthExtractorService::has_permission;
+    RolesManage => "roles:manage",
+    KeysManage => "keys:manage",
+    OAuthClientsManage => "oauth_clients:manage",
+    SessionsRevoke => "sessions:revoke",
+    LockoutsManage => "lockouts:manage",
+}
+
+require_extractor! {
+    /// Authenticates the request like `AuthExtractorService` and requires one of the roles of the
+    /// principal to grant the permission `P`
+    RequirePermission, Permission
+}
@@ -0,0 +1,457 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use diesel::sql_types::{Bool, Double, Text};
+use diesel::{ExpressionMethods, QueryDsl, QueryableByName};
+use diesel_async::RunQueryDsl;
+use futures_util::future::BoxFuture;
+
+use crate::configs::common::RateLimitConfig;
+use crate::helper::type_alias::DbPool;
+use crate::helper::utils::matches_path;
+use crate::schema::rate_limit_buckets;
+
+/// Takes a token from a bucket in a single statement, so that concurrent requests of every
+/// worker and replica see each other. The bucket is refilled by the time elapsed since its last
+/// update before the token is taken.
+const TAKE_TOKEN_SQL: &str = r#"
+INSERT INTO "rate_limit_buckets" AS "bucket" ("key", "tokens", "allowed", "updated_at")
+VALUES ($1, $2 - 1, TRUE, NOW() AT TIME ZONE 'UTC')
+ON CONFLICT ("key") DO UPDATE
+    SET ("tokens", "allowed", "updated_at") = (
+        SELECT CASE WHEN "refilled" >= 1 THEN "refilled" - 1 ELSE "refilled" END,
+               "refilled" >= 1,
+               NOW() AT TIME ZONE 'UTC'
+        FROM (SELECT LEAST(
+                  $2,
+                  "bucket"."tokens" + $3 * EXTRACT(
+                      EPOCH FROM (NOW() AT TIME ZONE 'UTC') - "bucket"."updated_at"
+                  )::DOUBLE PRECISION
+              ) AS "refilled") AS "refill"
+    )
+RETURNING "tokens", "allowed"
+"#;
+
+/// Buckets unused for longer than this are full again and can be dropped
+const BUCKET_IDLE_SECONDS: i64 = 24 * 60 * 60;
+
+/// What a request is counted against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitKey {
+    /// The client address
+    Ip,
+    /// The authenticated user, or the client address for anonymous requests
+    User,
+    /// The OAuth client of the access token, or the user or address when there is none
+    Client,
+}
+
+/**
+ * Token bucket limit of the requests matching a method and a path pattern. Written as
+ * `[METHOD ]<pattern>=<ip|user|client>:<burst>:<per minute>`, for example
+ * `POST /auth/login=ip:10:10`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitPolicy {
+    pub method: Option<String>,
+    pub pattern: String,
+    pub key: RateLimitKey,
+    /// Size of the bucket, the number of requests allowed in a burst
+    pub capacity: u32,
+    /// Tokens added to the bucket every minute
+    pub per_minute: u32,
+}
+
+impl RateLimitPolicy {
+    /**
+     * Parses a comma separated list of policies
+     *
+     * @param policies: &str
+     * @return Result<Vec<RateLimitPolicy>, String>
+     */
+    pub fn parse_all(policies: &str) -> Result<Vec<RateLimitPolicy>, String> {
+        policies
+            .split(',')
+            .map(str::trim)
+            .filter(|policy| !policy.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    /**
+     * Parses a single policy
+     *
+     * @param policy: &str
+     * @return Result<RateLimitPolicy, String>
+     */
+    pub fn parse(policy: &str) -> Result<RateLimitPolicy, String> {
+        let invalid = || format!("Invalid rate limit policy {}", policy);
+        let (route, limit) = policy.split_once('=').ok_or_else(invalid)?;
+        let (method, pattern) = match route.trim().split_once(' ') {
+            Some((method, pattern)) => (Some(method.to_uppercase()), pattern.trim()),
+            None => (None, route.trim()),
+        };
+        if !pattern.starts_with('/') {
+            return Err(invalid());
+        }
+        let parts = limit.trim().split(':').collect::<Vec<_>>();
+        let [key, capacity, per_minute] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let key = match *key {
+            "ip" => RateLimitKey::Ip,
+            "user" => RateLimitKey::User,
+            "client" => RateLimitKey::Client,
+            _ => return Err(invalid()),
+        };
+        let capacity = capacity.parse::<u32>().map_err(|_| invalid())?;
+        let per_minute = per_minute.parse::<u32>().map_err(|_| invalid())?;
+        if capacity == 0 || per_minute == 0 {
+            return Err(invalid());
+        }
+        Ok(RateLimitPolicy {
+            method,
+            pattern: pattern.to_string(),
+            key,
+            capacity,
+            per_minute,
+        })
+    }
+
+    pub fn matches(&self, method: &str, path: &str) -> bool {
+        self.method
+            .as_deref()
+            .is_none_or(|policy_method| policy_method == method)
+            && matches_path(path, &self.pattern)
+    }
+
+    pub fn refill_per_second(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+
+    /**
+     * Seconds until the bucket holds `tokens`
+     *
+     * @param bucket: &Bucket
+     * @param tokens: f64
+     * @return u64
+     */
+    pub fn seconds_until(&self, bucket: &Bucket, tokens: f64) -> u64 {
+        ((tokens - bucket.tokens).max(0.0) / self.refill_per_second()).ceil() as u64
+    }
+}
+
+/// State of a bucket after a request
+#[derive(Debug, Clone, PartialEq, QueryableByName)]
+pub struct Bucket {
+    /// Tokens left in the bucket
+    #[diesel(sql_type = Double)]
+    pub tokens: f64,
+    /// Whether the request got a token
+    #[diesel(sql_type = Bool)]
+    pub allowed: bool,
+}
+
+impl Bucket {
+    /**
+     * Refills a bucket for the elapsed time and takes a token when there is one
+     *
+     * @param tokens: f64 the tokens at the last update
+     * @param elapsed: Duration since the last update
+     * @param capacity: f64
+     * @param refill_per_second: f64
+     * @return Bucket
+     */
+    pub fn take(tokens: f64, elapsed: Duration, capacity: f64, refill_per_second: f64) -> Bucket {
+        let refilled = (tokens + elapsed.as_secs_f64() * refill_per_second).min(capacity);
+        if refilled >= 1.0 {
+            Bucket {
+                tokens: refilled - 1.0,
+                allowed: true,
+            }
+        } else {
+            Bucket {
+                tokens: refilled,
+                allowed: false,
+            }
+        }
+    }
+}
+
+/**
+ * Shared storage of the buckets. It must be shared by every worker and replica for the limits
+ * to hold across them.
+ */
+pub trait RateLimitStore: Send + Sync {
+    /**
+     * Takes a token from a bucket, creating it full when it does not exist
+     *
+     * @param key: &str
+     * @param capacity: f64
+     * @param refill_per_second: f64
+     * @return BoxFuture<Result<Bucket, String>>
+     */
+    fn take<'a>(
+        &'a self,
+        key: &'a str,
+        capacity: f64,
+        refill_per_second: f64,
+    ) -> BoxFuture<'a, Result<Bucket, String>>;
+}
+
+/// Keeps the buckets in Postgres, shared by every replica
+pub struct PostgresRateLimitStore {
+    pool: DbPool,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl RateLimitStore for PostgresRateLimitStore {
+    fn take<'a>(
+        &'a self,
+        key: &'a str,
+        capacity: f64,
+        refill_per_second: f64,
+    ) -> BoxFuture<'a, Result<Bucket, String>> {
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| e.to_string())?;
+            diesel::sql_query(TAKE_TOKEN_SQL)
+                .bind::<Text, _>(key)
+                .bind::<Double, _>(capacity)
+                .bind::<Double, _>(refill_per_second)
+                .get_result::<Bucket>(&mut conn)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Keeps the buckets in memory. Limits only hold within a single process, so it is meant for
+/// development and tests
+#[derive(Default)]
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn take<'a>(
+        &'a self,
+        key: &'a str,
+        capacity: f64,
+        refill_per_second: f64,
+    ) -> BoxFuture<'a, Result<Bucket, String>> {
+        Box::pin(async move {
+            let now = Instant::now();
+            let mut buckets = self.buckets.lock().map_err(|e| e.to_string())?;
+            let (tokens, updated_at) = buckets.get(key).copied().unwrap_or((capacity, now));
+            let bucket = Bucket::take(
+                tokens,
+                now.duration_since(updated_at),
+                capacity,
+                refill_per_second,
+            );
+            buckets.insert(key.to_string(), (bucket.tokens, now));
+            Ok(bucket)
+        })
+    }
+}
+
+/// Policies and store used by `RateLimitMiddleware`
+pub struct RateLimiter {
+    pub policies: Vec<RateLimitPolicy>,
+    pub store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    /**
+     * Finds the first policy matching a request
+     *
+     * @param method: &str
+     * @param path: &str
+     * @return Option<&RateLimitPolicy>
+     */
+    pub fn policy_for(&self, method: &str, path: &str) -> Option<&RateLimitPolicy> {
+        self.policies
+            .iter()
+            .find(|policy| policy.matches(method, path))
+    }
+}
+
+pub struct RateLimitService;
+
+impl RateLimitService {
+    /**
+     * Builds the rate limiter from the configuration. It has no policy when rate limiting is
+     * disabled.
+     *
+     * @param rate_limit_config: &RateLimitConfig
+     * @param pool: DbPool
+     * @return Result<RateLimiter, String>
+     */
+    pub fn configured(
+        rate_limit_config: &RateLimitConfig,
+        pool: DbPool,
+    ) -> Result<RateLimiter, String> {
+        let store: Arc<dyn RateLimitStore> = match rate_limit_config.rate_limit_backend.as_str() {
+            "postgres" => Arc::new(PostgresRateLimitStore::new(pool)),
+            "memory" => Arc::new(MemoryRateLimitStore::default()),
+            backend => return Err(format!("Unsupported rate limit backend {}", backend)),
+        };
+        let policies = if rate_limit_config.rate_limit_enabled {
+            RateLimitPolicy::parse_all(&rate_limit_config.rate_limit_policies)?
+        } else {
+            Vec::new()
+        };
+        Ok(RateLimiter { policies, store })
+    }
+
+    /**
+     * Drops the Postgres buckets that have not been used for a day, forever
+     *
+     * @param pool: DbPool
+     * @param interval: Duration
+     */
+    pub async fn purge_periodically(pool: DbPool, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            let idle_since =
+                chrono::Utc::now().naive_utc() - chrono::Duration::seconds(BUCKET_IDLE_SECONDS);
+            let purged = match pool.get().await {
+                Ok(mut conn) => {
+                    diesel::delete(
+                        rate_limit_buckets::table
+                            .filter(rate_limit_buckets::updated_at.lt(idle_since)),
+                    )
+                    .execute(&mut conn)
+                    .await
+                }
+                Err(e) => {
+                    log::error!("Failed to get pool: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = purged {
+                log::error!("Failed to purge rate limit buckets: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse() {
+        let policies =
+            RateLimitPolicy::parse_all("post /auth/login=ip:10:5, /*=user:120:600,").unwrap();
+        assert_eq!(
+            policies,
+            vec![
+                RateLimitPolicy {
+                    method: Some("POST".to_string()),
+                    pattern: "/auth/login".to_string(),
+                    key: RateLimitKey::Ip,
+                    capacity: 10,
+                    per_minute: 5,
+                },
+                RateLimitPolicy {
+                    method: None,
+                    pattern: "/*".to_string(),
+                    key: RateLimitKey::User,
+                    capacity: 120,
+                    per_minute: 600,
+                },
+            ]
+        );
+        assert!(RateLimitPolicy::parse("/auth/login=ip:10").is_err());
+        assert!(RateLimitPolicy::parse("/auth/login=session:10:5").is_err());
+        assert!(RateLimitPolicy::parse("/auth/login=ip:0:5").is_err());
+        assert!(RateLimitPolicy::parse("auth/login=ip:10:5").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_policy_for() {
+        let limiter = RateLimiter {
+            policies: RateLimitPolicy::parse_all("POST /users=ip:5:5,/*=user:100:100").unwrap(),
+            store: Arc::new(MemoryRateLimitStore::default()),
+        };
+        assert_eq!(
+            limiter.policy_for("POST", "/users").unwrap().key,
+            RateLimitKey::Ip
+        );
+        assert_eq!(
+            limiter.policy_for("GET", "/users").unwrap().key,
+            RateLimitKey::User
+        );
+        assert_eq!(
+            limiter.policy_for("GET", "/schools/1").unwrap().key,
+            RateLimitKey::User
+        );
+
+        let limiter = RateLimiter {
+            policies: Vec::new(),
+            ..limiter
+        };
+        assert!(limiter.policy_for("GET", "/users").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bucket() {
+        let bucket = Bucket::take(0.5, Duration::from_secs(1), 10.0, 0.25);
+        assert_eq!(
+            bucket,
+            Bucket {
+                tokens: 0.75,
+                allowed: false
+            }
+        );
+        let bucket = Bucket::take(0.5, Duration::from_secs(2), 10.0, 0.25);
+        assert_eq!(
+            bucket,
+            Bucket {
+                tokens: 0.0,
+                allowed: true
+            }
+        );
+        let bucket = Bucket::take(9.0, Duration::from_secs(3600), 10.0, 1.0);
+        assert_eq!(
+            bucket,
+            Bucket {
+                tokens: 9.0,
+                allowed: true
+            }
+        );
+
+        let policy = RateLimitPolicy::parse("/*=ip:10:30").unwrap();
+        assert_eq!(policy.seconds_until(&bucket, 10.0), 2);
+        assert_eq!(
+            policy.seconds_until(
+                &Bucket {
+                    tokens: 0.75,
+                    allowed: false
+                },
+                1.0
+            ),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        let store = MemoryRateLimitStore::default();
+        for remaining in (0..3).rev() {
+            let bucket = store.take("key", 3.0, 0.001).await.unwrap();
+            assert!(bucket.allowed);
+            assert!((bucket.tokens - remaining as f64).abs() < 0.01);
+        }
+        assert!(!store.take("key", 3.0, 0.001).await.unwrap().allowed);
+        assert!(store.take("other", 3.0, 0.001).await.unwrap().allowed);
+    }
+}
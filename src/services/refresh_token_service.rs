@@ -0,0 +1,257 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::ExpressionMethods;
+use diesel::OptionalExtension;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::exceptions::AppError;
+use crate::models::refresh_token_model::RefreshTokenModel;
+use crate::schema::{authorization_codes, refresh_tokens};
+
+pub struct RefreshTokenService;
+
+impl RefreshTokenService {
+    /**
+     * Generates a new opaque refresh token
+     *
+     * @return String
+     */
+    pub fn generate() -> String {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /**
+     * Hashes a refresh token. Only the hash is ever stored in the database
+     *
+     * @param token: &str
+     * @return String
+     */
+    pub fn hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    /**
+     * Issues a new refresh token for the user. A new family is started unless one is given.
+     * The requested scope is kept so refreshed access tokens get the same scope.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param family_id: Option<Uuid>
+     * @param scope: Option<String>
+     * @param auth_config: &AuthConfig
+     * @return Result<String, Error>
+     */
+    pub async fn issue(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        family_id: Option<Uuid>,
+        scope: Option<String>,
+        auth_config: &AuthConfig,
+    ) -> Result<String, Error> {
+        let token = Self::generate();
+        let expires_at =
+            chrono::Utc::now().naive_utc() + Duration::days(auth_config.refresh_token_expire_days);
+        let new_token = RefreshTokenModel::new(
+            user_id,
+            family_id.unwrap_or_else(Uuid::new_v4),
+            Self::hash(&token),
+            expires_at,
+            scope,
+        );
+
+        diesel::insert_into(refresh_tokens::table)
+            .values(&new_token)
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create refresh token: {}", e);
+                e
+            })?;
+        Ok(token)
+    }
+
+    /**
+     * Finds the OAuth client a refresh token family was issued to. Families started by an
+     * authorization code exchange carry the id of the code, other families belong to no client.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param family_id: Uuid
+     * @return Result<Option<Uuid>, Error>
+     */
+    pub async fn client_of(
+        conn: &mut AsyncPgConnection,
+        family_id: Uuid,
+    ) -> Result<Option<Uuid>, Error> {
+        authorization_codes::table
+            .find(family_id)
+            .select(authorization_codes::client_id)
+            .first::<Uuid>(conn)
+            .await
+            .optional()
+    }
+
+    /**
+     * Finds a refresh token that can still be used
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token: &str
+     * @return Result<RefreshTokenModel, Error>
+     */
+    pub async fn find_active(
+        conn: &mut AsyncPgConnection,
+        token: &str,
+    ) -> Result<RefreshTokenModel, Error> {
+        refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(Self::hash(token)))
+            .filter(refresh_tokens::consumed_at.is_null())
+            .filter(refresh_tokens::revoked_at.is_null())
+            .filter(refresh_tokens::expires_at.gt(chrono::Utc::now().naive_utc()))
+            .get_result::<RefreshTokenModel>(conn)
+            .await
+    }
+
+    /**
+     * Consumes a refresh token so it can never be used again. Presenting a token that was
+     * already consumed revokes its whole family, as it means the token has been replayed.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token: &str
+     * @return Result<RefreshTokenModel, AppError>
+     */
+    pub async fn consume(
+        conn: &mut AsyncPgConnection,
+        token: &str,
+    ) -> Result<RefreshTokenModel, AppError> {
+        let invalid = || AppError::Unauthorized("Invalid refresh token".to_string());
+        let stored = refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(Self::hash(token)))
+            .get_result::<RefreshTokenModel>(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| {
+                log::error!("Refresh token not found");
+                invalid()
+            })?;
+
+        if stored.revoked_at.is_some() {
+            log::error!("Refresh token {} has been revoked", stored.id);
+            return Err(invalid());
+        }
+        if stored.consumed_at.is_some() {
+            log::warn!(
+                "Refresh token {} reused. Revoking family {}",
+                stored.id,
+                stored.family_id
+            );
+            Self::revoke_family(conn, stored.family_id).await?;
+            return Err(invalid());
+        }
+        let now = chrono::Utc::now().naive_utc();
+        if stored.expires_at <= now {
+            log::error!("Refresh token {} has expired", stored.id);
+            return Err(invalid());
+        }
+
+        // Only one concurrent request may win the rotation
+        let consumed = diesel::update(
+            refresh_tokens::table
+                .find(stored.id)
+                .filter(refresh_tokens::consumed_at.is_null()),
+        )
+        .set(refresh_tokens::consumed_at.eq(now))
+        .execute(conn)
+        .await?;
+        if consumed == 0 {
+            log::warn!(
+                "Refresh token {} consumed concurrently. Revoking family {}",
+                stored.id,
+                stored.family_id
+            );
+            Self::revoke_family(conn, stored.family_id).await?;
+            return Err(invalid());
+        }
+        Ok(stored)
+    }
+
+    /**
+     * Revokes the family of a refresh token owned by the given user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param token: &str
+     * @param user_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn revoke(
+        conn: &mut AsyncPgConnection,
+        token: &str,
+        user_id: Uuid,
+    ) -> Result<usize, Error> {
+        let family_id = refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(Self::hash(token)))
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .select(refresh_tokens::family_id)
+            .get_result::<Uuid>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to get refresh token: {}", e);
+                e
+            })?;
+        Self::revoke_family(conn, family_id).await
+    }
+
+    /**
+     * Revokes every refresh token of a family
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param family_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn revoke_family(
+        conn: &mut AsyncPgConnection,
+        family_id: Uuid,
+    ) -> Result<usize, Error> {
+        diesel::update(
+            refresh_tokens::table
+                .filter(refresh_tokens::family_id.eq(family_id))
+                .filter(refresh_tokens::revoked_at.is_null()),
+        )
+        .set(refresh_tokens::revoked_at.eq(chrono::Utc::now().naive_utc()))
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to revoke refresh token family {}: {}", family_id, e);
+            e
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_generate() {
+        let token_1 = RefreshTokenService::generate();
+        let token_2 = RefreshTokenService::generate();
+        assert_eq!(token_1.len(), 43);
+        assert_ne!(token_1, token_2);
+    }
+
+    #[tokio::test]
+    async fn test_hash() {
+        let token = RefreshTokenService::generate();
+        let hashed_token = RefreshTokenService::hash(&token);
+        assert_ne!(token, hashed_token);
+        assert_eq!(hashed_token, RefreshTokenService::hash(&token));
+        assert_eq!(hashed_token.len(), 64);
+    }
+}
@@ -0,0 +1,173 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+
+use actix_web::{FromRequest, HttpRequest};
+
+use crate::helper::exceptions::AppError;
+use crate::services::auth_extractor::AuthExtractorService;
+
+/**
+ * A scope or permission a route can require
+ */
+pub trait Requirement {
+    /// Kind of the requirement, such as `scope`, used in the logs
+    const KIND: &'static str;
+    const NAME: &'static str;
+
+    /**
+     * Checks whether the authenticated principal meets the requirement
+     *
+     * @param auth: &AuthExtractorService
+     * @return bool
+     */
+    fn is_met(auth: &AuthExtractorService) -> bool;
+}
+
+/**
+ * Declares requirements of one kind. Each one is an uninhabited type implementing `Requirement`,
+ * checked with `$check(auth, NAME)`, and the marker trait of its kind when one is given.
+ */
+macro_rules! requirements {
+    ($kind:literal, $check:path; $($requirement:ident => $name:literal),* $(,)?) => {
+        $(
+            pub enum $requirement {}
+
+            impl $crate::services::requirement_extractor::Requirement for $requirement {
+                const KIND: &'static str = $kind;
+                const NAME: &'static str = $name;
+
+                fn is_met(auth: &AuthExtractorService) -> bool {
+                    $check(auth, $name)
+                }
+            }
+        )*
+    };
+    ($marker:ident, $kind:literal, $check:path; $($requirement:ident => $name:literal),* $(,)?) => {
+        requirements!($kind, $check; $($requirement => $name),*);
+        $(
+            impl $marker for $requirement {}
+        )*
+    };
+}
+
+pub(crate) use requirements;
+
+/**
+ * Declares the extractor of one kind of requirement, a newtype over `Require` bounded by the
+ * marker trait of the kind, so that a requirement of another kind does not compile.
+ */
+macro_rules! require_extractor {
+    ($(#[$meta:meta])* $extractor:ident, $marker:ident) => {
+        $(#[$meta])*
+        pub struct $extractor<R: $marker>($crate::services::requirement_extractor::Require<R>);
+
+        impl<R: $marker> std::ops::Deref for $extractor<R> {
+            type Target = AuthExtractorService;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<R: $marker + 'static> actix_web::FromRequest for $extractor<R> {
+            type Error = actix_web::Error;
+            type Future = std::pin::Pin<
+                Box<dyn std::future::Future<Output = Result<Self, Self::Error>>>,
+            >;
+
+            fn from_request(
+                req: &actix_web::HttpRequest,
+                payload: &mut actix_web::dev::Payload,
+            ) -> Self::Future {
+                let require =
+                    $crate::services::requirement_extractor::Require::<R>::from_request(
+                        req, payload,
+                    );
+                Box::pin(async move { Ok($extractor(require.await?)) })
+            }
+        }
+    };
+}
+
+pub(crate) use require_extractor;
+
+/**
+ * Authenticates the request like `AuthExtractorService` and requires the principal to meet the
+ * requirement `R`
+ */
+pub struct Require<R: Requirement> {
+    auth: AuthExtractorService,
+    requirement: PhantomData<R>,
+}
+
+impl<R: Requirement> Deref for Require<R> {
+    type Target = AuthExtractorService;
+
+    fn deref(&self) -> &Self::Target {
+        &self.auth
+    }
+}
+
+impl<R: Requirement + 'static> FromRequest for Require<R> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    /**
+     * Extracts the authenticated principal and checks the requirement
+     *
+     * @param req: &HttpRequest
+     * @param payload: &mut actix_web::dev::Payload
+     * @return Self::Future
+     */
+    fn from_request(req: &HttpRequest, payload: &mut actix_web::dev::Payload) -> Self::Future {
+        let auth = AuthExtractorService::from_request(req, payload);
+        Box::pin(async move {
+            let auth = auth.await?;
+            if !R::is_met(&auth) {
+                log::error!(
+                    "Token of {} is missing the {} {}",
+                    auth.id,
+                    R::NAME,
+                    R::KIND
+                );
+                return Err(AppError::Forbidden("Forbidden".to_string()).into());
+            }
+            Ok(Require {
+                auth,
+                requirement: PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    use crate::services::permission_extractor::{KeysManage, RolesManage};
+    use crate::services::scope_extractor::{UsersRead, UsersWrite};
+
+    #[tokio::test]
+    async fn test_is_met() {
+        let auth = AuthExtractorService {
+            jti: Uuid::new_v4(),
+            exp: 0,
+            id: Uuid::new_v4(),
+            tenant_id: None,
+            email: "user@example.com".to_string(),
+            admin: false,
+            active: true,
+            client_id: None,
+            scopes: vec!["users:read".to_string()],
+            permissions: vec!["roles:manage".to_string()],
+        };
+        assert!(UsersRead::is_met(&auth));
+        assert!(!UsersWrite::is_met(&auth));
+        assert!(RolesManage::is_met(&auth));
+        assert!(!KeysManage::is_met(&auth));
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use diesel::result::Error;
+use diesel::upsert::excluded;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::models::revoked_token_model::RevokedTokenModel;
+use crate::models::user_token_revocation_model::UserTokenRevocationModel;
+use crate::schema::{refresh_tokens, revoked_tokens, user_token_revocations};
+
+struct CacheEntry<T> {
+    value: T,
+    cached_at: Instant,
+}
+
+/**
+ * In-process cache in front of the revocation tables. Revoked tokens are cached until they
+ * expire, everything else is cached for `ttl` so revocations made by other replicas are
+ * picked up quickly.
+ */
+pub struct RevocationCache {
+    ttl: Duration,
+    tokens: RwLock<HashMap<Uuid, CacheEntry<Option<i64>>>>,
+    users: RwLock<HashMap<Uuid, CacheEntry<Option<i64>>>>,
+}
+
+impl RevocationCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            tokens: RwLock::new(HashMap::new()),
+            users: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry<Option<i64>>, now: i64) -> bool {
+        match entry.value {
+            Some(expires_at) => expires_at > now,
+            None => entry.cached_at.elapsed() < self.ttl,
+        }
+    }
+
+    fn get_token(&self, jti: &Uuid) -> Option<Option<i64>> {
+        let now = chrono::Utc::now().timestamp();
+        let tokens = self.tokens.read().unwrap();
+        tokens
+            .get(jti)
+            .filter(|entry| self.is_fresh(entry, now))
+            .map(|entry| entry.value)
+    }
+
+    fn set_token(&self, jti: Uuid, revoked_until: Option<i64>) {
+        let now = chrono::Utc::now().timestamp();
+        let mut tokens = self.tokens.write().unwrap();
+        tokens.retain(|_, entry| self.is_fresh(entry, now));
+        tokens.insert(
+            jti,
+            CacheEntry {
+                value: revoked_until,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    fn get_user(&self, user_id: &Uuid) -> Option<Option<i64>> {
+        let users = self.users.read().unwrap();
+        users
+            .get(user_id)
+            .filter(|entry| entry.cached_at.elapsed() < self.ttl)
+            .map(|entry| entry.value)
+    }
+
+    fn set_user(&self, user_id: Uuid, revoked_before: Option<i64>) {
+        let mut users = self.users.write().unwrap();
+        users.retain(|_, entry| entry.cached_at.elapsed() < self.ttl);
+        users.insert(
+            user_id,
+            CacheEntry {
+                value: revoked_before,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+}
+
+pub struct RevocationService;
+
+impl RevocationService {
+    /**
+     * Checks whether a token has been revoked, either by itself or through its user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param cache: &RevocationCache
+     * @param jti: Uuid
+     * @param user_id: Uuid
+     * @param issued_at: i64
+     * @return Result<bool, Error>
+     */
+    pub async fn is_revoked(
+        conn: &mut AsyncPgConnection,
+        cache: &RevocationCache,
+        jti: Uuid,
+        user_id: Uuid,
+        issued_at: i64,
+    ) -> Result<bool, Error> {
+        let revoked_until = match cache.get_token(&jti) {
+            Some(revoked_until) => revoked_until,
+            None => {
+                let revoked_until = revoked_tokens::table
+                    .find(jti)
+                    .select(revoked_tokens::expires_at)
+                    .get_result::<NaiveDateTime>(conn)
+                    .await
+                    .map(|expires_at| Some(expires_at.and_utc().timestamp()))
+                    .or_else(|e| match e {
+                        Error::NotFound => Ok(None),
+                        e => Err(e),
+                    })?;
+                cache.set_token(jti, revoked_until);
+                revoked_until
+            }
+        };
+        if revoked_until.is_some() {
+            log::warn!("Token {} has been revoked", jti);
+            return Ok(true);
+        }
+
+        let revoked_before = match cache.get_user(&user_id) {
+            Some(revoked_before) => revoked_before,
+            None => {
+                let revoked_before = user_token_revocations::table
+                    .find(user_id)
+                    .select(user_token_revocations::revoked_before)
+                    .get_result::<NaiveDateTime>(conn)
+                    .await
+                    .map(|revoked_before| Some(revoked_before.and_utc().timestamp()))
+                    .or_else(|e| match e {
+                        Error::NotFound => Ok(None),
+                        e => Err(e),
+                    })?;
+                cache.set_user(user_id, revoked_before);
+                revoked_before
+            }
+        };
+        if Self::revokes(revoked_before, issued_at) {
+            log::warn!("Sessions of user {} have been revoked", user_id);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /**
+     * Checks whether a revocation of all sessions covers a token. Both are in whole seconds, so
+     * tokens issued within the second of the revocation are kept, as they may have been issued
+     * right after it.
+     *
+     * @param revoked_before: Option<i64>
+     * @param issued_at: i64
+     * @return bool
+     */
+    fn revokes(revoked_before: Option<i64>, issued_at: i64) -> bool {
+        revoked_before.is_some_and(|revoked_before| issued_at < revoked_before)
+    }
+
+    /**
+     * Revokes a single access token until it expires
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param cache: &RevocationCache
+     * @param jti: Uuid
+     * @param user_id: Uuid
+     * @param expires_at: i64
+     * @return Result<(), Error>
+     */
+    pub async fn revoke_token(
+        conn: &mut AsyncPgConnection,
+        cache: &RevocationCache,
+        jti: Uuid,
+        user_id: Uuid,
+        expires_at: i64,
+    ) -> Result<(), Error> {
+        let now = chrono::Utc::now().naive_utc();
+        let revoked_until = chrono::DateTime::from_timestamp(expires_at, 0)
+            .map(|revoked_until| revoked_until.naive_utc())
+            .unwrap_or(now);
+
+        // Expired tokens are rejected by their signature check, no need to keep them around
+        diesel::delete(revoked_tokens::table.filter(revoked_tokens::expires_at.lt(now)))
+            .execute(conn)
+            .await?;
+        diesel::insert_into(revoked_tokens::table)
+            .values(&RevokedTokenModel::new(jti, user_id, revoked_until))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to revoke token {}: {}", jti, e);
+                e
+            })?;
+        cache.set_token(jti, Some(expires_at));
+        log::info!("Token {} of user {} revoked", jti, user_id);
+        Ok(())
+    }
+
+    /**
+     * Revokes every access and refresh token issued to a user so far
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param cache: &RevocationCache
+     * @param user_id: Uuid
+     * @return Result<(), Error>
+     */
+    pub async fn revoke_user(
+        conn: &mut AsyncPgConnection,
+        cache: &RevocationCache,
+        user_id: Uuid,
+    ) -> Result<(), Error> {
+        let revocation = UserTokenRevocationModel::new(user_id);
+        diesel::insert_into(user_token_revocations::table)
+            .values(&revocation)
+            .on_conflict(user_token_revocations::user_id)
+            .do_update()
+            .set(
+                user_token_revocations::revoked_before
+                    .eq(excluded(user_token_revocations::revoked_before)),
+            )
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to revoke sessions of user {}: {}", user_id, e);
+                e
+            })?;
+        diesel::update(
+            refresh_tokens::table
+                .filter(refresh_tokens::user_id.eq(user_id))
+                .filter(refresh_tokens::revoked_at.is_null()),
+        )
+        .set(refresh_tokens::revoked_at.eq(revocation.revoked_before))
+        .execute(conn)
+        .await?;
+        cache.set_user(
+            user_id,
+            Some(revocation.revoked_before.and_utc().timestamp()),
+        );
+        log::info!("All sessions of user {} revoked", user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cache_token() {
+        let cache = RevocationCache::new(Duration::from_secs(30));
+        let jti = Uuid::new_v4();
+        let expires_at = chrono::Utc::now().timestamp() + 60;
+        assert_eq!(cache.get_token(&jti), None);
+        cache.set_token(jti, None);
+        assert_eq!(cache.get_token(&jti), Some(None));
+        cache.set_token(jti, Some(expires_at));
+        assert_eq!(cache.get_token(&jti), Some(Some(expires_at)));
+    }
+
+    #[tokio::test]
+    async fn test_cache_expiry() {
+        let cache = RevocationCache::new(Duration::ZERO);
+        let now = chrono::Utc::now().timestamp();
+        let jti = Uuid::new_v4();
+        let revoked_jti = Uuid::new_v4();
+        let expired_jti = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        cache.set_token(jti, None);
+        cache.set_token(revoked_jti, Some(now + 60));
+        cache.set_token(expired_jti, Some(now - 60));
+        cache.set_user(user_id, Some(now));
+        assert_eq!(cache.get_token(&jti), None);
+        assert_eq!(cache.get_token(&revoked_jti), Some(Some(now + 60)));
+        assert_eq!(cache.get_token(&expired_jti), None);
+        assert_eq!(cache.get_user(&user_id), None);
+    }
+
+    #[tokio::test]
+    async fn test_revokes() {
+        let revocation = UserTokenRevocationModel::new(Uuid::new_v4());
+        let revoked_before = revocation.revoked_before.and_utc().timestamp();
+        assert_eq!(
+            revocation.revoked_before.and_utc().timestamp_subsec_nanos(),
+            0
+        );
+        assert!(RevocationService::revokes(
+            Some(revoked_before),
+            revoked_before - 1
+        ));
+        assert!(!RevocationService::revokes(
+            Some(revoked_before),
+            revoked_before
+        ));
+        assert!(!RevocationService::revokes(None, revoked_before));
+    }
+}
@@ -0,0 +1,289 @@
+use diesel::result::Error;
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::models::permission_model::PermissionModel;
+use crate::models::role_model::RoleModel;
+use crate::models::role_permission_model::RolePermissionModel;
+use crate::models::user_model::UserModel;
+use crate::models::user_role_model::UserRoleModel;
+use crate::repositories::permission_repository::PermissionRepository;
+use crate::repositories::role_repository::RoleRepository;
+use crate::schema::{permissions, role_permissions, roles, user_roles};
+use crate::schemas::role_schemas::{PermissionResponse, RoleResponse};
+
+/// Role every permission is granted to. `UserModel.is_admin` is kept as an alias of it
+const ADMIN_ROLE: &str = "admin";
+
+pub struct RoleService;
+
+impl RoleService {
+    /**
+     * Lists every role
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @return Result<Vec<RoleResponse>, Error>
+     */
+    pub async fn list_roles(conn: &mut AsyncPgConnection) -> Result<Vec<RoleResponse>, Error> {
+        let roles = roles::table
+            .order(roles::name)
+            .load::<RoleModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to list roles: {}", e);
+                e
+            })?;
+        Ok(roles.into_iter().map(RoleRepository::to_response).collect())
+    }
+
+    /**
+     * Lists every permission
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @return Result<Vec<PermissionResponse>, Error>
+     */
+    pub async fn list_permissions(
+        conn: &mut AsyncPgConnection,
+    ) -> Result<Vec<PermissionResponse>, Error> {
+        let permissions = permissions::table
+            .order(permissions::name)
+            .load::<PermissionModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to list permissions: {}", e);
+                e
+            })?;
+        Ok(permissions
+            .into_iter()
+            .map(PermissionRepository::to_response)
+            .collect())
+    }
+
+    /**
+     * Lists the permissions granted to a role
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param role_id: Uuid
+     * @return Result<Vec<PermissionResponse>, Error>
+     */
+    pub async fn role_permissions(
+        conn: &mut AsyncPgConnection,
+        role_id: Uuid,
+    ) -> Result<Vec<PermissionResponse>, Error> {
+        let permissions = role_permissions::table
+            .inner_join(permissions::table)
+            .filter(role_permissions::role_id.eq(role_id))
+            .select(permissions::all_columns)
+            .order(permissions::name)
+            .load::<PermissionModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to list permissions of role {}: {}", role_id, e);
+                e
+            })?;
+        Ok(permissions
+            .into_iter()
+            .map(PermissionRepository::to_response)
+            .collect())
+    }
+
+    /**
+     * Grants a permission to a role
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param role_id: Uuid
+     * @param permission_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn grant_permission(
+        conn: &mut AsyncPgConnection,
+        role_id: Uuid,
+        permission_id: Uuid,
+    ) -> Result<usize, Error> {
+        diesel::insert_into(role_permissions::table)
+            .values(&RolePermissionModel::new(role_id, permission_id))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Failed to grant permission {} to role {}: {}",
+                    permission_id,
+                    role_id,
+                    e
+                );
+                e
+            })
+    }
+
+    /**
+     * Revokes a permission from a role
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param role_id: Uuid
+     * @param permission_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn revoke_permission(
+        conn: &mut AsyncPgConnection,
+        role_id: Uuid,
+        permission_id: Uuid,
+    ) -> Result<usize, Error> {
+        diesel::delete(role_permissions::table.find((role_id, permission_id)))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Failed to revoke permission {} from role {}: {}",
+                    permission_id,
+                    role_id,
+                    e
+                );
+                e
+            })
+    }
+
+    /**
+     * Lists the roles of a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<Vec<RoleResponse>, Error>
+     */
+    pub async fn user_roles(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Vec<RoleResponse>, Error> {
+        let roles = user_roles::table
+            .inner_join(roles::table)
+            .filter(user_roles::user_id.eq(user_id))
+            .select(roles::all_columns)
+            .order(roles::name)
+            .load::<RoleModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to list roles of user {}: {}", user_id, e);
+                e
+            })?;
+        Ok(roles.into_iter().map(RoleRepository::to_response).collect())
+    }
+
+    /**
+     * Assigns a role to a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param role_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn assign_role(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        role_id: Uuid,
+    ) -> Result<usize, Error> {
+        diesel::insert_into(user_roles::table)
+            .values(&UserRoleModel::new(user_id, role_id))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Failed to assign role {} to user {}: {}",
+                    role_id,
+                    user_id,
+                    e
+                );
+                e
+            })
+    }
+
+    /**
+     * Removes a role from a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param role_id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn unassign_role(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        role_id: Uuid,
+    ) -> Result<usize, Error> {
+        diesel::delete(user_roles::table.find((user_id, role_id)))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Failed to remove role {} from user {}: {}",
+                    role_id,
+                    user_id,
+                    e
+                );
+                e
+            })
+    }
+
+    /**
+     * Resolves the permissions of a user through their roles
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @return Result<Vec<String>, Error>
+     */
+    pub async fn user_permissions(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+    ) -> Result<Vec<String>, Error> {
+        let role_ids = user_roles::table
+            .filter(user_roles::user_id.eq(user.id))
+            .select(user_roles::role_id);
+        let query = role_permissions::table
+            .inner_join(permissions::table)
+            .inner_join(roles::table)
+            .select(permissions::name)
+            .distinct()
+            .order(permissions::name)
+            .into_boxed();
+        let query = if user.is_admin {
+            query.filter(roles::id.eq_any(role_ids).or(roles::name.eq(ADMIN_ROLE)))
+        } else {
+            query.filter(roles::id.eq_any(role_ids))
+        };
+        query.load::<String>(conn).await.map_err(|e| {
+            log::error!("Failed to resolve permissions of user {}: {}", user.id, e);
+            e
+        })
+    }
+
+    /**
+     * Checks whether one of the roles of a user requires multi-factor authentication
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @return Result<bool, Error>
+     */
+    pub async fn mfa_required(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+    ) -> Result<bool, Error> {
+        let role_ids = user_roles::table
+            .filter(user_roles::user_id.eq(user.id))
+            .select(user_roles::role_id);
+        let query = roles::table
+            .filter(roles::mfa_required.eq(true))
+            .select(roles::id)
+            .into_boxed();
+        let query = if user.is_admin {
+            query.filter(roles::id.eq_any(role_ids).or(roles::name.eq(ADMIN_ROLE)))
+        } else {
+            query.filter(roles::id.eq_any(role_ids))
+        };
+        let role = query.first::<Uuid>(conn).await.optional().map_err(|e| {
+            log::error!("Failed to check MFA requirement of user {}: {}", user.id, e);
+            e
+        })?;
+        Ok(role.is_some())
+    }
+}
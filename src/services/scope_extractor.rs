@@ -0,0 +1,38 @@
+use crate::services::auth_extractor::AuthExtractorService;
+use crate::services::requirement_extractor::{require_extractor, requirements, Requirement};
+
+/**
+ * A scope a route can require
+ */
+pub trait Scope: Requirement {}
+
+/**
+ * Checks whether the token carries a scope
+ *
+ * @param auth: &AuthExtractorService
+ * @param scope: &str
+ * @return bool
+ */
+fn has_scope(auth: &AuthExtractorService, scope: &str) -> bool {
+    auth.scopes.iter().any(|granted| granted == scope)
+}
+
+requirements! {
+    Scope, "scope", has_scope;
+    UsersRead => "users:read",
+    UsersWrite => "users:write",
+    SchoolsRead => "schools:read",
+    SchoolsWrite => "schools:write",
+    StudentsRead => "students:read",
+    StudentsWrite => "students:write",
+    ClassesRead => "classes:read",
+    ClassesWrite => "classes:write",
+    SchedulesRead => "schedules:read",
+    SchedulesWrite => "schedules:write",
+}
+
+require_extractor! {
+    /// Authenticates the request like `AuthExtractorService` and requires the token to carry the
+    /// scope `S`
+    RequireScope, Scope
+}
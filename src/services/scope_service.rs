@@ -0,0 +1,117 @@
+use crate::services::oidc_service::{OidcService, OIDC_SCOPES};
+
+/// Every scope protecting the API
+pub const API_SCOPES: [&str; 10] = [
+    "users:read",
+    "users:write",
+    "schools:read",
+    "schools:write",
+    "students:read",
+    "students:write",
+    "classes:read",
+    "classes:write",
+    "schedules:read",
+    "schedules:write",
+];
+
+/// Scopes a user is only entitled to when one of their roles grants the permission of the same
+/// name. `users:write` is not one of them, as users may update their own account and the
+/// ownership policy keeps them from updating others.
+const ADMIN_SCOPES: [&str; 1] = ["schools:write"];
+
+pub struct ScopeService;
+
+impl ScopeService {
+    /**
+     * Lists the scopes a user holding the given permissions is entitled to
+     *
+     * @param permissions: &[String]
+     * @return Vec<String>
+     */
+    pub fn entitled_scopes(permissions: &[String]) -> Vec<String> {
+        API_SCOPES
+            .iter()
+            .filter(|scope| {
+                !ADMIN_SCOPES.contains(scope) || permissions.iter().any(|p| p == *scope)
+            })
+            .map(|scope| scope.to_string())
+            .collect()
+    }
+
+    /**
+     * Limits the requested scopes to the entitled ones, along with the requested OpenID Connect
+     * scopes. Without a requested scope every entitled scope is granted.
+     *
+     * @param requested: Option<&str>
+     * @param entitled: &[String]
+     * @return Vec<String>
+     */
+    pub fn grant(requested: Option<&str>, entitled: &[String]) -> Vec<String> {
+        match requested {
+            Some(requested) => OIDC_SCOPES
+                .iter()
+                .map(|scope| scope.to_string())
+                .chain(entitled.iter().cloned())
+                .filter(|scope| OidcService::has_scope(requested, scope))
+                .collect(),
+            None => entitled.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    use crate::services::auth_extractor::AuthExtractorService;
+    use crate::services::ownership_service::OwnershipService;
+    use crate::services::requirement_extractor::Requirement;
+    use crate::services::scope_extractor::UsersWrite;
+
+    #[tokio::test]
+    async fn test_entitled_scopes() {
+        let scopes = ScopeService::entitled_scopes(&[]);
+        assert!(scopes.contains(&"schools:read".to_string()));
+        assert!(!scopes.contains(&"schools:write".to_string()));
+        assert!(scopes.contains(&"users:write".to_string()));
+
+        let permissions = vec!["schools:write".to_string()];
+        assert_eq!(
+            ScopeService::entitled_scopes(&permissions).len(),
+            API_SCOPES.len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_own_profile() {
+        let id = Uuid::new_v4();
+        let user = AuthExtractorService {
+            jti: Uuid::new_v4(),
+            exp: 0,
+            id,
+            tenant_id: None,
+            email: "user@example.com".to_string(),
+            admin: false,
+            active: true,
+            client_id: None,
+            scopes: ScopeService::grant(None, &ScopeService::entitled_scopes(&[])),
+            permissions: Vec::new(),
+        };
+        assert!(UsersWrite::is_met(&user));
+        assert!(OwnershipService::require_user(&user, id).is_ok());
+        assert!(OwnershipService::require_user(&user, Uuid::new_v4()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grant() {
+        let entitled = vec!["schools:read".to_string(), "students:read".to_string()];
+        assert_eq!(ScopeService::grant(None, &entitled), entitled);
+        assert_eq!(
+            ScopeService::grant(Some("openid students:read schools:write"), &entitled),
+            vec!["openid".to_string(), "students:read".to_string()]
+        );
+        assert!(ScopeService::grant(Some(""), &entitled).is_empty());
+    }
+}
@@ -1,27 +1,28 @@
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use serde::Serialize;
 
 use crate::configs::common::AuthConfig;
 use crate::schemas::auth_schemas::TokenClaims;
+use crate::services::key_service::{KeyRing, SigningKey};
 
 pub struct TokenService;
 
 impl TokenService {
     /**
-     * Encodes a token with the given secret and claim
+     * Encodes a token with the given signing key and claim
      *
-     * @param secret: &str
-     * @param claim: TokenClaims
+     * @param signing_key: &SigningKey
+     * @param claim: T
      */
-    pub async fn encode(
-        secret: &str,
-        claim: TokenClaims,
+    pub async fn encode<T: Serialize>(
+        signing_key: &SigningKey,
+        claim: T,
     ) -> Result<String, jsonwebtoken::errors::Error> {
-        let token = encode(
-            &Header::default(),
-            &claim,
-            &EncodingKey::from_secret(secret.as_ref()),
-        )
-        .map_err(|e| {
+        let mut header = Header::new(signing_key.algorithm);
+        header.kid = Some(signing_key.kid.clone());
+
+        let token = encode(&header, &claim, &signing_key.encoding_key).map_err(|e| {
             log::error!("Failed to generate token: {}", e);
             e
         });
@@ -29,37 +30,67 @@ impl TokenService {
     }
 
     /**
-     * Decodes a token with the given secret and claim
+     * Decodes a token with the key of the key ring it was signed with. The issuer, one of the
+     * accepted audiences and the token lifetime are validated, allowing for clock skew
      *
      * @param token: &str
+     * @param key_ring: &KeyRing
      * @param auth_config: &AuthConfig
      */
     pub async fn decode(
         token: &str,
+        key_ring: &KeyRing,
         auth_config: &AuthConfig,
     ) -> Result<TokenClaims, jsonwebtoken::errors::Error> {
-        let mut validation = Validation::new(Algorithm::HS256);
+        let signing_key = Self::find_key(token, key_ring)?;
+        let mut validation = Validation::new(signing_key.algorithm);
         validation.validate_exp = true;
+        validation.validate_nbf = true;
+        validation.leeway = auth_config.leeway_seconds;
+        validation.set_issuer(&[&auth_config.issuer]);
+        validation.set_audience(&auth_config.audiences());
+        validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud"]);
 
-        let token_data = decode::<TokenClaims>(
-            token,
-            &DecodingKey::from_secret(&auth_config.secret_key.as_ref()),
-            &validation,
-        );
+        let token_data = decode::<TokenClaims>(token, &signing_key.decoding_key, &validation);
         token_data.map(|data| data.claims).map_err(|e| {
             log::error!("Failed to authenticate token: {}", e);
             e
         })
     }
+
+    /**
+     * Finds the key a token was signed with, from its `kid` header
+     *
+     * @param token: &str
+     * @param key_ring: &KeyRing
+     */
+    pub fn find_key(
+        token: &str,
+        key_ring: &KeyRing,
+    ) -> Result<std::sync::Arc<SigningKey>, jsonwebtoken::errors::Error> {
+        let header = decode_header(token)?;
+        let kid = header.kid.ok_or_else(|| {
+            log::error!("Token has no key id");
+            jsonwebtoken::errors::Error::from(ErrorKind::InvalidToken)
+        })?;
+        key_ring.get(&kid).ok_or_else(|| {
+            log::error!("Unknown signing key {}", kid);
+            jsonwebtoken::errors::Error::from(ErrorKind::InvalidSignature)
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use chrono::{Duration, Utc};
+    use jsonwebtoken::Algorithm;
+    use ring::rand::SystemRandom;
+    use ring::signature::Ed25519KeyPair;
     use uuid::Uuid;
 
     use crate::configs::common::AuthConfig;
     use crate::schemas::auth_schemas::TokenClaims;
+    use crate::services::key_service::KeyService;
 
     use super::*;
 
@@ -70,45 +101,68 @@ mod tests {
         let auth_config = AuthConfig {
             secret_key: SECRET_KEY.to_string(),
             token_expire_minutes: 10_i64,
-            issuer: "".to_string(),
-            audience: "".to_string(),
+            issuer: "https://auth.domain.com".to_string(),
+            audience: "api.domain.com, mobile.domain.com".to_string(),
+            ..AuthConfig::default()
         };
         let token_claims = TokenClaims {
+            jti: Uuid::new_v4(),
+            iss: auth_config.issuer.clone(),
+            aud: auth_config.audiences(),
             exp: Utc::now().timestamp()
                 + Duration::minutes(auth_config.token_expire_minutes).num_seconds(),
+            nbf: Utc::now().timestamp(),
             iat: Utc::now().timestamp(),
             sub: Uuid::parse_str("70819fbb-e89c-454a-b80a-507c994264ee").unwrap(),
             email: "test@domain.com".to_string(),
             tenant_id: None,
             admin: false,
             active: true,
+            client_id: None,
+            azp: None,
+            scope: None,
+            permissions: Vec::new(),
         };
-        let token = TokenService::encode(&auth_config.secret_key, token_claims).await;
+        let signing_key = SigningKey::from_secret("default", &auth_config.secret_key);
+        let token = TokenService::encode(&signing_key, token_claims).await;
         assert!(token.is_ok());
     }
 
     #[tokio::test]
+    #[allow(clippy::bool_assert_comparison)]
     async fn test_decode() {
         let auth_config = AuthConfig {
             secret_key: SECRET_KEY.to_string(),
             token_expire_minutes: 10_i64,
-            issuer: "".to_string(),
-            audience: "".to_string(),
+            issuer: "https://auth.domain.com".to_string(),
+            audience: "api.domain.com, mobile.domain.com".to_string(),
+            ..AuthConfig::default()
         };
         let token_claims = TokenClaims {
+            jti: Uuid::new_v4(),
+            iss: auth_config.issuer.clone(),
+            aud: auth_config.audiences(),
             exp: Utc::now().timestamp()
                 + Duration::minutes(auth_config.token_expire_minutes).num_seconds(),
+            nbf: Utc::now().timestamp(),
             iat: Utc::now().timestamp(),
             sub: Uuid::parse_str("70819fbb-e89c-454a-b80a-507c994264ee").unwrap(),
             email: "test@domain.com".to_string(),
             tenant_id: None,
             admin: false,
             active: true,
+            client_id: None,
+            azp: None,
+            scope: None,
+            permissions: Vec::new(),
         };
-        let token = TokenService::encode(&auth_config.secret_key, token_claims).await;
+        let configured_key = KeyService::configured_key(&auth_config).unwrap();
+        let signing_key = SigningKey::from_secret(&configured_key.kid, &configured_key.private_key);
+        let key_ring = KeyRing::new(signing_key, vec![]);
+        let token = TokenService::encode(&key_ring.active(), token_claims).await;
         assert!(token.is_ok());
         let token = token.unwrap();
-        let decoded = TokenService::decode(&token, &auth_config).await;
+        let decoded = TokenService::decode(&token, &key_ring, &auth_config).await;
         assert!(&decoded.is_ok());
 
         let claims = decoded.unwrap();
@@ -122,5 +176,131 @@ mod tests {
         );
         assert_eq!(claims.exp > Utc::now().timestamp(), true);
         assert_eq!(claims.iat <= Utc::now().timestamp(), true);
+        assert_eq!(claims.iss, "https://auth.domain.com");
+        assert_eq!(claims.aud, vec!["api.domain.com", "mobile.domain.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_decode_eddsa() {
+        let auth_config = AuthConfig {
+            secret_key: SECRET_KEY.to_string(),
+            token_expire_minutes: 10_i64,
+            issuer: "https://auth.domain.com".to_string(),
+            audience: "api.domain.com".to_string(),
+            signing_algorithm: "EdDSA".to_string(),
+            ..AuthConfig::default()
+        };
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let private_key = pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref().to_vec()));
+        let signing_key =
+            SigningKey::from_pem(None, Algorithm::EdDSA, private_key.as_bytes()).unwrap();
+        let token_claims = TokenClaims {
+            jti: Uuid::new_v4(),
+            iss: auth_config.issuer.clone(),
+            aud: auth_config.audiences(),
+            exp: Utc::now().timestamp() + Duration::minutes(10).num_seconds(),
+            nbf: Utc::now().timestamp(),
+            iat: Utc::now().timestamp(),
+            sub: Uuid::parse_str("70819fbb-e89c-454a-b80a-507c994264ee").unwrap(),
+            email: "test@domain.com".to_string(),
+            tenant_id: None,
+            admin: false,
+            active: true,
+            client_id: None,
+            azp: None,
+            scope: None,
+            permissions: Vec::new(),
+        };
+        let token = TokenService::encode(&signing_key, token_claims)
+            .await
+            .unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid, Some(signing_key.kid.clone()));
+
+        let previous_key = SigningKey::from_secret("default", SECRET_KEY);
+        let key_ring = KeyRing::new(previous_key, vec![signing_key]);
+        let decoded = TokenService::decode(&token, &key_ring, &auth_config).await;
+        assert!(decoded.is_ok());
+
+        let other_key = SigningKey::from_secret("default", SECRET_KEY);
+        let key_ring = KeyRing::new(other_key, vec![]);
+        assert!(TokenService::decode(&token, &key_ring, &auth_config)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_claims_validation() {
+        let mut auth_config = AuthConfig {
+            secret_key: SECRET_KEY.to_string(),
+            token_expire_minutes: 10_i64,
+            issuer: "https://auth.domain.com".to_string(),
+            audience: "api.domain.com,mobile.domain.com".to_string(),
+            ..AuthConfig::default()
+        };
+        let now = Utc::now().timestamp();
+        let key_ring = KeyRing::new(SigningKey::from_secret("default", SECRET_KEY), vec![]);
+        let claims = |iss: &str, aud: &str, nbf: i64| TokenClaims {
+            jti: Uuid::new_v4(),
+            iss: iss.to_string(),
+            aud: vec![aud.to_string()],
+            exp: now + Duration::minutes(10).num_seconds(),
+            nbf,
+            iat: now,
+            sub: Uuid::new_v4(),
+            email: "test@domain.com".to_string(),
+            tenant_id: None,
+            admin: false,
+            active: true,
+            client_id: None,
+            azp: None,
+            scope: None,
+            permissions: Vec::new(),
+        };
+
+        let token = TokenService::encode(
+            &key_ring.active(),
+            claims("https://auth.domain.com", "mobile.domain.com", now + 30),
+        )
+        .await
+        .unwrap();
+        assert!(TokenService::decode(&token, &key_ring, &auth_config)
+            .await
+            .is_ok());
+
+        let token = TokenService::encode(
+            &key_ring.active(),
+            claims("https://auth.domain.com", "mobile.domain.com", now + 120),
+        )
+        .await
+        .unwrap();
+        assert!(TokenService::decode(&token, &key_ring, &auth_config)
+            .await
+            .is_err());
+
+        let token = TokenService::encode(
+            &key_ring.active(),
+            claims("https://other.domain.com", "api.domain.com", now),
+        )
+        .await
+        .unwrap();
+        assert!(TokenService::decode(&token, &key_ring, &auth_config)
+            .await
+            .is_err());
+
+        let token = TokenService::encode(
+            &key_ring.active(),
+            claims("https://auth.domain.com", "other.domain.com", now),
+        )
+        .await
+        .unwrap();
+        assert!(TokenService::decode(&token, &key_ring, &auth_config)
+            .await
+            .is_err());
+        auth_config.audience = "other.domain.com".to_string();
+        assert!(TokenService::decode(&token, &key_ring, &auth_config)
+            .await
+            .is_ok());
     }
 }
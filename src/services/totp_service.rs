@@ -0,0 +1,229 @@
+use rand::RngCore;
+use ring::hmac;
+
+/// RFC 4648 alphabet used by authenticator apps for TOTP secrets
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// Length of a time step in seconds
+const PERIOD: i64 = 30;
+/// Number of digits of a code
+const DIGITS: u32 = 6;
+/// Number of steps a code is still accepted before or after the current one, for clock drift
+const WINDOW: i64 = 1;
+
+/**
+ * Time-based one-time passwords (RFC 6238) with HMAC-SHA1, 6 digits and a 30 seconds period,
+ * which is what every common authenticator app expects.
+ */
+pub struct TotpService;
+
+impl TotpService {
+    /**
+     * Generates a new random secret, base32 encoded
+     *
+     * @return String
+     */
+    pub fn generate_secret() -> String {
+        let mut bytes = [0u8; 20];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self::encode_base32(&bytes)
+    }
+
+    /**
+     * Builds the `otpauth://` URI that authenticator apps read from a QR code
+     *
+     * @param issuer: &str
+     * @param account: &str
+     * @param secret: &str
+     * @return String
+     */
+    pub fn provisioning_uri(issuer: &str, account: &str, secret: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            Self::percent_encode(issuer),
+            Self::percent_encode(account),
+            secret,
+            Self::percent_encode(issuer),
+            DIGITS,
+            PERIOD
+        )
+    }
+
+    /**
+     * Computes the code of a time step
+     *
+     * @param secret: &[u8]
+     * @param step: i64
+     * @return String
+     */
+    pub fn code(secret: &[u8], step: i64) -> String {
+        let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+        let digest = hmac::sign(&key, &step.to_be_bytes());
+        let digest = digest.as_ref();
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10_u32.pow(DIGITS),
+            width = DIGITS as usize
+        )
+    }
+
+    /**
+     * Checks a code against the steps around the given time. Steps up to `last_used_step` are
+     * rejected so a code can not be replayed.
+     *
+     * @param secret: &str
+     * @param code: &str
+     * @param timestamp: i64
+     * @param last_used_step: Option<i64>
+     * @return Option<i64> the matching step
+     */
+    pub fn verify(
+        secret: &str,
+        code: &str,
+        timestamp: i64,
+        last_used_step: Option<i64>,
+    ) -> Option<i64> {
+        let secret = Self::decode_base32(secret)?;
+        let code = code.trim();
+        let current = timestamp / PERIOD;
+        (current - WINDOW..=current + WINDOW)
+            .filter(|step| last_used_step.is_none_or(|last| *step > last))
+            .find(|step| {
+                ring::constant_time::verify_slices_are_equal(
+                    Self::code(&secret, *step).as_bytes(),
+                    code.as_bytes(),
+                )
+                .is_ok()
+            })
+    }
+
+    /**
+     * Encodes bytes as unpadded base32
+     *
+     * @param bytes: &[u8]
+     * @return String
+     */
+    pub fn encode_base32(bytes: &[u8]) -> String {
+        let mut encoded = String::new();
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+        for byte in bytes {
+            buffer = (buffer << 8) | *byte as u32;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+        }
+        encoded
+    }
+
+    /**
+     * Decodes base32, ignoring case, padding and spaces
+     *
+     * @param encoded: &str
+     * @return Option<Vec<u8>>
+     */
+    pub fn decode_base32(encoded: &str) -> Option<Vec<u8>> {
+        let mut decoded = Vec::new();
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+        for c in encoded.chars().filter(|c| *c != '=' && *c != ' ') {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|a| *a as char == c.to_ascii_uppercase())?;
+            buffer = (buffer << 5) | value as u32;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                decoded.push((buffer >> bits) as u8);
+            }
+        }
+        Some(decoded)
+    }
+
+    fn percent_encode(value: &str) -> String {
+        value
+            .bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                    (byte as char).to_string()
+                }
+                _ => format!("%{:02X}", byte),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[tokio::test]
+    async fn test_code() {
+        assert_eq!(TotpService::code(RFC_SECRET, 59 / PERIOD), "287082");
+        assert_eq!(TotpService::code(RFC_SECRET, 1111111109 / PERIOD), "081804");
+        assert_eq!(TotpService::code(RFC_SECRET, 2000000000 / PERIOD), "279037");
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let secret = TotpService::encode_base32(RFC_SECRET);
+        assert_eq!(
+            TotpService::verify(&secret, "081804", 1111111109, None),
+            Some(1111111109 / PERIOD)
+        );
+        assert_eq!(
+            TotpService::verify(&secret, "081804", 1111111109 + PERIOD, None),
+            Some(1111111109 / PERIOD)
+        );
+        assert_eq!(
+            TotpService::verify(&secret, "081804", 1111111109 + 3 * PERIOD, None),
+            None
+        );
+        assert_eq!(
+            TotpService::verify(&secret, "081804", 1111111109, Some(1111111109 / PERIOD)),
+            None
+        );
+        assert_eq!(
+            TotpService::verify(&secret, "000000", 1111111109, None),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_base32() {
+        assert_eq!(
+            TotpService::encode_base32(RFC_SECRET),
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+        assert_eq!(TotpService::encode_base32(b"f"), "MY");
+        assert_eq!(
+            TotpService::decode_base32("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap(),
+            RFC_SECRET
+        );
+        assert_eq!(TotpService::decode_base32("MY======").unwrap(), b"f");
+        assert!(TotpService::decode_base32("M1").is_none());
+        let secret = TotpService::generate_secret();
+        assert_eq!(TotpService::decode_base32(&secret).unwrap().len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_uri() {
+        assert_eq!(
+            TotpService::provisioning_uri("My App", "jane@domain.com", "ABC"),
+            "otpauth://totp/My%20App:jane@domain.com?secret=ABC&issuer=My%20App&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
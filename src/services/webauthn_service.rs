@@ -0,0 +1,1010 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Duration;
+use diesel::result::Error;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use ring::signature;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::configs::common::AuthConfig;
+use crate::helper::cbor::CborValue;
+use crate::helper::exceptions::AppError;
+use crate::models::user_model::UserModel;
+use crate::models::webauthn_challenge_model::WebAuthnChallengeModel;
+use crate::models::webauthn_credential_model::WebAuthnCredentialModel;
+use crate::schema::{users, webauthn_challenges, webauthn_credentials};
+use crate::schemas::auth_schemas::LoginResponse;
+use crate::schemas::webauthn_schemas::{
+    AssertionRequest, AuthenticatorSelection, CreationOptions, CredentialDescriptor,
+    CredentialParameter, RegistrationRequest, RelyingParty, RequestOptions,
+    WebAuthnCredentialResponse, WebAuthnLoginOptionsRequest, WebAuthnMfaOptionsRequest,
+    WebAuthnMfaVerifyRequest, WebAuthnUser,
+};
+use crate::services::auth_service::AuthService;
+use crate::services::email_verification_service::EmailVerificationService;
+use crate::services::key_service::KeyRing;
+use crate::services::lockout_service::LockoutService;
+use crate::services::mfa_service::MfaService;
+use crate::services::refresh_token_service::RefreshTokenService;
+
+const CEREMONY_REGISTRATION: &str = "registration";
+const CEREMONY_AUTHENTICATION: &str = "authentication";
+const CREDENTIAL_TYPE: &str = "public-key";
+
+/// User present
+const FLAG_UP: u8 = 0x01;
+/// User verified, by PIN or biometrics
+const FLAG_UV: u8 = 0x04;
+/// Attested credential data included
+const FLAG_AT: u8 = 0x40;
+
+/// COSE algorithms accepted for credentials: ES256, EdDSA and RS256
+const COSE_ES256: i128 = -7;
+const COSE_EDDSA: i128 = -8;
+const COSE_RS256: i128 = -257;
+/// COSE key types and curves the accepted algorithms require
+const COSE_KTY_OKP: i128 = 1;
+const COSE_KTY_EC2: i128 = 2;
+const COSE_KTY_RSA: i128 = 3;
+const COSE_CRV_P256: i128 = 1;
+const COSE_CRV_ED25519: i128 = 6;
+
+/// Attestation formats accepted on registration
+const ATTESTATION_NONE: &str = "none";
+const ATTESTATION_PACKED: &str = "packed";
+
+#[derive(Debug, Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct AuthenticatorData {
+    rp_id_hash: Vec<u8>,
+    flags: u8,
+    sign_count: u32,
+    credential_id: Option<Vec<u8>>,
+    public_key: Option<Vec<u8>>,
+}
+
+/**
+ * WebAuthn (passkey) registration and authentication ceremonies. Credentials can be used as the
+ * second factor of a password login or on their own for a passwordless login, which requires
+ * user verification by the authenticator.
+ *
+ * Registrations request `none` attestation and only accept `none` or `packed` self attestation,
+ * whose signature is checked with the credential key. Attestation certificates are rejected, so
+ * the authenticator model is not verified and credentials are trusted on first use, like a
+ * password set by the user.
+ */
+pub struct WebAuthnService;
+
+impl WebAuthnService {
+    /**
+     * Starts the registration of a credential for a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user: &UserModel
+     * @param auth_config: &AuthConfig
+     * @return Result<CreationOptions, Error>
+     */
+    pub async fn registration_options(
+        conn: &mut AsyncPgConnection,
+        user: &UserModel,
+        auth_config: &AuthConfig,
+    ) -> Result<CreationOptions, Error> {
+        let challenge =
+            Self::create_challenge(conn, Some(user.id), CEREMONY_REGISTRATION, auth_config).await?;
+        let exclude_credentials = Self::credentials_of(conn, user.id)
+            .await?
+            .iter()
+            .map(Self::descriptor)
+            .collect();
+        Ok(CreationOptions {
+            challenge,
+            rp: RelyingParty {
+                id: auth_config.webauthn_rp_id.clone(),
+                name: auth_config.webauthn_rp_name.clone(),
+            },
+            user: WebAuthnUser {
+                id: Self::user_handle(user.id),
+                name: user.email.clone(),
+                display_name: user.email.clone(),
+            },
+            pub_key_cred_params: [COSE_ES256, COSE_EDDSA, COSE_RS256]
+                .iter()
+                .map(|alg| CredentialParameter {
+                    kind: CREDENTIAL_TYPE.to_string(),
+                    alg: *alg as i64,
+                })
+                .collect(),
+            timeout: auth_config.webauthn_challenge_expire_seconds * 1000,
+            attestation: "none".to_string(),
+            exclude_credentials,
+            authenticator_selection: AuthenticatorSelection {
+                resident_key: "preferred".to_string(),
+                user_verification: "preferred".to_string(),
+            },
+        })
+    }
+
+    /**
+     * Completes the registration of a credential
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param registration: RegistrationRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<WebAuthnCredentialResponse, AppError>
+     */
+    pub async fn register(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        registration: RegistrationRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<WebAuthnCredentialResponse, AppError> {
+        let (client_data, raw_client_data) = Self::check_client_data(
+            &registration.response.client_data_json,
+            "webauthn.create",
+            &auth_config.webauthn_origin,
+        )
+        .map_err(Self::invalid)?;
+        let challenge =
+            Self::consume_challenge(conn, &client_data.challenge, CEREMONY_REGISTRATION)
+                .await?
+                .ok_or_else(|| Self::invalid("Invalid challenge".to_string()))?;
+        if challenge.user_id != Some(user_id) {
+            return Err(Self::invalid(format!(
+                "Challenge was not issued to user {}",
+                user_id
+            )));
+        }
+
+        let attestation_object = URL_SAFE_NO_PAD
+            .decode(&registration.response.attestation_object)
+            .map_err(|e| Self::invalid(e.to_string()))?;
+        let (attestation, _) = CborValue::decode(&attestation_object).map_err(Self::invalid)?;
+        let format = attestation
+            .get_text("fmt")
+            .and_then(|value| value.as_text())
+            .ok_or_else(|| Self::invalid("Missing attestation format".to_string()))?;
+        log::info!("WebAuthn attestation format: {}", format);
+        let raw_authenticator_data = attestation
+            .get_text("authData")
+            .and_then(|value| value.as_bytes())
+            .ok_or_else(|| Self::invalid("Missing authenticator data".to_string()))?;
+        let authenticator_data = Self::check_authenticator_data(
+            raw_authenticator_data,
+            &auth_config.webauthn_rp_id,
+            false,
+        )
+        .map_err(Self::invalid)?;
+        let (credential_id, public_key) = match (
+            authenticator_data.credential_id,
+            authenticator_data.public_key,
+        ) {
+            (Some(credential_id), Some(public_key)) => (credential_id, public_key),
+            _ => return Err(Self::invalid("Missing attested credential".to_string())),
+        };
+        let statement = attestation
+            .get_text("attStmt")
+            .ok_or_else(|| Self::invalid("Missing attestation statement".to_string()))?;
+        Self::check_attestation(
+            format,
+            statement,
+            raw_authenticator_data,
+            &Sha256::digest(&raw_client_data),
+            &public_key,
+        )
+        .map_err(Self::invalid)?;
+        let credential_id = URL_SAFE_NO_PAD.encode(credential_id);
+        if credential_id != registration.id {
+            return Err(Self::invalid("Credential id mismatch".to_string()));
+        }
+
+        let transports = Some(registration.response.transports.join(","))
+            .filter(|transports| !transports.is_empty());
+        let credential = diesel::insert_into(webauthn_credentials::table)
+            .values(&WebAuthnCredentialModel::new(
+                user_id,
+                credential_id,
+                URL_SAFE_NO_PAD.encode(public_key),
+                authenticator_data.sign_count as i64,
+                transports,
+                registration.name,
+            ))
+            .get_result::<WebAuthnCredentialModel>(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to register WebAuthn credential: {}", e);
+                e
+            })?;
+        log::info!("WebAuthn credential registered for user {}", user_id);
+        Ok(Self::to_response(credential))
+    }
+
+    /**
+     * Starts a passwordless login. Without an email, any discoverable credential is accepted;
+     * unknown emails get the same response as accounts without credentials.
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param options_request: WebAuthnLoginOptionsRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<RequestOptions, Error>
+     */
+    pub async fn login_options(
+        conn: &mut AsyncPgConnection,
+        options_request: WebAuthnLoginOptionsRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<RequestOptions, Error> {
+        let user_id = match options_request.email {
+            Some(email) => users::table
+                .filter(users::email.eq(email))
+                .select(users::id)
+                .first::<Uuid>(conn)
+                .await
+                .optional()?,
+            None => None,
+        };
+        Self::request_options(conn, user_id, "required", auth_config).await
+    }
+
+    /**
+     * Completes a passwordless login
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param assertion: AssertionRequest
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginResponse, AppError>
+     */
+    pub async fn login(
+        conn: &mut AsyncPgConnection,
+        assertion: AssertionRequest,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<LoginResponse, AppError> {
+        let user_id = Self::authenticate(conn, &assertion, None, true, auth_config).await?;
+        let user = users::table
+            .find(user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        AuthService::require_active(&user)?;
+        EmailVerificationService::check_login(&user, auth_config)?;
+        AuthService::issue_tokens(conn, user, None, None, auth_config, key_ring).await
+    }
+
+    /**
+     * Starts the WebAuthn step of an MFA challenge
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param options_request: WebAuthnMfaOptionsRequest
+     * @param auth_config: &AuthConfig
+     * @return Result<RequestOptions, AppError>
+     */
+    pub async fn mfa_options(
+        conn: &mut AsyncPgConnection,
+        options_request: WebAuthnMfaOptionsRequest,
+        auth_config: &AuthConfig,
+    ) -> Result<RequestOptions, AppError> {
+        let challenge = MfaService::find_challenge(conn, &options_request.challenge_token).await?;
+        Ok(Self::request_options(conn, Some(challenge.user_id), "preferred", auth_config).await?)
+    }
+
+    /**
+     * Completes an MFA challenge with a WebAuthn assertion
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param verify_request: WebAuthnMfaVerifyRequest
+     * @param client_ip: Option<&str>
+     * @param auth_config: &AuthConfig
+     * @param key_ring: &KeyRing
+     * @return Result<LoginResponse, AppError>
+     */
+    pub async fn verify_mfa(
+        conn: &mut AsyncPgConnection,
+        verify_request: WebAuthnMfaVerifyRequest,
+        client_ip: Option<&str>,
+        auth_config: &AuthConfig,
+        key_ring: &KeyRing,
+    ) -> Result<LoginResponse, AppError> {
+        let challenge = MfaService::attempt(conn, &verify_request.challenge_token).await?;
+        let user = users::table
+            .find(challenge.user_id)
+            .get_result::<UserModel>(conn)
+            .await?;
+        LockoutService::require_unlocked(conn, &user.email, client_ip).await?;
+
+        let authenticated = Self::authenticate(
+            conn,
+            &verify_request.credential,
+            Some(user.id),
+            false,
+            auth_config,
+        )
+        .await;
+        if let Err(e @ (AppError::Unauthorized(_) | AppError::Validation(_))) = authenticated {
+            LockoutService::record_failure(conn, &user.email, client_ip, auth_config).await?;
+            return Err(e);
+        }
+        authenticated?;
+        MfaService::complete(conn, &challenge, auth_config, key_ring).await
+    }
+
+    /**
+     * Lists the credentials of a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<Vec<WebAuthnCredentialResponse>, Error>
+     */
+    pub async fn list_credentials(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Vec<WebAuthnCredentialResponse>, Error> {
+        Ok(Self::credentials_of(conn, user_id)
+            .await?
+            .into_iter()
+            .map(Self::to_response)
+            .collect())
+    }
+
+    /**
+     * Removes a credential of a user
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @param id: Uuid
+     * @return Result<usize, Error>
+     */
+    pub async fn delete_credential(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<usize, Error> {
+        diesel::delete(
+            webauthn_credentials::table
+                .find(id)
+                .filter(webauthn_credentials::user_id.eq(user_id)),
+        )
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to delete WebAuthn credential {}: {}", id, e);
+            e
+        })
+    }
+
+    /**
+     * Checks whether a user has registered a credential
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param user_id: Uuid
+     * @return Result<bool, Error>
+     */
+    pub async fn has_credentials(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<bool, Error> {
+        let credential = webauthn_credentials::table
+            .filter(webauthn_credentials::user_id.eq(user_id))
+            .select(webauthn_credentials::id)
+            .first::<Uuid>(conn)
+            .await
+            .optional()?;
+        Ok(credential.is_some())
+    }
+
+    /**
+     * Verifies an assertion and updates the sign counter of the credential
+     *
+     * @param conn: &mut AsyncPgConnection
+     * @param assertion: &AssertionRequest
+     * @param expected_user: Option<Uuid>
+     * @param user_verification: bool
+     * @param auth_config: &AuthConfig
+     * @return Result<Uuid, AppError> the user the credential belongs to
+     */
+    async fn authenticate(
+        conn: &mut AsyncPgConnection,
+        assertion: &AssertionRequest,
+        expected_user: Option<Uuid>,
+        user_verification: bool,
+        auth_config: &AuthConfig,
+    ) -> Result<Uuid, AppError> {
+        let (client_data, client_data_json) = Self::check_client_data(
+            &assertion.response.client_data_json,
+            "webauthn.get",
+            &auth_config.webauthn_origin,
+        )
+        .map_err(Self::rejected)?;
+        let challenge =
+            Self::consume_challenge(conn, &client_data.challenge, CEREMONY_AUTHENTICATION)
+                .await?
+                .ok_or_else(|| Self::rejected("Invalid challenge".to_string()))?;
+
+        let credential = webauthn_credentials::table
+            .filter(webauthn_credentials::credential_id.eq(&assertion.id))
+            .get_result::<WebAuthnCredentialModel>(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| Self::rejected(format!("Unknown credential {}", assertion.id)))?;
+        if challenge
+            .user_id
+            .is_some_and(|user_id| user_id != credential.user_id)
+            || expected_user.is_some_and(|user_id| user_id != credential.user_id)
+        {
+            return Err(Self::rejected(format!(
+                "Credential {} does not belong to the challenged user",
+                credential.id
+            )));
+        }
+        if let Some(user_handle) = &assertion.response.user_handle {
+            if *user_handle != Self::user_handle(credential.user_id) {
+                return Err(Self::rejected("User handle mismatch".to_string()));
+            }
+        }
+
+        let authenticator_data = URL_SAFE_NO_PAD
+            .decode(&assertion.response.authenticator_data)
+            .map_err(|e| Self::rejected(e.to_string()))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(&assertion.response.signature)
+            .map_err(|e| Self::rejected(e.to_string()))?;
+        let public_key = URL_SAFE_NO_PAD
+            .decode(&credential.public_key)
+            .map_err(|e| Self::rejected(e.to_string()))?;
+        let parsed = Self::check_authenticator_data(
+            &authenticator_data,
+            &auth_config.webauthn_rp_id,
+            user_verification,
+        )
+        .map_err(Self::rejected)?;
+
+        let mut message = authenticator_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data_json));
+        Self::verify_signature(&public_key, &message, &signature).map_err(Self::rejected)?;
+
+        if !Self::check_sign_count(credential.sign_count, parsed.sign_count) {
+            return Err(Self::rejected(format!(
+                "Sign counter of credential {} went backwards, it may be cloned",
+                credential.id
+            )));
+        }
+        diesel::update(webauthn_credentials::table.find(credential.id))
+            .set((
+                webauthn_credentials::sign_count.eq(parsed.sign_count as i64),
+                webauthn_credentials::last_used_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+            .await?;
+        Ok(credential.user_id)
+    }
+
+    async fn request_options(
+        conn: &mut AsyncPgConnection,
+        user_id: Option<Uuid>,
+        user_verification: &str,
+        auth_config: &AuthConfig,
+    ) -> Result<RequestOptions, Error> {
+        let challenge =
+            Self::create_challenge(conn, user_id, CEREMONY_AUTHENTICATION, auth_config).await?;
+        let allow_credentials = match user_id {
+            Some(user_id) => Self::credentials_of(conn, user_id)
+                .await?
+                .iter()
+                .map(Self::descriptor)
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(RequestOptions {
+            challenge,
+            timeout: auth_config.webauthn_challenge_expire_seconds * 1000,
+            rp_id: auth_config.webauthn_rp_id.clone(),
+            allow_credentials,
+            user_verification: user_verification.to_string(),
+        })
+    }
+
+    async fn create_challenge(
+        conn: &mut AsyncPgConnection,
+        user_id: Option<Uuid>,
+        ceremony: &str,
+        auth_config: &AuthConfig,
+    ) -> Result<String, Error> {
+        let challenge = RefreshTokenService::generate();
+        let expires_at = chrono::Utc::now().naive_utc()
+            + Duration::seconds(auth_config.webauthn_challenge_expire_seconds);
+        diesel::insert_into(webauthn_challenges::table)
+            .values(&WebAuthnChallengeModel::new(
+                RefreshTokenService::hash(&challenge),
+                user_id,
+                ceremony.to_string(),
+                expires_at,
+            ))
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                log::error!("Failed to create WebAuthn challenge: {}", e);
+                e
+            })?;
+        Ok(challenge)
+    }
+
+    async fn consume_challenge(
+        conn: &mut AsyncPgConnection,
+        challenge: &str,
+        ceremony: &str,
+    ) -> Result<Option<WebAuthnChallengeModel>, Error> {
+        diesel::update(
+            webauthn_challenges::table
+                .filter(
+                    webauthn_challenges::challenge_hash.eq(RefreshTokenService::hash(challenge)),
+                )
+                .filter(webauthn_challenges::ceremony.eq(ceremony))
+                .filter(webauthn_challenges::consumed_at.is_null())
+                .filter(webauthn_challenges::expires_at.gt(chrono::Utc::now().naive_utc())),
+        )
+        .set(webauthn_challenges::consumed_at.eq(chrono::Utc::now().naive_utc()))
+        .get_result::<WebAuthnChallengeModel>(conn)
+        .await
+        .optional()
+    }
+
+    async fn credentials_of(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> Result<Vec<WebAuthnCredentialModel>, Error> {
+        webauthn_credentials::table
+            .filter(webauthn_credentials::user_id.eq(user_id))
+            .order(webauthn_credentials::created_at)
+            .load::<WebAuthnCredentialModel>(conn)
+            .await
+    }
+
+    fn descriptor(credential: &WebAuthnCredentialModel) -> CredentialDescriptor {
+        CredentialDescriptor {
+            kind: CREDENTIAL_TYPE.to_string(),
+            id: credential.credential_id.clone(),
+            transports: credential
+                .transports
+                .as_ref()
+                .map(|transports| transports.split(',').map(String::from).collect()),
+        }
+    }
+
+    fn to_response(credential: WebAuthnCredentialModel) -> WebAuthnCredentialResponse {
+        WebAuthnCredentialResponse {
+            id: credential.id,
+            credential_id: credential.credential_id,
+            name: credential.name,
+            transports: credential
+                .transports
+                .map(|transports| transports.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+            sign_count: credential.sign_count,
+            created_at: credential.created_at,
+            last_used_at: credential.last_used_at,
+        }
+    }
+
+    /**
+     * Encodes the user handle given to authenticators. It is the user id, never the email
+     *
+     * @param user_id: Uuid
+     * @return String
+     */
+    fn user_handle(user_id: Uuid) -> String {
+        URL_SAFE_NO_PAD.encode(user_id.as_bytes())
+    }
+
+    /// Rejects an assertion, which is a credential like a password
+    fn rejected(reason: String) -> AppError {
+        log::error!("WebAuthn response rejected: {}", reason);
+        AppError::Unauthorized("Unauthorized".to_string())
+    }
+
+    /// Rejects a registration, made by an already authenticated user
+    fn invalid(reason: String) -> AppError {
+        log::error!("WebAuthn registration rejected: {}", reason);
+        AppError::Validation("Invalid credential".to_string())
+    }
+
+    /**
+     * Decodes the client data and checks its type and origin
+     *
+     * @param client_data_json: &str base64url encoded
+     * @param kind: &str
+     * @param origin: &str
+     * @return Result<(ClientData, Vec<u8>), String> the client data and its raw bytes
+     */
+    fn check_client_data(
+        client_data_json: &str,
+        kind: &str,
+        origin: &str,
+    ) -> Result<(ClientData, Vec<u8>), String> {
+        let raw = URL_SAFE_NO_PAD
+            .decode(client_data_json)
+            .map_err(|e| e.to_string())?;
+        let client_data = serde_json::from_slice::<ClientData>(&raw).map_err(|e| e.to_string())?;
+        if client_data.kind != kind {
+            return Err(format!("Unexpected client data type {}", client_data.kind));
+        }
+        if client_data.origin != origin {
+            return Err(format!("Unexpected origin {}", client_data.origin));
+        }
+        Ok((client_data, raw))
+    }
+
+    /**
+     * Parses authenticator data and checks the relying party and the user flags
+     *
+     * @param data: &[u8]
+     * @param rp_id: &str
+     * @param user_verification: bool
+     * @return Result<AuthenticatorData, String>
+     */
+    fn check_authenticator_data(
+        data: &[u8],
+        rp_id: &str,
+        user_verification: bool,
+    ) -> Result<AuthenticatorData, String> {
+        if data.len() < 37 {
+            return Err("Authenticator data too short".to_string());
+        }
+        let mut parsed = AuthenticatorData {
+            rp_id_hash: data[..32].to_vec(),
+            flags: data[32],
+            sign_count: u32::from_be_bytes([data[33], data[34], data[35], data[36]]),
+            credential_id: None,
+            public_key: None,
+        };
+        if parsed.rp_id_hash != Sha256::digest(rp_id.as_bytes()).as_slice() {
+            return Err("Relying party mismatch".to_string());
+        }
+        if parsed.flags & FLAG_UP == 0 {
+            return Err("User not present".to_string());
+        }
+        if user_verification && parsed.flags & FLAG_UV == 0 {
+            return Err("User not verified".to_string());
+        }
+        if parsed.flags & FLAG_AT != 0 {
+            // AAGUID (16 bytes), credential id length (2 bytes), credential id, COSE key
+            let rest = data
+                .get(37 + 16..)
+                .ok_or("Attested credential data too short")?;
+            let length = match rest {
+                [high, low, ..] => u16::from_be_bytes([*high, *low]) as usize,
+                _ => return Err("Attested credential data too short".to_string()),
+            };
+            let credential_id = rest
+                .get(2..2 + length)
+                .ok_or("Attested credential data too short")?;
+            let (_, used) = CborValue::decode(&rest[2 + length..])?;
+            parsed.credential_id = Some(credential_id.to_vec());
+            parsed.public_key = Some(rest[2 + length..2 + length + used].to_vec());
+        }
+        Ok(parsed)
+    }
+
+    /**
+     * Finds the algorithm of a COSE key, rejecting unsupported ones and keys whose type or
+     * curve does not match the algorithm
+     *
+     * @param cose_key: &[u8]
+     * @return Result<i128, String>
+     */
+    fn algorithm_of(cose_key: &[u8]) -> Result<i128, String> {
+        let (key, _) = CborValue::decode(cose_key)?;
+        let param = |label: i128| key.get_int(label).and_then(|value| value.as_integer());
+        let algorithm = match param(3) {
+            Some(alg @ (COSE_ES256 | COSE_EDDSA | COSE_RS256)) => alg,
+            Some(alg) => return Err(format!("Unsupported COSE algorithm {}", alg)),
+            None => return Err("Missing COSE algorithm".to_string()),
+        };
+        let (key_type, curve) = match algorithm {
+            COSE_ES256 => (COSE_KTY_EC2, Some(COSE_CRV_P256)),
+            COSE_EDDSA => (COSE_KTY_OKP, Some(COSE_CRV_ED25519)),
+            _ => (COSE_KTY_RSA, None),
+        };
+        if param(1) != Some(key_type) {
+            return Err(format!("Wrong COSE key type for algorithm {}", algorithm));
+        }
+        if curve.is_some() && param(-1) != curve {
+            return Err(format!("Wrong COSE curve for algorithm {}", algorithm));
+        }
+        Ok(algorithm)
+    }
+
+    /**
+     * Verifies the attestation statement of a new credential. Only `none` and `packed` self
+     * attestation, signed with the credential key itself, are accepted, as attestation
+     * certificates would need the roots of every authenticator vendor.
+     *
+     * @param format: &str
+     * @param statement: &CborValue
+     * @param authenticator_data: &[u8]
+     * @param client_data_hash: &[u8]
+     * @param public_key: &[u8] COSE key of the credential
+     * @return Result<(), String>
+     */
+    fn check_attestation(
+        format: &str,
+        statement: &CborValue,
+        authenticator_data: &[u8],
+        client_data_hash: &[u8],
+        public_key: &[u8],
+    ) -> Result<(), String> {
+        let algorithm = Self::algorithm_of(public_key)?;
+        match format {
+            ATTESTATION_NONE => match statement {
+                CborValue::Map(entries) if entries.is_empty() => Ok(()),
+                _ => Err("Unexpected attestation statement".to_string()),
+            },
+            ATTESTATION_PACKED => {
+                if statement.get_text("x5c").is_some() {
+                    return Err("Attestation certificates are not supported".to_string());
+                }
+                if statement.get_text("alg").and_then(|alg| alg.as_integer()) != Some(algorithm) {
+                    return Err("Attestation algorithm mismatch".to_string());
+                }
+                let signature = statement
+                    .get_text("sig")
+                    .and_then(|sig| sig.as_bytes())
+                    .ok_or("Missing attestation signature")?;
+                let message = [authenticator_data, client_data_hash].concat();
+                Self::verify_signature(public_key, &message, signature)
+            }
+            format => Err(format!("Unsupported attestation format {}", format)),
+        }
+    }
+
+    /**
+     * Verifies a signature with a COSE public key
+     *
+     * @param cose_key: &[u8]
+     * @param message: &[u8]
+     * @param signature: &[u8]
+     * @return Result<(), String>
+     */
+    fn verify_signature(cose_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+        let (key, _) = CborValue::decode(cose_key)?;
+        let param = |label: i128| {
+            key.get_int(label)
+                .and_then(|value| value.as_bytes())
+                .ok_or(format!("Missing COSE key parameter {}", label))
+        };
+        match Self::algorithm_of(cose_key)? {
+            COSE_ES256 => {
+                let mut point = vec![0x04];
+                point.extend_from_slice(param(-2)?);
+                point.extend_from_slice(param(-3)?);
+                signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, point)
+                    .verify(message, signature)
+            }
+            COSE_EDDSA => signature::UnparsedPublicKey::new(&signature::ED25519, param(-2)?)
+                .verify(message, signature),
+            _ => signature::RsaPublicKeyComponents {
+                n: param(-1)?,
+                e: param(-2)?,
+            }
+            .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, signature),
+        }
+        .map_err(|_| "Invalid signature".to_string())
+    }
+
+    /**
+     * Checks the sign counter of an assertion. Authenticators without a counter always report
+     * zero; otherwise the counter must grow, or the credential may have been cloned.
+     *
+     * @param stored: i64
+     * @param received: u32
+     * @return bool
+     */
+    fn check_sign_count(stored: i64, received: u32) -> bool {
+        (stored == 0 && received == 0) || received as i64 > stored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, Ed25519KeyPair, KeyPair};
+
+    use super::*;
+
+    const RP_ID: &str = "localhost";
+    const ORIGIN: &str = "http://localhost:8080";
+
+    fn cbor_bytes(bytes: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![0x58, bytes.len() as u8];
+        encoded.extend_from_slice(bytes);
+        encoded
+    }
+
+    fn es256_key(public_key: &[u8]) -> Vec<u8> {
+        // {1: 2, 3: -7, -1: 1, -2: x, -3: y}
+        let mut key = vec![0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01, 0x21];
+        key.extend(cbor_bytes(&public_key[1..33]));
+        key.push(0x22);
+        key.extend(cbor_bytes(&public_key[33..65]));
+        key
+    }
+
+    fn eddsa_key(public_key: &[u8]) -> Vec<u8> {
+        // {1: 1, 3: -8, -1: 6, -2: x}
+        let mut key = vec![0xa4, 0x01, 0x01, 0x03, 0x27, 0x20, 0x06, 0x21];
+        key.extend(cbor_bytes(public_key));
+        key
+    }
+
+    fn authenticator_data(
+        flags: u8,
+        sign_count: u32,
+        credential: Option<(&[u8], &[u8])>,
+    ) -> Vec<u8> {
+        let mut data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        if let Some((credential_id, public_key)) = credential {
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&(credential_id.len() as u16).to_be_bytes());
+            data.extend_from_slice(credential_id);
+            data.extend_from_slice(public_key);
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_check_client_data() {
+        let client_data = URL_SAFE_NO_PAD.encode(format!(
+            r#"{{"type":"webauthn.get","challenge":"abc","origin":"{}","crossOrigin":false}}"#,
+            ORIGIN
+        ));
+        let (parsed, _) =
+            WebAuthnService::check_client_data(&client_data, "webauthn.get", ORIGIN).unwrap();
+        assert_eq!(parsed.challenge, "abc");
+        assert!(
+            WebAuthnService::check_client_data(&client_data, "webauthn.create", ORIGIN).is_err()
+        );
+        assert!(WebAuthnService::check_client_data(
+            &client_data,
+            "webauthn.get",
+            "https://evil.com"
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_authenticator_data() {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let cose_key = eddsa_key(key_pair.public_key().as_ref());
+
+        let data = authenticator_data(FLAG_UP | FLAG_AT, 7, Some((b"credential", &cose_key)));
+        let parsed = WebAuthnService::check_authenticator_data(&data, RP_ID, false).unwrap();
+        assert_eq!(parsed.sign_count, 7);
+        assert_eq!(parsed.credential_id.as_deref(), Some(&b"credential"[..]));
+        assert_eq!(parsed.public_key, Some(cose_key.clone()));
+        assert_eq!(WebAuthnService::algorithm_of(&cose_key), Ok(COSE_EDDSA));
+
+        assert!(WebAuthnService::check_authenticator_data(&data, "other.com", false).is_err());
+        assert!(WebAuthnService::check_authenticator_data(&data, RP_ID, true).is_err());
+        let data = authenticator_data(FLAG_UV, 0, None);
+        assert!(WebAuthnService::check_authenticator_data(&data, RP_ID, false).is_err());
+        assert!(WebAuthnService::check_authenticator_data(&data[..36], RP_ID, false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_algorithm_of() {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let cose_key = eddsa_key(key_pair.public_key().as_ref());
+        assert_eq!(WebAuthnService::algorithm_of(&cose_key), Ok(COSE_EDDSA));
+
+        // X25519 is not a signing curve
+        let mut wrong_curve = cose_key.clone();
+        wrong_curve[6] = 0x04;
+        assert!(WebAuthnService::algorithm_of(&wrong_curve).is_err());
+        let mut wrong_key_type = cose_key;
+        wrong_key_type[2] = 0x02;
+        assert!(WebAuthnService::algorithm_of(&wrong_key_type).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_attestation() {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let cose_key = eddsa_key(key_pair.public_key().as_ref());
+        let data = authenticator_data(FLAG_UP | FLAG_AT, 0, Some((b"credential", &cose_key)));
+        let client_data_hash = Sha256::digest(b"client data");
+        let check = |format: &str, statement: &CborValue| {
+            WebAuthnService::check_attestation(
+                format,
+                statement,
+                &data,
+                &client_data_hash,
+                &cose_key,
+            )
+        };
+        let packed = |alg: i128, signature: &[u8]| {
+            CborValue::Map(vec![
+                (CborValue::Text("alg".to_string()), CborValue::Integer(alg)),
+                (
+                    CborValue::Text("sig".to_string()),
+                    CborValue::Bytes(signature.to_vec()),
+                ),
+            ])
+        };
+
+        assert!(check(ATTESTATION_NONE, &CborValue::Map(Vec::new())).is_ok());
+        let signature = key_pair.sign(&[&data[..], &client_data_hash[..]].concat());
+        assert!(check(ATTESTATION_NONE, &packed(COSE_EDDSA, signature.as_ref())).is_err());
+        assert!(check(ATTESTATION_PACKED, &packed(COSE_EDDSA, signature.as_ref())).is_ok());
+        assert!(check(ATTESTATION_PACKED, &packed(COSE_ES256, signature.as_ref())).is_err());
+        let wrong_signature = key_pair.sign(&data);
+        assert!(check(
+            ATTESTATION_PACKED,
+            &packed(COSE_EDDSA, wrong_signature.as_ref())
+        )
+        .is_err());
+        assert!(check("fido-u2f", &CborValue::Map(Vec::new())).is_err());
+
+        let mut with_certificates = packed(COSE_EDDSA, signature.as_ref());
+        if let CborValue::Map(entries) = &mut with_certificates {
+            entries.push((
+                CborValue::Text("x5c".to_string()),
+                CborValue::Array(Vec::new()),
+            ));
+        }
+        assert!(check(ATTESTATION_PACKED, &with_certificates).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_signature() {
+        let rng = SystemRandom::new();
+        let message = authenticator_data(FLAG_UP | FLAG_UV, 1, None);
+
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key_pair = EcdsaKeyPair::from_pkcs8(
+            &signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+            pkcs8.as_ref(),
+            &rng,
+        )
+        .unwrap();
+        let cose_key = es256_key(key_pair.public_key().as_ref());
+        let signature = key_pair.sign(&rng, &message).unwrap();
+        assert!(WebAuthnService::verify_signature(&cose_key, &message, signature.as_ref()).is_ok());
+        assert!(
+            WebAuthnService::verify_signature(&cose_key, b"other", signature.as_ref()).is_err()
+        );
+
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let cose_key = eddsa_key(key_pair.public_key().as_ref());
+        let signature = key_pair.sign(&message);
+        assert!(WebAuthnService::verify_signature(&cose_key, &message, signature.as_ref()).is_ok());
+        assert!(
+            WebAuthnService::verify_signature(&cose_key, b"other", signature.as_ref()).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_sign_count() {
+        assert!(WebAuthnService::check_sign_count(0, 0));
+        assert!(WebAuthnService::check_sign_count(0, 1));
+        assert!(WebAuthnService::check_sign_count(5, 6));
+        assert!(!WebAuthnService::check_sign_count(5, 5));
+        assert!(!WebAuthnService::check_sign_count(5, 0));
+    }
+}
@@ -9,6 +9,8 @@ table! {
         is_admin -> Bool,
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        email_verified_at -> Nullable<Timestamp>,
+        password_changed_at -> Timestamp,
     }
 }
 table! {
@@ -58,10 +60,286 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(users, schools, students, classes, schedules,);
+table! {
+    refresh_tokens {
+        id -> Uuid,
+        user_id -> Uuid,
+        family_id -> Uuid,
+        token_hash -> VarChar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        scope -> Nullable<VarChar>,
+    }
+}
+
+table! {
+    revoked_tokens (jti) {
+        jti -> Uuid,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        revoked_at -> Timestamp,
+    }
+}
+
+table! {
+    user_token_revocations (user_id) {
+        user_id -> Uuid,
+        revoked_before -> Timestamp,
+    }
+}
+
+table! {
+    signing_keys (kid) {
+        kid -> VarChar,
+        algorithm -> VarChar,
+        private_key -> Text,
+        created_at -> Timestamp,
+        activated_at -> Nullable<Timestamp>,
+        retired_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    oauth_clients {
+        id -> Uuid,
+        name -> VarChar,
+        redirect_uris -> Array<Text>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        client_secret -> Nullable<VarChar>,
+        scopes -> Array<Text>,
+        tenant_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    authorization_codes {
+        id -> Uuid,
+        code_hash -> VarChar,
+        client_id -> Uuid,
+        user_id -> Uuid,
+        redirect_uri -> VarChar,
+        code_challenge -> VarChar,
+        code_challenge_method -> VarChar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        scope -> VarChar,
+        nonce -> Nullable<VarChar>,
+    }
+}
+
+table! {
+    roles {
+        id -> Uuid,
+        name -> VarChar,
+        description -> Nullable<VarChar>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        mfa_required -> Bool,
+    }
+}
+
+table! {
+    permissions {
+        id -> Uuid,
+        name -> VarChar,
+        description -> Nullable<VarChar>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    role_permissions (role_id, permission_id) {
+        role_id -> Uuid,
+        permission_id -> Uuid,
+    }
+}
+
+table! {
+    user_roles (user_id, role_id) {
+        user_id -> Uuid,
+        role_id -> Uuid,
+    }
+}
+
+table! {
+    totp_factors (user_id) {
+        user_id -> Uuid,
+        secret -> VarChar,
+        confirmed_at -> Nullable<Timestamp>,
+        last_used_step -> Nullable<BigInt>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    recovery_codes {
+        id -> Uuid,
+        user_id -> Uuid,
+        code_hash -> VarChar,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    mfa_challenges {
+        id -> Uuid,
+        token_hash -> VarChar,
+        user_id -> Uuid,
+        attempts -> Integer,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    webauthn_credentials {
+        id -> Uuid,
+        user_id -> Uuid,
+        credential_id -> VarChar,
+        public_key -> VarChar,
+        sign_count -> BigInt,
+        transports -> Nullable<VarChar>,
+        name -> Nullable<VarChar>,
+        created_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    webauthn_challenges {
+        id -> Uuid,
+        challenge_hash -> VarChar,
+        user_id -> Nullable<Uuid>,
+        ceremony -> VarChar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    password_reset_tokens {
+        id -> Uuid,
+        token_hash -> VarChar,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    mail_outbox {
+        id -> Uuid,
+        recipient -> VarChar,
+        subject -> VarChar,
+        text_body -> Text,
+        html_body -> Nullable<Text>,
+        attempts -> Integer,
+        last_error -> Nullable<VarChar>,
+        next_attempt_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+        failed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    email_verification_tokens {
+        id -> Uuid,
+        token_hash -> VarChar,
+        user_id -> Uuid,
+        email -> VarChar,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    login_lockouts {
+        id -> Uuid,
+        scope -> VarChar,
+        key -> VarChar,
+        failures -> Integer,
+        last_failure_at -> Timestamp,
+        locked_until -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    rate_limit_buckets (key) {
+        key -> VarChar,
+        tokens -> Double,
+        allowed -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    password_history {
+        id -> Uuid,
+        user_id -> Uuid,
+        password_hash -> VarChar,
+        created_at -> Timestamp,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(
+    users,
+    schools,
+    students,
+    classes,
+    schedules,
+    refresh_tokens,
+    revoked_tokens,
+    user_token_revocations,
+    signing_keys,
+    oauth_clients,
+    authorization_codes,
+    roles,
+    permissions,
+    role_permissions,
+    user_roles,
+    totp_factors,
+    recovery_codes,
+    mfa_challenges,
+    webauthn_credentials,
+    webauthn_challenges,
+    password_reset_tokens,
+    mail_outbox,
+    email_verification_tokens,
+    login_lockouts,
+    rate_limit_buckets,
+    password_history,
+);
 
 joinable!(students -> users (user_id));
 joinable!(students -> schools (school_id));
 joinable!(classes -> students (student_id));
 joinable!(schedules -> students (student_id));
 joinable!(schedules -> classes (class_id));
+joinable!(refresh_tokens -> users (user_id));
+joinable!(authorization_codes -> oauth_clients (client_id));
+joinable!(authorization_codes -> users (user_id));
+joinable!(role_permissions -> roles (role_id));
+joinable!(role_permissions -> permissions (permission_id));
+joinable!(user_roles -> users (user_id));
+joinable!(user_roles -> roles (role_id));
+joinable!(totp_factors -> users (user_id));
+joinable!(recovery_codes -> users (user_id));
+joinable!(mfa_challenges -> users (user_id));
+joinable!(webauthn_credentials -> users (user_id));
+joinable!(webauthn_challenges -> users (user_id));
+joinable!(password_reset_tokens -> users (user_id));
+joinable!(email_verification_tokens -> users (user_id));
+joinable!(password_history -> users (user_id));
+joinable!(oauth_clients -> schools (tenant_id));